@@ -1,11 +1,66 @@
+pub mod atl;
+pub mod bisimulation;
+pub mod cegar;
+pub mod ctl_star;
+pub mod emptiness;
+pub mod equivalence;
 pub mod explicit_state;
+pub mod ic3;
+pub mod k_induction;
+pub mod ltlf;
+pub mod markov_decision;
+pub mod mu_calculus;
+pub mod on_the_fly;
+pub mod parity;
+pub mod partial_order;
+pub mod probabilistic;
+pub mod stuttering;
+pub mod symmetry;
+pub mod timed;
+pub mod trace;
+pub mod vacuity;
 
-use crate::{ltl::LTLFormulae, model::kripke::Kripke};
+use crate::{
+    algorithm::{
+        ctl_star::satisfying_states,
+        explicit_state::explicit_state_checking,
+        on_the_fly::{check_ltl, LtlError},
+    },
+    ctl::CTLFormulae,
+    ctl_star::CTLStarFormulae,
+    ltl::LTLFormulae,
+    model::kripke::Kripke,
+    mu_calculus::{MuError, MuFormulae},
+};
 
-pub fn ltl_checking(model: &Kripke, formula: &LTLFormulae) -> Result<(), ()> {
-    todo!()
+/// Check that every path of `model` satisfies `formula`.
+///
+/// On failure, returns a path of state ids violating `formula`, or why it
+/// cannot be checked.
+pub fn ltl_checking(model: &Kripke, formula: &LTLFormulae) -> Result<(), LtlError<usize>> {
+    check_ltl(model, formula)
 }
 
-pub fn ctl_checking(model: &Kripke, formula: &LTLFormulae) -> Result<(), ()> {
-    todo!()
+/// Check whether the initial state of `model` satisfies `formula`.
+///
+/// The model is labelled with every subformula as a side effect. Fails as
+/// [`explicit_state_checking`] does.
+pub fn ctl_checking(model: &mut Kripke, formula: CTLFormulae) -> Result<bool, String> {
+    let id = explicit_state_checking(model, formula)?;
+    Ok(model
+        .get_state_with_label_as_set(id)
+        .contains(&model.initial_state))
+}
+
+/// Check whether the initial state of `model` satisfies `formula`, a path
+/// formula holding when all the paths satisfy it.
+pub fn ctl_star_checking(model: &Kripke, formula: &CTLStarFormulae) -> bool {
+    satisfying_states(model, formula).contains(&model.initial_state)
+}
+
+/// Check whether the initial state of `model` satisfies `formula`, which
+/// must have no free variable.
+pub fn mu_checking(model: &Kripke, formula: &MuFormulae) -> Result<bool, MuError> {
+    let states = mu_calculus::satisfying_states(model, formula)?;
+    Ok(states.contains(&model.initial_state))
 }
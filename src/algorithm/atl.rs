@@ -0,0 +1,228 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::{atl::ATLFormulae, model::concurrent_game::ConcurrentGame};
+
+/// Label the Kripke structure of `game` with `formula` and all of its
+/// subformulae. The atomic propositions which are not labels of the game
+/// never hold.
+///
+/// Returns the index of the label of `formula`, `usize::MAX` meaning true.
+/// Fails when a coalition has an agent which is not one of the game.
+pub fn atl_checking(game: &mut ConcurrentGame, formula: &ATLFormulae) -> Result<usize, String> {
+    if let Some(agent) = formula.agents().iter().find(|a| !game.agents.contains(a)) {
+        return Err(format!("agent {} is not defined", agent));
+    }
+    Ok(process(game, formula))
+}
+
+/// Process the formulae and return the index of the label
+///
+/// `usize::MAX` means true, which holds for all states
+fn process(game: &mut ConcurrentGame, f: &ATLFormulae) -> usize {
+    if let Some(id) = game.kripke.contains_label(&f.get_str()) {
+        return id;
+    }
+
+    match f {
+        ATLFormulae::True => usize::MAX,
+        ATLFormulae::Atomic(atomic) => game.kripke.get_label_id_or_add(atomic),
+        ATLFormulae::Not(g) => {
+            let not_id = game.kripke.get_label_id_or_add(&f.get_str());
+            let states = states_of(game, g);
+            for s in game.kripke.states.keys().cloned().collect::<Vec<usize>>() {
+                if !states.contains(&s) {
+                    game.kripke.add_state_for_label(not_id, s);
+                }
+            }
+            not_id
+        }
+        ATLFormulae::And(f1, f2) => {
+            let and_id = game.kripke.get_label_id_or_add(&f.get_str());
+            let s1 = states_of(game, f1);
+            let s2 = states_of(game, f2);
+            label(game, and_id, s1.intersection(&s2).cloned().collect());
+            and_id
+        }
+        ATLFormulae::Or(f1, f2) => {
+            let or_id = game.kripke.get_label_id_or_add(&f.get_str());
+            let s1 = states_of(game, f1);
+            let s2 = states_of(game, f2);
+            label(game, or_id, s1.union(&s2).cloned().collect());
+            or_id
+        }
+        ATLFormulae::Next(coalition, g) => {
+            let next_id = game.kripke.get_label_id_or_add(&f.get_str());
+            check_next(game, next_id, coalition, g);
+            next_id
+        }
+        ATLFormulae::Globally(coalition, g) => {
+            let globally_id = game.kripke.get_label_id_or_add(&f.get_str());
+            check_globally(game, globally_id, coalition, g);
+            globally_id
+        }
+        ATLFormulae::Until(coalition, f1, f2) => {
+            let until_id = game.kripke.get_label_id_or_add(&f.get_str());
+            check_until(game, until_id, coalition, f1, f2);
+            until_id
+        }
+    }
+}
+
+fn states_of(game: &mut ConcurrentGame, f: &ATLFormulae) -> HashSet<usize> {
+    let id = process(game, f);
+    game.kripke.get_state_with_label_as_set(id)
+}
+
+fn label(game: &mut ConcurrentGame, id: usize, states: Vec<usize>) {
+    for s in states {
+        game.kripke.add_state_for_label(id, s);
+    }
+}
+
+/// The indices of the agents of `coalition`, all defined as checked by
+/// [`atl_checking`].
+fn agents(game: &ConcurrentGame, coalition: &[String]) -> Vec<usize> {
+    coalition
+        .iter()
+        .filter_map(|agent| game.agents.iter().position(|a| a == agent))
+        .collect()
+}
+
+/// The states where the agents of `coalition` have moves forcing the next
+/// state into `states`, whatever the moves of the other agents.
+fn controllable_pre(
+    game: &ConcurrentGame,
+    coalition: &[usize],
+    states: &HashSet<usize>,
+) -> HashSet<usize> {
+    game.outcomes
+        .iter()
+        .filter(|(_, joint)| {
+            // whether the moves of the coalition force `states`
+            let mut forced = HashMap::new();
+            for (moves, to) in joint.iter() {
+                let own = coalition.iter().map(|a| moves[*a]).collect::<Vec<usize>>();
+                *forced.entry(own).or_insert(true) &= states.contains(to);
+            }
+            forced.values().any(|f| *f)
+        })
+        .map(|(s, _)| *s)
+        .collect()
+}
+
+fn check_next(game: &mut ConcurrentGame, next_id: usize, coalition: &[String], f: &ATLFormulae) {
+    let coalition = agents(game, coalition);
+    let states = states_of(game, f);
+    let pre = controllable_pre(game, &coalition, &states);
+    label(game, next_id, pre.into_iter().collect());
+}
+
+fn check_globally(
+    game: &mut ConcurrentGame,
+    globally_id: usize,
+    coalition: &[String],
+    f: &ATLFormulae,
+) {
+    let coalition = agents(game, coalition);
+    // greatest fixpoint, removing the states where f cannot be kept
+    let mut z = states_of(game, f);
+    loop {
+        let pre = controllable_pre(game, &coalition, &z);
+        let next = z.intersection(&pre).cloned().collect::<HashSet<usize>>();
+        if next.len() == z.len() {
+            break;
+        }
+        z = next;
+    }
+    label(game, globally_id, z.into_iter().collect());
+}
+
+fn check_until(
+    game: &mut ConcurrentGame,
+    until_id: usize,
+    coalition: &[String],
+    f1: &ATLFormulae,
+    f2: &ATLFormulae,
+) {
+    let coalition = agents(game, coalition);
+    let s1 = states_of(game, f1);
+    // least fixpoint, adding the f1 states where the coalition can force
+    // the next state in
+    let mut z = states_of(game, f2);
+    loop {
+        let pre = controllable_pre(game, &coalition, &z);
+        let next = s1.intersection(&pre).cloned().collect::<Vec<usize>>();
+        let size = z.len();
+        z.extend(next);
+        if z.len() == size {
+            break;
+        }
+    }
+    label(game, until_id, z.into_iter().collect());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::atl_parser::parse_atl;
+
+    #[test]
+    fn test_atl() {
+        // matching pennies, after which a can retry a lost round or stop
+        let data = r#"
+        {
+            "agents": ["a", "b"],
+            "states": [
+                {
+                    "id": 0, "name": "s0", "labels": [],
+                    "moves": [["l", "r"], ["l", "r"]],
+                    "transitions": [
+                        { "moves": ["l", "l"], "to": 1 },
+                        { "moves": ["r", "r"], "to": 1 },
+                        { "moves": ["l", "r"], "to": 2 },
+                        { "moves": ["r", "l"], "to": 2 }
+                    ]
+                },
+                { "id": 1, "name": "s1", "labels": ["win"] },
+                {
+                    "id": 2, "name": "s2", "labels": [],
+                    "moves": [["retry", "stop"], ["wait"]],
+                    "transitions": [
+                        { "moves": ["retry", "wait"], "to": 0 },
+                        { "moves": ["stop", "wait"], "to": 3 }
+                    ]
+                },
+                { "id": 3, "name": "s3", "labels": ["lose"] }
+            ],
+            "initial_state": 0
+        }"#;
+        let mut game = ConcurrentGame::from_json(data).unwrap();
+        let mut check = |formula: &str| {
+            let id = atl_checking(&mut game, &parse_atl(formula).unwrap()).unwrap();
+            let mut states = game
+                .kripke
+                .get_state_with_label_as_set(id)
+                .into_iter()
+                .collect::<Vec<usize>>();
+            states.sort();
+            states
+        };
+        assert_eq!(check("<<a>> X win"), vec![1]);
+        assert_eq!(check("<<a, b>> X win"), vec![0, 1]);
+        assert_eq!(check("<<>> X (win || !win)"), vec![0, 1, 2, 3]);
+        assert_eq!(check("<<a>> F win"), vec![1]);
+        assert_eq!(check("<<a, b>> F win"), vec![0, 1, 2]);
+        assert_eq!(check("<<a>> G !lose"), vec![0, 1, 2]);
+        assert_eq!(check("<<b>> G !lose"), vec![1]);
+        assert_eq!(check("<<b>> F lose"), vec![3]);
+        assert_eq!(check("<<a>> (!lose U win)"), vec![1]);
+        assert_eq!(check("<<a>> X <<a>> X <<a>> X lose"), vec![2, 3]);
+        assert_eq!(check("<<a, b>> F nothing"), Vec::<usize>::new());
+
+        let formula = parse_atl("<<a>> X <<c>> F win").unwrap();
+        assert_eq!(
+            atl_checking(&mut game, &formula),
+            Err("agent c is not defined".to_string())
+        );
+    }
+}
@@ -0,0 +1,394 @@
+use std::{
+    collections::{BTreeSet, HashMap, HashSet},
+    fmt::Display,
+};
+
+use crate::model::kripke::Kripke;
+
+/// A Kripke structure with its states numbered densely, in increasing order
+/// of their ids.
+pub(crate) struct Dense {
+    pub ids: Vec<usize>,
+    pub post: Vec<Vec<usize>>,
+    pub pre: Vec<Vec<usize>>,
+}
+
+impl Dense {
+    pub fn new(model: &Kripke) -> Dense {
+        let mut ids = model.states.keys().cloned().collect::<Vec<usize>>();
+        ids.sort();
+        let index = ids
+            .iter()
+            .enumerate()
+            .map(|(i, id)| (*id, i))
+            .collect::<HashMap<usize, usize>>();
+
+        let mut post = vec![Vec::new(); ids.len()];
+        let mut pre = vec![Vec::new(); ids.len()];
+        for (i, id) in ids.iter().enumerate() {
+            for to in model.transitions.get(id).into_iter().flatten() {
+                // transitions to undefined states are ignored
+                if let Some(j) = index.get(to) {
+                    post[i].push(*j);
+                    pre[*j].push(i);
+                }
+            }
+            post[i].sort();
+        }
+        for p in &mut pre {
+            p.sort();
+        }
+
+        Dense { ids, post, pre }
+    }
+
+    /// Group the states by the `labels` they satisfy, all labels if `None`.
+    pub fn label_classes(&self, model: &Kripke, labels: Option<&[&str]>) -> Vec<usize> {
+        let mut classes: HashMap<BTreeSet<&str>, usize> = HashMap::new();
+        self.ids
+            .iter()
+            .map(|id| {
+                let valuation = model
+                    .label_names_of(*id)
+                    .into_iter()
+                    .filter(|label| labels.is_none_or(|labels| labels.contains(label)))
+                    .collect::<BTreeSet<&str>>();
+                let next = classes.len();
+                *classes.entry(valuation).or_insert(next)
+            })
+            .collect()
+    }
+}
+
+/// Sizes of a model before and after a reduction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Reduction {
+    pub states_before: usize,
+    pub states_after: usize,
+    pub transitions_before: usize,
+    pub transitions_after: usize,
+}
+
+impl Display for Reduction {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            formatter,
+            "states: {} -> {}, transitions: {} -> {}",
+            self.states_before, self.states_after, self.transitions_before, self.transitions_after
+        )
+    }
+}
+
+/// A model quotiented by an equivalence on its states.
+#[derive(Debug)]
+pub struct Quotient {
+    pub model: Kripke,
+    /// The block, that is the state of the quotient, of each original state.
+    pub blocks: HashMap<usize, usize>,
+    pub reduction: Reduction,
+}
+
+/// The coarsest partition of the states of `model` that is a strong
+/// bisimulation with respect to `labels`, all labels if `None`.
+///
+/// This is the relational coarsest partition algorithm of Paige and Tarjan:
+/// the current partition is kept stable with respect to a coarser partition
+/// of compound blocks, and splitting a compound block uses the smaller half,
+/// so each state is used as a splitter `O(log n)` times.
+pub fn strong_bisimulation(model: &Kripke, labels: Option<&[&str]>) -> Vec<Vec<usize>> {
+    let dense = Dense::new(model);
+    let n = dense.ids.len();
+    if n == 0 {
+        return Vec::new();
+    }
+
+    // the initial partition groups equally labelled states, and separates
+    // deadlocks, which makes it stable with respect to the set of all states
+    let classes = dense.label_classes(model, labels);
+    let mut initial: HashMap<(usize, bool), Vec<usize>> = HashMap::new();
+    for (x, class) in classes.into_iter().enumerate() {
+        initial
+            .entry((class, dense.post[x].is_empty()))
+            .or_default()
+            .push(x);
+    }
+    let mut blocks = initial.into_values().collect::<Vec<Vec<usize>>>();
+    blocks.sort();
+
+    let mut block_of = vec![0; n];
+    for (b, members) in blocks.iter().enumerate() {
+        for x in members {
+            block_of[*x] = b;
+        }
+    }
+    let mut compound_of = vec![0; blocks.len()];
+    let mut compounds = vec![(0..blocks.len()).collect::<Vec<usize>>()];
+    // number of successors of each state in each compound block
+    let mut count: HashMap<(usize, usize), usize> = (0..n)
+        .filter(|x| !dense.post[*x].is_empty())
+        .map(|x| ((x, 0), dense.post[x].len()))
+        .collect();
+    let mut pending = if blocks.len() > 1 { vec![0] } else { vec![] };
+
+    while let Some(s) = pending.pop() {
+        if compounds[s].len() < 2 {
+            continue;
+        }
+
+        // take the smaller of two blocks of s out as a new compound block
+        let (first, second) = (compounds[s][0], compounds[s][1]);
+        let b = if blocks[first].len() <= blocks[second].len() {
+            first
+        } else {
+            second
+        };
+        compounds[s].retain(|c| *c != b);
+        let s_prime = compounds.len();
+        compounds.push(vec![b]);
+        compound_of[b] = s_prime;
+        if compounds[s].len() > 1 {
+            pending.push(s);
+        }
+
+        let mut in_b: HashMap<usize, usize> = HashMap::new();
+        for y in &blocks[b] {
+            for x in &dense.pre[*y] {
+                *in_b.entry(*x).or_default() += 1;
+            }
+        }
+
+        // split every block touched by pre(b) into the states without a
+        // successor in b, those with successors only in b among s, and the
+        // others
+        let mut touched: HashMap<usize, (Vec<usize>, Vec<usize>)> = HashMap::new();
+        let mut sorted = in_b.keys().cloned().collect::<Vec<usize>>();
+        sorted.sort();
+        for x in sorted {
+            let only_b = in_b[&x] == count[&(x, s)];
+            let (only, both) = touched.entry(block_of[x]).or_default();
+            if only_b {
+                only.push(x);
+            } else {
+                both.push(x);
+            }
+        }
+
+        for (x, c) in &in_b {
+            let remaining = count[&(*x, s)] - c;
+            if remaining == 0 {
+                count.remove(&(*x, s));
+            } else {
+                count.insert((*x, s), remaining);
+            }
+            count.insert((*x, s_prime), *c);
+        }
+
+        let mut touched = touched.into_iter().collect::<Vec<_>>();
+        touched.sort();
+        for (d, (only, both)) in touched {
+            let moved = only.len() + both.len();
+            let mut parts = Vec::new();
+            if moved < blocks[d].len() {
+                let stay = only
+                    .iter()
+                    .chain(&both)
+                    .cloned()
+                    .collect::<HashSet<usize>>();
+                blocks[d].retain(|x| !stay.contains(x));
+                parts.push(only);
+                parts.push(both);
+            } else if !only.is_empty() && !both.is_empty() {
+                blocks[d] = only;
+                parts.push(both);
+            }
+
+            for part in parts.into_iter().filter(|part| !part.is_empty()) {
+                let new = blocks.len();
+                for x in &part {
+                    block_of[*x] = new;
+                }
+                blocks.push(part);
+                let c = compound_of[d];
+                compound_of.push(c);
+                compounds[c].push(new);
+                if compounds[c].len() == 2 {
+                    pending.push(c);
+                }
+            }
+        }
+    }
+
+    let mut result = blocks
+        .into_iter()
+        .map(|members| {
+            let mut ids = members
+                .into_iter()
+                .map(|x| dense.ids[x])
+                .collect::<Vec<usize>>();
+            ids.sort();
+            ids
+        })
+        .collect::<Vec<Vec<usize>>>();
+    result.sort();
+    result
+}
+
+/// Build the quotient of `model` by the partition `blocks`, keeping the
+/// `labels`, all labels if `None`.
+///
+/// The states of the quotient are numbered in the order of `blocks`, and a
+/// block moves to another if one of its states does. Transitions within a
+/// block `b` only give a self-loop if `keep_loop(b)`.
+pub(crate) fn quotient(
+    model: &Kripke,
+    partition: &[Vec<usize>],
+    labels: Option<&[&str]>,
+    keep_loop: impl Fn(usize) -> bool,
+) -> Quotient {
+    let mut blocks = HashMap::new();
+    for (b, members) in partition.iter().enumerate() {
+        for id in members {
+            blocks.insert(*id, b);
+        }
+    }
+
+    let mut result = Kripke::new(blocks[&model.initial_state]);
+    // labels no state satisfies are still defined
+    let mut names = model
+        .labels
+        .values()
+        .filter(|label| labels.is_none_or(|labels| labels.contains(&label.as_str())))
+        .collect::<Vec<&String>>();
+    names.sort();
+    for name in names {
+        result.get_label_id_or_add(name);
+    }
+    for (b, members) in partition.iter().enumerate() {
+        let names = members
+            .iter()
+            .map(|id| model.states[id].as_str())
+            .collect::<Vec<&str>>();
+        let mut valuation = model
+            .label_names_of(members[0])
+            .into_iter()
+            .filter(|label| labels.is_none_or(|labels| labels.contains(label)))
+            .collect::<Vec<&str>>();
+        valuation.sort();
+        result.add_state(b, &format!("[{}]", names.join(", ")), &valuation);
+    }
+    for (from, to) in &model.transitions {
+        for t in to {
+            if let (Some(b), Some(c)) = (blocks.get(from), blocks.get(t)) {
+                if b != c || keep_loop(*b) {
+                    result.add_transition(*b, *c);
+                }
+            }
+        }
+    }
+
+    let reduction = Reduction {
+        states_before: model.states.len(),
+        states_after: result.states.len(),
+        transitions_before: model.transitions.values().map(|to| to.len()).sum(),
+        transitions_after: result.transitions.values().map(|to| to.len()).sum(),
+    };
+    Quotient {
+        model: result,
+        blocks,
+        reduction,
+    }
+}
+
+/// Minimize `model` by strong bisimulation over `labels`, all labels if
+/// `None`.
+///
+/// The quotient satisfies the same CTL* formulae over these labels.
+pub fn bisimulation_quotient(model: &Kripke, labels: Option<&[&str]>) -> Quotient {
+    let partition = strong_bisimulation(model, labels);
+    quotient(model, &partition, labels, |_| true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{algorithm::ctl_checking, parser::ctl_parser::parse_ctl};
+
+    /// Two copies of a request-grant cycle, one of them unrolled twice and
+    /// flagging an error, and a state that can only idle.
+    fn model() -> Kripke {
+        let data = r#"
+        {
+            "states": [
+                { "id": 0, "name": "s0", "labels": [], "transit_to": [1, 3, 7] },
+                { "id": 1, "name": "a1", "labels": ["req"], "transit_to": [2] },
+                { "id": 2, "name": "a2", "labels": ["ack"], "transit_to": [1] },
+                { "id": 3, "name": "b1", "labels": ["req"], "transit_to": [4] },
+                { "id": 4, "name": "b2", "labels": ["ack"], "transit_to": [5] },
+                { "id": 5, "name": "b3", "labels": ["req", "err"], "transit_to": [6] },
+                { "id": 6, "name": "b4", "labels": ["ack"], "transit_to": [3] },
+                { "id": 7, "name": "idle", "labels": [], "transit_to": [7] }
+            ],
+            "initial_state": 0
+        }"#;
+        Kripke::from_json(data).unwrap()
+    }
+
+    #[test]
+    fn test_strong_bisimulation() {
+        let k = model();
+        let partition = strong_bisimulation(&k, Some(&["req", "ack"]));
+        assert_eq!(
+            partition,
+            vec![vec![0], vec![1, 3, 5], vec![2, 4, 6], vec![7]]
+        );
+
+        let partition = strong_bisimulation(&k, None);
+        assert_eq!(partition.len(), 8);
+    }
+
+    #[test]
+    fn test_quotient() {
+        let k = model();
+        let q = bisimulation_quotient(&k, Some(&["req", "ack"]));
+        assert_eq!(q.model.states.len(), 4);
+        assert_eq!(q.blocks[&3], q.blocks[&5]);
+        assert_eq!(q.model.states[&q.blocks[&1]], "[a1, b1, b3]");
+        assert!(q.model.contains_label("err").is_none());
+        assert_eq!(
+            q.reduction,
+            Reduction {
+                states_before: 8,
+                states_after: 4,
+                transitions_before: 10,
+                transitions_after: 5,
+            }
+        );
+        assert_eq!(
+            q.reduction.to_string(),
+            "states: 8 -> 4, transitions: 10 -> 5"
+        );
+    }
+
+    #[test]
+    fn test_quotient_preserves_ctl() {
+        for formula in [
+            "AG (req -> AX ack)",
+            "EG (req || ack)",
+            "AF ack",
+            "E (!ack U req)",
+            "AG (ack -> EX req)",
+            "EF AG !req",
+            "AG EF req",
+        ] {
+            let mut k = model();
+            let mut q = bisimulation_quotient(&k, Some(&["req", "ack"])).model;
+            let formula = parse_ctl(formula).unwrap();
+            assert_eq!(
+                ctl_checking(&mut k, formula.clone()).unwrap(),
+                ctl_checking(&mut q, formula.clone()).unwrap(),
+                "{}",
+                formula
+            );
+        }
+    }
+}
@@ -0,0 +1,499 @@
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+use crate::{
+    algorithm::{
+        ltl_checking,
+        on_the_fly::{Lasso, LtlError},
+    },
+    expr::Expr,
+    ltl::LTLFormulae,
+    model::{
+        guarded::{GuardedProgram, ProgramError},
+        transition_system::TransitionSystem,
+    },
+};
+
+/// The abstraction of a program hiding some of its variables.
+///
+/// An abstract state gives the values of the visible variables, `None`
+/// standing for a hidden one. There is an abstract transition when some
+/// values of the hidden variables allow a concrete one, so the abstraction
+/// has all the paths of the program, and more.
+///
+/// As in the symbolic encoding, a command whose guard or updates cannot be
+/// evaluated counts as disabled, and so does a label which does not hold.
+pub struct Abstraction<'a> {
+    program: &'a GuardedProgram,
+    visible: BTreeSet<usize>,
+}
+
+impl<'a> Abstraction<'a> {
+    pub fn new(program: &'a GuardedProgram, visible: BTreeSet<usize>) -> Abstraction<'a> {
+        Abstraction { program, visible }
+    }
+
+    /// Whether the concrete `state` is represented by `abstraction`.
+    pub fn represents(&self, abstraction: &[Option<i64>], state: &[i64]) -> bool {
+        abstraction
+            .iter()
+            .zip(state)
+            .all(|(a, s)| a.is_none_or(|a| a == *s))
+    }
+
+    pub fn abstract_state(&self, state: &[i64]) -> Vec<Option<i64>> {
+        state
+            .iter()
+            .enumerate()
+            .map(|(v, value)| self.visible.contains(&v).then_some(*value))
+            .collect()
+    }
+
+    /// The concrete states represented by `state` that differ in the hidden
+    /// variables among `vars`, the other hidden variables being at their
+    /// minimum.
+    fn valuations(&self, state: &[Option<i64>], vars: &BTreeSet<usize>) -> Vec<Vec<i64>> {
+        let base = state
+            .iter()
+            .zip(&self.program.variables)
+            .map(|(value, v)| value.unwrap_or(v.min))
+            .collect::<Vec<i64>>();
+        let mut result = vec![base];
+        for var in vars.iter().filter(|v| state[**v].is_none()) {
+            let v = &self.program.variables[*var];
+            result = result
+                .into_iter()
+                .flat_map(|s| {
+                    (v.min..=v.max).map(move |value| {
+                        let mut s = s.clone();
+                        s[*var] = value;
+                        s
+                    })
+                })
+                .collect();
+        }
+        result
+    }
+
+    /// The abstract successors of `state` through `command`.
+    fn post(&self, state: &[Option<i64>], command: usize) -> Vec<Vec<Option<i64>>> {
+        let command_ref = &self.program.commands[command];
+        let mut result = self
+            .valuations(state, &self.program.reads(command))
+            .into_iter()
+            .filter(|s| command_ref.guard.holds(s) == Ok(true))
+            .filter_map(|s| {
+                let mut next = state.to_vec();
+                for (var, value) in &command_ref.updates {
+                    if self.visible.contains(var) {
+                        let value = value.eval(&s).ok()?;
+                        let v = &self.program.variables[*var];
+                        if value < v.min || value > v.max {
+                            return None;
+                        }
+                        next[*var] = Some(value);
+                    }
+                }
+                Some(next)
+            })
+            .collect::<Vec<_>>();
+        result.sort();
+        result.dedup();
+        result
+    }
+
+    /// Whether some state represented by `state` has no enabled command.
+    fn may_deadlock(&self, state: &[Option<i64>]) -> bool {
+        let guards = self.guard_variables();
+        self.valuations(state, &guards).iter().any(|s| {
+            self.program
+                .commands
+                .iter()
+                .all(|command| command.guard.holds(s) != Ok(true))
+        })
+    }
+
+    fn guard_variables(&self) -> BTreeSet<usize> {
+        self.program
+            .commands
+            .iter()
+            .flat_map(|command| command.guard.variables())
+            .collect()
+    }
+
+    /// The hidden variables that can explain the abstract step from `from`
+    /// to `to`.
+    fn explain(&self, from: &[Option<i64>], to: &[Option<i64>]) -> BTreeSet<usize> {
+        let mut vars = (0..self.program.commands.len())
+            .filter(|c| self.post(from, *c).iter().any(|next| next == to))
+            .flat_map(|c| self.program.reads(c))
+            .collect::<BTreeSet<usize>>();
+        if from == to && self.may_deadlock(from) {
+            vars.extend(self.guard_variables());
+        }
+        vars.retain(|v| !self.visible.contains(v));
+        if vars.is_empty() {
+            vars = (0..self.program.variables.len())
+                .filter(|v| !self.visible.contains(v))
+                .collect();
+        }
+        vars
+    }
+}
+
+impl TransitionSystem for Abstraction<'_> {
+    type State = Vec<Option<i64>>;
+
+    fn initial_states(&self) -> Vec<Vec<Option<i64>>> {
+        vec![self.abstract_state(&self.program.initial_state())]
+    }
+
+    fn successors(&self, state: &Vec<Option<i64>>) -> Vec<Vec<Option<i64>>> {
+        let mut next = (0..self.program.commands.len())
+            .flat_map(|c| self.post(state, c))
+            .collect::<Vec<_>>();
+        if self.may_deadlock(state) {
+            next.push(state.clone());
+        }
+        next.sort();
+        next.dedup();
+        next
+    }
+
+    /// The labels over visible variables holding in `state`.
+    fn labels(&self, state: &Vec<Option<i64>>) -> HashSet<String> {
+        let s = &self.valuations(state, &BTreeSet::new())[0];
+        self.program
+            .labels
+            .iter()
+            .filter(|(_, expr)| {
+                expr.variables().is_subset(&self.visible) && expr.holds(s) == Ok(true)
+            })
+            .map(|(name, _)| name.clone())
+            .collect()
+    }
+
+    fn state_name(&self, state: &Vec<Option<i64>>) -> String {
+        self.program
+            .variables
+            .iter()
+            .zip(state)
+            .filter_map(|(v, value)| value.map(|value| format!("{}={}", v.name, value)))
+            .collect::<Vec<String>>()
+            .join(", ")
+    }
+}
+
+/// What became of the abstract counterexample of an iteration.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Outcome {
+    /// The abstraction satisfies the formula.
+    Proved,
+    /// The counterexample is a real one.
+    Real,
+    /// The counterexample is spurious: no concrete path follows it past
+    /// `step`, so the `refined` variables are made visible.
+    Spurious { step: usize, refined: Vec<String> },
+}
+
+/// An iteration of the abstraction refinement loop.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Iteration {
+    pub visible: Vec<String>,
+    pub abstract_states: usize,
+    pub abstract_transitions: usize,
+    pub outcome: Outcome,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CegarReport {
+    pub iterations: Vec<Iteration>,
+}
+
+/// Check an LTL formula on `program` by counterexample-guided abstraction
+/// refinement.
+///
+/// The abstraction first only shows the variables of the labels of
+/// `formula`. Each abstract counterexample is simulated on the program, and
+/// when it is spurious, the hidden variables read by the commands taking the
+/// step that cannot be followed are made visible. Fails when a command of a
+/// simulated path cannot be executed, or when no variable is left to refine
+/// a spurious counterexample.
+pub fn cegar(
+    program: &GuardedProgram,
+    formula: &LTLFormulae,
+) -> (Result<(), LtlError<Vec<i64>>>, CegarReport) {
+    let mut visible = formula
+        .atomics()
+        .iter()
+        .filter_map(|label| program.label(label))
+        .flat_map(Expr::variables)
+        .collect::<BTreeSet<usize>>();
+    let mut report = CegarReport::default();
+
+    loop {
+        let abstraction = Abstraction::new(program, visible.clone());
+        let (kripke, states) = abstraction.to_kripke_with_states();
+        let mut iteration = Iteration {
+            visible: visible
+                .iter()
+                .map(|v| program.variables[*v].name.clone())
+                .collect(),
+            abstract_states: kripke.states.len(),
+            abstract_transitions: kripke.transitions.values().map(|to| to.len()).sum(),
+            outcome: Outcome::Proved,
+        };
+
+        let lasso = match ltl_checking(&kripke, formula) {
+            Ok(()) => {
+                report.iterations.push(iteration);
+                return (Ok(()), report);
+            }
+            Err(LtlError::Counterexample(lasso)) => Lasso {
+                prefix: lasso.prefix.iter().map(|id| states[*id].clone()).collect(),
+                cycle: lasso.cycle.iter().map(|id| states[*id].clone()).collect(),
+            },
+            Err(LtlError::Unsupported(reason)) => {
+                return (Err(LtlError::Unsupported(reason)), report);
+            }
+        };
+
+        match concretize(program, &abstraction, &lasso) {
+            Err(error) => return (Err(LtlError::Unsupported(error.to_string())), report),
+            Ok(Ok(concrete)) => {
+                iteration.outcome = Outcome::Real;
+                report.iterations.push(iteration);
+                return (Err(LtlError::Counterexample(concrete)), report);
+            }
+            Ok(Err(step)) => {
+                let at = |i: usize| abstract_at(&lasso, i);
+                let refined = abstraction.explain(at(step), at(step + 1));
+                if refined.is_empty() {
+                    let reason = format!(
+                        "the spurious counterexample past step {} cannot be refined",
+                        step
+                    );
+                    return (Err(LtlError::Unsupported(reason)), report);
+                }
+                iteration.outcome = Outcome::Spurious {
+                    step,
+                    refined: refined
+                        .iter()
+                        .map(|v| program.variables[*v].name.clone())
+                        .collect(),
+                };
+                report.iterations.push(iteration);
+                visible.extend(refined);
+            }
+        }
+    }
+}
+
+/// The `i`-th state of the unrolled `lasso`.
+fn abstract_at<S>(lasso: &Lasso<S>, i: usize) -> &S {
+    match lasso.prefix.get(i) {
+        Some(state) => state,
+        None => &lasso.cycle[(i - lasso.prefix.len()) % lasso.cycle.len()],
+    }
+}
+
+/// Find a concrete lasso following the abstract one, or the step past which
+/// no concrete path follows it. Fails when a command cannot be executed.
+///
+/// The sets of concrete states at each position of the unrolled abstract
+/// lasso are computed until one is empty, or the set at the start of the
+/// cycle repeats, in which case every state of the repeated set can go
+/// around the cycle, backwards, forever.
+fn concretize(
+    program: &GuardedProgram,
+    abstraction: &Abstraction,
+    lasso: &Lasso<Vec<Option<i64>>>,
+) -> Result<Result<Lasso<Vec<i64>>, usize>, ProgramError> {
+    let start = lasso.prefix.len();
+    let period = lasso.cycle.len();
+    // the parent of each state at each position
+    let mut layers: Vec<HashMap<Vec<i64>, Vec<i64>>> =
+        vec![HashMap::from([(program.initial_state(), Vec::new())])];
+    let mut starts: HashMap<Vec<Vec<i64>>, usize> = HashMap::new();
+
+    for i in 0.. {
+        if i >= start && (i - start).is_multiple_of(period) {
+            let mut set = layers[i].keys().cloned().collect::<Vec<_>>();
+            set.sort();
+            if let Some(first) = starts.get(&set) {
+                return Ok(Ok(extract(&layers, *first, i)));
+            }
+            starts.insert(set, i);
+        }
+
+        let target = abstract_at(lasso, i + 1);
+        let mut next = HashMap::new();
+        for state in layers[i].keys() {
+            for successor in program.next_states(state)? {
+                if abstraction.represents(target, &successor) {
+                    next.entry(successor).or_insert_with(|| state.clone());
+                }
+            }
+        }
+        if next.is_empty() {
+            return Ok(Err(i));
+        }
+        layers.push(next);
+    }
+    unreachable!()
+}
+
+/// A lasso through the states of `layers`, where the states at positions
+/// `first` and `last` are the same.
+fn extract(layers: &[HashMap<Vec<i64>, Vec<i64>>], first: usize, last: usize) -> Lasso<Vec<i64>> {
+    let back = |mut state: Vec<i64>, from: usize, to: usize| {
+        let mut path = vec![state.clone()];
+        for i in (to + 1..=from).rev() {
+            state = layers[i][&state].clone();
+            path.push(state.clone());
+        }
+        path.reverse();
+        path
+    };
+
+    // going back from last to first maps the set to itself, so it loops
+    let mut state = layers[last].keys().min().unwrap().clone();
+    let mut seen = vec![state.clone()];
+    let mut segments = Vec::new();
+    loop {
+        let segment = back(state, last, first);
+        state = segment[0].clone();
+        segments.push(segment);
+        if let Some(i) = seen.iter().position(|s| *s == state) {
+            // the segments from i on lead backwards from seen[i] to itself
+            let mut cycle = Vec::new();
+            for segment in segments[i..].iter().rev() {
+                cycle.extend(segment[..segment.len() - 1].iter().cloned());
+            }
+            let mut prefix = back(state, first, 0);
+            prefix.pop();
+            return Lasso { prefix, cycle };
+        }
+        seen.push(state.clone());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{algorithm::on_the_fly::check_ltl, parser::ltl_parser::parse_ltl};
+
+    /// Two processes taking turns in a critical section, and a third one
+    /// counting for nothing.
+    const TURNS: &str = r#"
+    {
+        "variables": [
+            { "name": "pc", "min": 0, "max": 1, "init": 0 },
+            { "name": "qc", "min": 0, "max": 1, "init": 0 },
+            { "name": "turn", "min": 0, "max": 1, "init": 0 },
+            { "name": "junk", "min": 0, "max": 4, "init": 0 }
+        ],
+        "processes": [
+            {
+                "name": "p",
+                "commands": [
+                    { "name": "enter", "guard": "pc == 0 && turn == 0", "updates": { "pc": "1" } },
+                    { "name": "leave", "guard": "pc == 1", "updates": { "pc": "0", "turn": "1" } }
+                ]
+            },
+            {
+                "name": "q",
+                "commands": [
+                    { "name": "enter", "guard": "qc == 0 && turn == 1", "updates": { "qc": "1" } },
+                    { "name": "leave", "guard": "qc == 1", "updates": { "qc": "0", "turn": "0" } }
+                ]
+            },
+            {
+                "name": "r",
+                "commands": [ { "guard": "true", "updates": { "junk": "(junk + 1) % 5" } } ]
+            }
+        ],
+        "labels": { "both": "pc == 1 && qc == 1", "pcrit": "pc == 1" }
+    }"#;
+
+    /// Check that `lasso` is an infinite path of `program`.
+    fn is_path(program: &GuardedProgram, lasso: &Lasso<Vec<i64>>) -> bool {
+        let path = lasso
+            .prefix
+            .iter()
+            .chain(&lasso.cycle)
+            .chain(&lasso.cycle[..1])
+            .collect::<Vec<_>>();
+        *path[0] == program.initial_state()
+            && path
+                .windows(2)
+                .all(|w| program.successors(w[0]).contains(w[1]))
+    }
+
+    #[test]
+    fn test_cegar_proves() {
+        let program = GuardedProgram::from_json(TURNS).unwrap();
+        let (result, report) = cegar(&program, &parse_ltl("G !both").unwrap());
+        assert!(result.is_ok());
+        assert_eq!(report.iterations.len(), 2);
+        assert!(matches!(
+            &report.iterations[0].outcome,
+            Outcome::Spurious { refined, .. } if refined == &vec!["turn".to_string()]
+        ));
+        assert_eq!(report.iterations[1].visible, vec!["pc", "qc", "turn"]);
+        assert_eq!(report.iterations[1].outcome, Outcome::Proved);
+        // junk stays hidden
+        assert!(report.iterations[1].abstract_states < program.to_kripke().states.len());
+    }
+
+    #[test]
+    fn test_cegar_real_counterexamples() {
+        let program = GuardedProgram::from_json(TURNS).unwrap();
+        for formula in ["G !pcrit", "G F pcrit", "F G !pcrit", "F both"] {
+            let formula = parse_ltl(formula).unwrap();
+            let (result, report) = cegar(&program, &formula);
+            assert!(check_ltl(&program, &formula).is_err());
+            let lasso = result.unwrap_err().counterexample().unwrap();
+            assert!(is_path(&program, &lasso), "{}", formula);
+            assert_eq!(report.iterations.last().unwrap().outcome, Outcome::Real);
+        }
+    }
+
+    #[test]
+    fn test_cegar_verdicts() {
+        let program = GuardedProgram::from_json(TURNS).unwrap();
+        for formula in [
+            "G !both",
+            "G (pcrit -> F !pcrit)",
+            "G (both -> F pcrit)",
+            "G !pcrit",
+        ] {
+            let formula = parse_ltl(formula).unwrap();
+            let (result, _) = cegar(&program, &formula);
+            assert_eq!(
+                result.is_ok(),
+                check_ltl(&program, &formula).is_ok(),
+                "{}",
+                formula
+            );
+        }
+    }
+
+    #[test]
+    fn test_cegar_execution_error() {
+        // the hidden divisor is 0, while the abstraction steps with 1
+        let data = r#"
+        {
+            "variables": [
+                { "name": "x", "min": 0, "max": 1, "init": 0 },
+                { "name": "y", "min": 0, "max": 1, "init": 0 }
+            ],
+            "processes": [
+                { "name": "p", "commands": [ { "guard": "true", "updates": { "x": "1 / y" } } ] }
+            ],
+            "labels": { "zero": "x == 0" }
+        }"#;
+        let program = GuardedProgram::from_json(data).unwrap();
+        let (result, _) = cegar(&program, &parse_ltl("G zero").unwrap());
+        assert!(matches!(result, Err(LtlError::Unsupported(_))));
+    }
+}
@@ -0,0 +1,242 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    algorithm::on_the_fly::{check_ltl, Lasso, LtlError},
+    ctl_star::CTLStarFormulae,
+    ltl::LTLFormulae,
+    model::{kripke::Kripke, transition_system::TransitionSystem},
+};
+
+/// A Kripke structure started from another state, with the maximal state
+/// subformulae of a path formula as extra labels.
+struct Labelled<'a> {
+    model: &'a Kripke,
+    initial: usize,
+    /// key: label, value: states
+    extra: &'a HashMap<String, HashSet<usize>>,
+}
+
+impl TransitionSystem for Labelled<'_> {
+    type State = usize;
+
+    fn initial_states(&self) -> Vec<usize> {
+        vec![self.initial]
+    }
+
+    fn successors(&self, state: &usize) -> Vec<usize> {
+        self.model.successors(state)
+    }
+
+    fn labels(&self, state: &usize) -> HashSet<String> {
+        let mut labels = self.model.labels(state);
+        for (label, states) in self.extra {
+            if states.contains(state) {
+                labels.insert(label.clone());
+            }
+        }
+        labels
+    }
+}
+
+/// Translate the path formula `formula` to LTL, replacing its maximal state
+/// subformulae by labels added to `extra`.
+fn path_formula(
+    model: &Kripke,
+    formula: &CTLStarFormulae,
+    extra: &mut HashMap<String, HashSet<usize>>,
+) -> LTLFormulae {
+    let mut unary = |f: &CTLStarFormulae| Box::new(path_formula(model, f, extra));
+    match formula {
+        CTLStarFormulae::True => LTLFormulae::True,
+        CTLStarFormulae::Atomic(a) => LTLFormulae::Atomic(a.clone()),
+        f if f.is_state_formula() => {
+            // brackets cannot occur in the labels of the model
+            let label = format!("[{}]", f);
+            if !extra.contains_key(&label) {
+                let states = satisfying_states(model, f);
+                extra.insert(label.clone(), states);
+            }
+            LTLFormulae::Atomic(label)
+        }
+        CTLStarFormulae::Not(f) => LTLFormulae::Not(unary(f)),
+        CTLStarFormulae::And(f, g) => {
+            let f = unary(f);
+            LTLFormulae::And(f, unary(g))
+        }
+        // f || g = !(!f && !g)
+        CTLStarFormulae::Or(f, g) => {
+            let f = unary(f);
+            let g = unary(g);
+            LTLFormulae::Not(Box::new(LTLFormulae::And(
+                Box::new(LTLFormulae::Not(f)),
+                Box::new(LTLFormulae::Not(g)),
+            )))
+        }
+        CTLStarFormulae::Next(f) => LTLFormulae::Next(unary(f)),
+        CTLStarFormulae::Finally(f) => LTLFormulae::Until(Box::new(LTLFormulae::True), unary(f)),
+        CTLStarFormulae::Globally(f) => LTLFormulae::Not(Box::new(LTLFormulae::Until(
+            Box::new(LTLFormulae::True),
+            Box::new(LTLFormulae::Not(unary(f))),
+        ))),
+        CTLStarFormulae::Until(f, g) => {
+            let f = unary(f);
+            LTLFormulae::Until(f, unary(g))
+        }
+        // f R g = !(!f U !g)
+        CTLStarFormulae::Release(f, g) => {
+            let f = unary(f);
+            let g = unary(g);
+            LTLFormulae::Not(Box::new(LTLFormulae::Until(
+                Box::new(LTLFormulae::Not(f)),
+                Box::new(LTLFormulae::Not(g)),
+            )))
+        }
+        CTLStarFormulae::All(_) | CTLStarFormulae::Exist(_) => unreachable!(),
+    }
+}
+
+/// The states of `model` satisfying `formula`.
+///
+/// A path formula holds in a state when every path from it satisfies the
+/// formula, as in LTL. The atomic propositions which are not labels of the
+/// model never hold.
+pub fn satisfying_states(model: &Kripke, formula: &CTLStarFormulae) -> HashSet<usize> {
+    let all = || model.states.keys().cloned().collect::<HashSet<usize>>();
+    match formula {
+        CTLStarFormulae::True => all(),
+        CTLStarFormulae::Atomic(a) => match model.contains_label(a) {
+            Some(id) => model.get_state_with_label_as_set(id),
+            None => HashSet::new(),
+        },
+        CTLStarFormulae::Not(f) if f.is_state_formula() => {
+            let states = satisfying_states(model, f);
+            all().difference(&states).cloned().collect()
+        }
+        CTLStarFormulae::And(f, g) if formula.is_state_formula() => {
+            let states = satisfying_states(model, f);
+            states
+                .intersection(&satisfying_states(model, g))
+                .cloned()
+                .collect()
+        }
+        CTLStarFormulae::Or(f, g) if formula.is_state_formula() => {
+            let mut states = satisfying_states(model, f);
+            states.extend(satisfying_states(model, g));
+            states
+        }
+        // the states from which a path satisfies the path formula, with one
+        // check of LTL per state
+        CTLStarFormulae::Exist(path) => {
+            let mut extra = HashMap::new();
+            let negation = LTLFormulae::Not(Box::new(path_formula(model, path, &mut extra)));
+            all()
+                .into_iter()
+                .filter(|state| {
+                    let system = Labelled {
+                        model,
+                        initial: *state,
+                        extra: &extra,
+                    };
+                    // path formulae have no past operator, so they can be checked
+                    matches!(
+                        check_ltl(&system, &negation),
+                        Err(LtlError::Counterexample(_))
+                    )
+                })
+                .collect()
+        }
+        // A f = !E !f
+        CTLStarFormulae::All(path) => {
+            let negation = CTLStarFormulae::Not(path.clone());
+            let states = satisfying_states(model, &CTLStarFormulae::Exist(Box::new(negation)));
+            all().difference(&states).cloned().collect()
+        }
+        path => satisfying_states(model, &CTLStarFormulae::All(Box::new(path.clone()))),
+    }
+}
+
+/// A path from the initial state of `model` satisfying the path formula
+/// `path`, witnessing `E path`.
+pub fn witness(model: &Kripke, path: &CTLStarFormulae) -> Option<Lasso<usize>> {
+    let mut extra = HashMap::new();
+    let negation = LTLFormulae::Not(Box::new(path_formula(model, path, &mut extra)));
+    let system = Labelled {
+        model,
+        initial: model.initial_state,
+        extra: &extra,
+    };
+    check_ltl(&system, &negation).err()?.counterexample()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        algorithm::{ctl_star_checking, explicit_state::explicit_state_checking},
+        parser::{ctl_parser::parse_ctl, ctl_star_parser::parse_ctl_star, ltl_parser::parse_ltl},
+    };
+
+    /// `p` and `q` alternate, until staying in `q` or in the sink.
+    const MODEL: &str = r#"
+    {
+        "states": [
+            { "id": 0, "name": "s0", "labels": ["p"], "transit_to": [1, 2] },
+            { "id": 1, "name": "s1", "labels": ["q"], "transit_to": [0, 1] },
+            { "id": 2, "name": "s2", "labels": [], "transit_to": [2] }
+        ],
+        "initial_state": 0
+    }"#;
+
+    fn check(model: &Kripke, formula: &str) -> bool {
+        ctl_star_checking(model, &parse_ctl_star(formula).unwrap())
+    }
+
+    #[test]
+    fn test_ctl_star() {
+        let model = Kripke::from_json(MODEL).unwrap();
+        assert!(!check(&model, "E (G F p && F G q)"));
+        assert!(check(&model, "E (G F p && G F q)"));
+        assert!(check(&model, "E (X q && F G !p)"));
+        assert!(check(&model, "A (G F p -> G F q)"));
+        assert!(!check(&model, "A (F G q || F G !p)"));
+        // state subformulae under temporal operators
+        assert!(check(&model, "A G (p -> E X (q && E G q))"));
+        assert!(check(&model, "E (p U (q && A X E F G !p))"));
+        assert!(!check(&model, "E F (A G F p)"));
+        // a path formula holds on all paths
+        assert!(!check(&model, "F q"));
+        assert!(check(&model, "X (q || G !p)"));
+
+        let formula = parse_ctl_star("E (X X p) && A G !r").unwrap();
+        assert_eq!(satisfying_states(&model, &formula), HashSet::from([0, 1]));
+
+        let lasso = witness(&model, &parse_ctl_star("G F p && G F q").unwrap()).unwrap();
+        let states = lasso.cycle.iter().collect::<HashSet<&usize>>();
+        assert_eq!(states, HashSet::from([&0, &1]));
+        assert!(witness(&model, &parse_ctl_star("G p").unwrap()).is_none());
+
+        let ltl = |formula: &str| CTLStarFormulae::try_from(parse_ltl(formula).unwrap());
+        assert!(!ctl_star_checking(&model, &ltl("F q").unwrap()));
+        assert!(ctl_star_checking(&model, &ltl("X (q || G !p)").unwrap()));
+        assert!(ltl("G (q -> Y p)").is_err());
+    }
+
+    #[test]
+    fn test_ctl_agrees() {
+        for formula in [
+            "EG q",
+            "AF (q || !p)",
+            "E (p U q)",
+            "A (p U q)",
+            "AG EF p",
+            "EX AX q",
+            "A (!q R !p)",
+        ] {
+            let mut model = Kripke::from_json(MODEL).unwrap();
+            let id = explicit_state_checking(&mut model, parse_ctl(formula).unwrap()).unwrap();
+            let expected = model.get_state_with_label_as_set(id);
+            let formula = parse_ctl(formula).unwrap().into();
+            assert_eq!(satisfying_states(&model, &formula), expected, "{}", formula);
+        }
+    }
+}
@@ -0,0 +1,417 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use crate::{algorithm::on_the_fly::Lasso, model::transition_system::TransitionSystem};
+
+/// Emptiness check used to search for an accepting lasso.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Emptiness {
+    /// Nested depth-first search, on the degeneralized automaton.
+    #[default]
+    NestedDfs,
+    /// Couvreur's SCC-based search, on the generalized automaton.
+    Couvreur,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Color {
+    /// On the stack of the outer search.
+    Cyan,
+    /// Completely explored by the outer search.
+    Blue,
+}
+
+/// A frame of an explicit depth-first search stack.
+struct Frame<S> {
+    state: S,
+    successors: Vec<S>,
+    next: usize,
+}
+
+impl<S> Frame<S> {
+    fn new<T: TransitionSystem<State = S>>(system: &T, state: S) -> Frame<S> {
+        let successors = system.successors(&state);
+        Frame {
+            state,
+            successors,
+            next: 0,
+        }
+    }
+}
+
+/// Search for a lasso whose cycle goes through an accepting state with the
+/// nested depth-first search of Courcoubetis, Vardi, Wolper and Yannakakis,
+/// in the variant of Schwoon and Esparza.
+///
+/// The search stops as soon as a cycle is closed, which may happen before
+/// the outer search backtracks from the accepting state.
+pub fn nested_dfs<T, F>(system: &T, accepting: F) -> Option<Lasso<T::State>>
+where
+    T: TransitionSystem,
+    F: Fn(&T::State) -> bool,
+{
+    let mut colors: HashMap<T::State, Color> = HashMap::new();
+    let mut red: HashSet<T::State> = HashSet::new();
+
+    for init in system.initial_states() {
+        if colors.contains_key(&init) {
+            continue;
+        }
+        colors.insert(init.clone(), Color::Cyan);
+        let mut blue = vec![Frame::new(system, init)];
+
+        while let Some(frame) = blue.last_mut() {
+            if let Some(t) = frame.successors.get(frame.next).cloned() {
+                frame.next += 1;
+                let s = &frame.state;
+                match colors.get(&t) {
+                    Some(Color::Cyan) if accepting(s) || accepting(&t) => {
+                        let stack = blue.iter().map(|f| f.state.clone()).collect();
+                        return Some(close_cycle(stack, &t, Vec::new()));
+                    }
+                    None => {
+                        colors.insert(t.clone(), Color::Cyan);
+                        blue.push(Frame::new(system, t));
+                    }
+                    _ => {}
+                }
+                continue;
+            }
+
+            let s = frame.state.clone();
+            if accepting(&s) {
+                if let Some((target, path)) = red_dfs(system, &s, &colors, &mut red) {
+                    let stack = blue.iter().map(|f| f.state.clone()).collect();
+                    return Some(close_cycle(stack, &target, path));
+                }
+            }
+            colors.insert(s, Color::Blue);
+            blue.pop();
+        }
+    }
+
+    None
+}
+
+/// The inner search from the accepting state `seed`, looking for a state on
+/// the stack of the outer search.
+///
+/// Returns that state and the path leading to it from `seed`, exclusive on
+/// both ends.
+fn red_dfs<T: TransitionSystem>(
+    system: &T,
+    seed: &T::State,
+    colors: &HashMap<T::State, Color>,
+    red: &mut HashSet<T::State>,
+) -> Option<(T::State, Vec<T::State>)> {
+    red.insert(seed.clone());
+    let mut stack = vec![Frame::new(system, seed.clone())];
+
+    while let Some(frame) = stack.last_mut() {
+        let Some(t) = frame.successors.get(frame.next).cloned() else {
+            stack.pop();
+            continue;
+        };
+        frame.next += 1;
+        match colors.get(&t) {
+            Some(Color::Cyan) => {
+                let path = stack.iter().skip(1).map(|f| f.state.clone()).collect();
+                return Some((t, path));
+            }
+            Some(Color::Blue) if !red.contains(&t) => {
+                red.insert(t.clone());
+                stack.push(Frame::new(system, t));
+            }
+            _ => {}
+        }
+    }
+
+    None
+}
+
+/// Build the lasso made of the outer stack `stack` followed by `path`, which
+/// leads back to `target` on the stack.
+fn close_cycle<S: Clone + Eq>(mut stack: Vec<S>, target: &S, path: Vec<S>) -> Lasso<S> {
+    let start = stack.iter().position(|s| s == target).unwrap();
+    let mut cycle = stack.split_off(start);
+    cycle.extend(path);
+    Lasso {
+        prefix: stack,
+        cycle,
+    }
+}
+
+/// Search for a lasso whose cycle visits each of the `sets` acceptance sets,
+/// with the on-the-fly SCC algorithm of Couvreur.
+///
+/// `accepting(s, i)` tells whether `s` is in acceptance set `i`. The search
+/// stops as soon as a strongly connected component containing all the sets
+/// is closed.
+pub fn couvreur<T, F>(system: &T, sets: usize, accepting: F) -> Option<Lasso<T::State>>
+where
+    T: TransitionSystem,
+    F: Fn(&T::State, usize) -> bool,
+{
+    let acceptance_of =
+        |s: &T::State| -> Vec<bool> { (0..sets).map(|i| accepting(s, i)).collect() };
+
+    // dfs number of each visited state, 0 once its component is complete
+    let mut numbers: HashMap<T::State, usize> = HashMap::new();
+    let mut count = 0;
+    // roots of the components on the stack with their acceptance sets
+    let mut roots: Vec<(usize, Vec<bool>)> = Vec::new();
+    // states of the components on the stack, in visiting order
+    let mut active: Vec<T::State> = Vec::new();
+
+    for init in system.initial_states() {
+        if numbers.contains_key(&init) {
+            continue;
+        }
+        count += 1;
+        numbers.insert(init.clone(), count);
+        roots.push((count, acceptance_of(&init)));
+        active.push(init.clone());
+        let mut stack = vec![Frame::new(system, init)];
+
+        while let Some(frame) = stack.last_mut() {
+            if let Some(t) = frame.successors.get(frame.next).cloned() {
+                frame.next += 1;
+                match numbers.get(&t) {
+                    None => {
+                        count += 1;
+                        numbers.insert(t.clone(), count);
+                        roots.push((count, acceptance_of(&t)));
+                        active.push(t.clone());
+                        stack.push(Frame::new(system, t));
+                    }
+                    Some(0) => {}
+                    Some(number) => {
+                        // t is in a component on the stack, merge up to it
+                        let mut acceptance = vec![false; sets];
+                        let root = loop {
+                            let (root, a) = roots.pop().unwrap();
+                            acceptance.iter_mut().zip(a).for_each(|(x, y)| *x |= y);
+                            if root <= *number {
+                                break root;
+                            }
+                        };
+                        if acceptance.iter().all(|a| *a) {
+                            let component = active
+                                .iter()
+                                .filter(|s| numbers[*s] >= root)
+                                .cloned()
+                                .collect::<HashSet<T::State>>();
+                            let stack = stack.iter().map(|f| f.state.clone()).collect();
+                            return Some(accepting_lasso(
+                                system, stack, root, &numbers, &component, sets, &accepting,
+                            ));
+                        }
+                        roots.push((root, acceptance));
+                    }
+                }
+                continue;
+            }
+
+            let s = frame.state.clone();
+            if roots.last().map(|(root, _)| *root) == Some(numbers[&s]) {
+                // s is the root of a complete component, remove it
+                roots.pop();
+                while let Some(t) = active.pop() {
+                    let done = t == s;
+                    numbers.insert(t, 0);
+                    if done {
+                        break;
+                    }
+                }
+            }
+            stack.pop();
+        }
+    }
+
+    None
+}
+
+/// Build a lasso through the accepting `component`, whose root has number
+/// `root` and is on the search `stack`.
+fn accepting_lasso<T, F>(
+    system: &T,
+    mut stack: Vec<T::State>,
+    root: usize,
+    numbers: &HashMap<T::State, usize>,
+    component: &HashSet<T::State>,
+    sets: usize,
+    accepting: &F,
+) -> Lasso<T::State>
+where
+    T: TransitionSystem,
+    F: Fn(&T::State, usize) -> bool,
+{
+    let start = stack.iter().position(|s| numbers[s] == root).unwrap();
+    let entry = stack.split_off(start).swap_remove(0);
+
+    let mut cycle = vec![entry.clone()];
+    for i in 0..sets {
+        if cycle.iter().any(|s| accepting(s, i)) {
+            continue;
+        }
+        let from = cycle.last().unwrap().clone();
+        cycle.extend(path_within(system, component, &from, |s| accepting(s, i)));
+    }
+    let from = cycle.last().unwrap().clone();
+    let mut back = path_within(system, component, &from, |s| s == &entry);
+    back.pop();
+    cycle.extend(back);
+
+    Lasso {
+        prefix: stack,
+        cycle,
+    }
+}
+
+/// A shortest non-empty path from `from` to a state satisfying `target`
+/// staying inside `component`, excluding `from`.
+fn path_within<T, F>(
+    system: &T,
+    component: &HashSet<T::State>,
+    from: &T::State,
+    target: F,
+) -> Vec<T::State>
+where
+    T: TransitionSystem,
+    F: Fn(&T::State) -> bool,
+{
+    let mut parents: HashMap<T::State, Option<T::State>> = HashMap::new();
+    let mut queue = VecDeque::new();
+    for next in system.successors(from) {
+        if component.contains(&next) && !parents.contains_key(&next) {
+            parents.insert(next.clone(), None);
+            queue.push_back(next);
+        }
+    }
+
+    while let Some(state) = queue.pop_front() {
+        if target(&state) {
+            let mut path = vec![state];
+            while let Some(Some(parent)) = parents.get(path.last().unwrap()) {
+                path.push(parent.clone());
+            }
+            path.reverse();
+            return path;
+        }
+        for next in system.successors(&state) {
+            if component.contains(&next) && !parents.contains_key(&next) {
+                parents.insert(next.clone(), Some(state.clone()));
+                queue.push_back(next);
+            }
+        }
+    }
+
+    unreachable!("the component is strongly connected")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::kripke::Kripke;
+
+    /// An infinite chain of counters where 3 also jumps back to 0.
+    struct Counter;
+
+    impl TransitionSystem for Counter {
+        type State = u64;
+
+        fn initial_states(&self) -> Vec<u64> {
+            vec![0]
+        }
+
+        fn successors(&self, state: &u64) -> Vec<u64> {
+            if *state == 3 {
+                vec![0, 4]
+            } else {
+                vec![state + 1]
+            }
+        }
+
+        fn labels(&self, _: &u64) -> HashSet<String> {
+            HashSet::new()
+        }
+    }
+
+    fn is_lasso<T: TransitionSystem>(system: &T, lasso: &Lasso<T::State>) -> bool {
+        let path = lasso
+            .prefix
+            .iter()
+            .chain(lasso.cycle.iter())
+            .chain(lasso.cycle.first())
+            .collect::<Vec<&T::State>>();
+        system.initial_states().contains(path[0])
+            && path
+                .windows(2)
+                .all(|w| system.successors(w[0]).contains(w[1]))
+    }
+
+    fn model() -> Kripke {
+        let data = r#"
+        {
+            "states": [
+                { "id": 0, "name": "s0", "labels": [], "transit_to": [1, 3] },
+                { "id": 1, "name": "s1", "labels": ["a"], "transit_to": [2] },
+                { "id": 2, "name": "s2", "labels": [], "transit_to": [0, 2] },
+                { "id": 3, "name": "s3", "labels": ["b"], "transit_to": [4] },
+                { "id": 4, "name": "s4", "labels": ["c"], "transit_to": [4] }
+            ],
+            "initial_state": 0
+        }"#;
+        Kripke::from_json(data).unwrap()
+    }
+
+    #[test]
+    fn test_nested_dfs() {
+        let k = model();
+        let lasso = nested_dfs(&k, |s| k.has_label(s, "a")).unwrap();
+        assert!(is_lasso(&k, &lasso));
+        assert!(lasso.cycle.contains(&1));
+
+        let lasso = nested_dfs(&k, |s| k.has_label(s, "c")).unwrap();
+        assert_eq!(lasso.prefix, vec![0, 3]);
+        assert_eq!(lasso.cycle, vec![4]);
+
+        assert!(nested_dfs(&k, |s| k.has_label(s, "b")).is_none());
+    }
+
+    #[test]
+    fn test_early_termination() {
+        let lasso = nested_dfs(&Counter, |s| *s == 3).unwrap();
+        assert_eq!(lasso.cycle, vec![0, 1, 2, 3]);
+
+        let lasso = couvreur(&Counter, 1, |s, _| *s == 1).unwrap();
+        assert!(is_lasso(&Counter, &lasso));
+        assert!(lasso.cycle.contains(&1));
+    }
+
+    #[test]
+    fn test_couvreur() {
+        let k = model();
+        let accepting = |s: &usize, i: usize| match i {
+            0 => k.has_label(s, "a"),
+            _ => *s == 2,
+        };
+        let lasso = couvreur(&k, 2, accepting).unwrap();
+        assert!(is_lasso(&k, &lasso));
+        assert!(lasso.cycle.contains(&1) && lasso.cycle.contains(&2));
+
+        // the sets are only visited in different components
+        let accepting = |s: &usize, i: usize| match i {
+            0 => k.has_label(s, "a"),
+            _ => k.has_label(s, "c"),
+        };
+        assert!(couvreur(&k, 2, accepting).is_none());
+        assert!(couvreur(&k, 1, |s, _| k.has_label(s, "b")).is_none());
+        assert_eq!(
+            couvreur(&k, 1, |s, _| k.has_label(s, "c")),
+            Some(Lasso {
+                prefix: vec![0, 3],
+                cycle: vec![4]
+            })
+        );
+    }
+}
@@ -0,0 +1,697 @@
+use std::{
+    collections::{BTreeSet, HashMap, HashSet},
+    fmt::Display,
+    str::FromStr,
+};
+
+use crate::{algorithm::bisimulation::Dense, ctl::CTLFormulae, model::kripke::Kripke};
+
+/// The relations two models can be compared by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Relation {
+    /// Strong bisimulation, preserving CTL*.
+    #[default]
+    Bisimulation,
+    /// Divergence-sensitive stuttering bisimulation, preserving CTL* without
+    /// next.
+    Stuttering,
+    /// The left model is simulated by the right one, preserving the
+    /// existential fragment of CTL*.
+    Simulation,
+}
+
+impl FromStr for Relation {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Relation, String> {
+        match s {
+            "bisimulation" | "strong" => Ok(Relation::Bisimulation),
+            "stuttering" => Ok(Relation::Stuttering),
+            "simulation" => Ok(Relation::Simulation),
+            _ => Err(format!("unknown relation {}", s)),
+        }
+    }
+}
+
+impl Display for Relation {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Relation::Bisimulation => write!(formatter, "bisimulation"),
+            Relation::Stuttering => write!(formatter, "stuttering"),
+            Relation::Simulation => write!(formatter, "simulation"),
+        }
+    }
+}
+
+/// Decide whether the initial states of `left` and `right` are related by
+/// `relation` over `labels`, all labels if `None`.
+///
+/// When they are not, returns a CTL formula that holds in `left` and not in
+/// `right`. It has no next operator for stuttering bisimulation, and no
+/// universal path quantifier for simulation. Fails when the initial state of
+/// a model is not one of its states.
+pub fn check_equivalence(
+    left: &Kripke,
+    right: &Kripke,
+    relation: Relation,
+    labels: Option<&[&str]>,
+) -> Result<Option<CTLFormulae>, String> {
+    for (side, model) in [("left", left), ("right", right)] {
+        if !model.states.contains_key(&model.initial_state) {
+            return Err(format!(
+                "the initial state {} of the {} model is not one of its states",
+                model.initial_state, side
+            ));
+        }
+    }
+    let (union, s, t) = disjoint_union(left, right);
+
+    // the partitions the formulae are built from decide the verdict
+    let mut distinguisher = Distinguisher::new(&union, labels);
+    let formula = match relation {
+        Relation::Bisimulation => {
+            distinguisher.levels = strong_levels(&distinguisher);
+            distinguisher.strong(s, t)
+        }
+        Relation::Stuttering => {
+            distinguisher.levels = stuttering_levels(&distinguisher);
+            distinguisher.stuttering(s, t)
+        }
+        Relation::Simulation => {
+            let removed = simulation(&distinguisher);
+            distinguisher.simulation(&removed, s, t)
+        }
+    };
+    Ok(formula)
+}
+
+/// The states of `left` followed by those of `right`, numbered densely, and
+/// the initial states of both, which must be states of the models.
+fn disjoint_union(left: &Kripke, right: &Kripke) -> (Kripke, usize, usize) {
+    let mut union = Kripke::new(0);
+    let mut initial = Vec::new();
+    for model in [left, right] {
+        let offset = union.states.len();
+        let dense = Dense::new(model);
+        for (i, id) in dense.ids.iter().enumerate() {
+            let labels = model.label_names_of(*id).into_iter().collect::<Vec<&str>>();
+            union.add_state(offset + i, &model.states[id], &labels);
+            for j in &dense.post[i] {
+                union.add_transition(offset + i, offset + j);
+            }
+            if *id == model.initial_state {
+                initial.push(offset + i);
+            }
+        }
+    }
+    (union, initial[0], initial[1])
+}
+
+fn conjunction(formulae: Vec<CTLFormulae>) -> CTLFormulae {
+    let mut seen = HashSet::new();
+    formulae
+        .into_iter()
+        .filter(|f| seen.insert(f.get_str()))
+        .reduce(|f, g| CTLFormulae::And(Box::new(f), Box::new(g)))
+        .unwrap_or(CTLFormulae::True)
+}
+
+fn disjunction(formulae: Vec<CTLFormulae>) -> CTLFormulae {
+    let mut seen = HashSet::new();
+    formulae
+        .into_iter()
+        .filter(|f| seen.insert(f.get_str()))
+        .reduce(|f, g| CTLFormulae::Or(Box::new(f), Box::new(g)))
+        .unwrap_or(CTLFormulae::Not(Box::new(CTLFormulae::True)))
+}
+
+/// Builds distinguishing formulae from the successive partitions of a
+/// refinement, `levels[k][x]` being the block of `x` after `k` rounds.
+struct Distinguisher<'a> {
+    dense: Dense,
+    valuations: Vec<BTreeSet<&'a str>>,
+    /// Whether each state lies on a cycle of equally labelled states.
+    divergent: Vec<bool>,
+    levels: Vec<Vec<usize>>,
+    memo: HashMap<(usize, usize), CTLFormulae>,
+}
+
+impl<'a> Distinguisher<'a> {
+    fn new(model: &'a Kripke, labels: Option<&[&str]>) -> Distinguisher<'a> {
+        let dense = Dense::new(model);
+        let valuations = dense
+            .ids
+            .iter()
+            .map(|id| {
+                model
+                    .label_names_of(*id)
+                    .into_iter()
+                    .filter(|label| labels.is_none_or(|labels| labels.contains(label)))
+                    .collect()
+            })
+            .collect::<Vec<BTreeSet<&str>>>();
+        let classes = valuation_classes(&valuations);
+        let divergent = divergent_states(&dense, &classes);
+        Distinguisher {
+            dense,
+            valuations,
+            divergent,
+            levels: Vec::new(),
+            memo: HashMap::new(),
+        }
+    }
+
+    /// An atomic proposition or its negation holding in `s` and not in `t`.
+    fn literal(&self, s: usize, t: usize) -> CTLFormulae {
+        match self.valuations[s].difference(&self.valuations[t]).next() {
+            Some(label) => CTLFormulae::Atomic(label.to_string()),
+            None => {
+                let label = self.valuations[t].difference(&self.valuations[s]).next();
+                CTLFormulae::Not(Box::new(CTLFormulae::Atomic(label.unwrap().to_string())))
+            }
+        }
+    }
+
+    /// The first round separating `s` and `t`, if they are not equivalent.
+    fn split(&self, s: usize, t: usize) -> Option<usize> {
+        self.levels.iter().position(|level| level[s] != level[t])
+    }
+
+    /// A formula holding in `s` and not in `t`, if they are not strongly
+    /// bisimilar.
+    fn strong(&mut self, s: usize, t: usize) -> Option<CTLFormulae> {
+        if let Some(f) = self.memo.get(&(s, t)) {
+            return Some(f.clone());
+        }
+
+        let k = self.split(s, t)?;
+        let f = if k == 0 {
+            self.literal(s, t)
+        } else {
+            let previous = self.levels[k - 1].clone();
+            let blocks = |x: usize| -> HashSet<usize> {
+                self.dense.post[x].iter().map(|y| previous[*y]).collect()
+            };
+            let (of_s, of_t) = (blocks(s), blocks(t));
+            let post = self.dense.post.clone();
+            // a successor of one that no successor of the other matches
+            match post[s].iter().find(|y| !of_t.contains(&previous[**y])) {
+                Some(y) => {
+                    let each = post[t]
+                        .iter()
+                        .map(|z| self.strong(*y, *z))
+                        .collect::<Option<_>>()?;
+                    exist_next(conjunction(each))
+                }
+                None => {
+                    let y = post[t].iter().find(|y| !of_s.contains(&previous[**y]))?;
+                    let each = post[s]
+                        .iter()
+                        .map(|z| self.strong(*y, *z))
+                        .collect::<Option<_>>()?;
+                    CTLFormulae::Not(Box::new(exist_next(conjunction(each))))
+                }
+            }
+        };
+        self.memo.insert((s, t), f.clone());
+        Some(f)
+    }
+
+    /// The states reachable from `x` within its block of `level`, in
+    /// breadth-first order.
+    fn inert(&self, level: &[usize], x: usize) -> Vec<usize> {
+        let mut reached = vec![x];
+        let mut seen = HashSet::from([x]);
+        let mut i = 0;
+        while i < reached.len() {
+            for y in &self.dense.post[reached[i]] {
+                if level[*y] == level[x] && seen.insert(*y) {
+                    reached.push(*y);
+                }
+            }
+            i += 1;
+        }
+        reached
+    }
+
+    /// The blocks of `level` the states inertly reachable from `x` step to,
+    /// `None` standing for divergence.
+    fn exits(&self, level: &[usize], x: usize) -> BTreeSet<Option<usize>> {
+        self.inert(level, x)
+            .into_iter()
+            .flat_map(|y| {
+                let exits = self.dense.post[y]
+                    .iter()
+                    .filter(|z| level[**z] != level[x])
+                    .map(|z| Some(level[*z]))
+                    .collect::<Vec<_>>();
+                let diverges = self.divergent[y].then_some(None);
+                exits.into_iter().chain(diverges)
+            })
+            .collect()
+    }
+
+    /// A formula without next holding in `s` and not in `t`, if they are not
+    /// stuttering bisimilar.
+    fn stuttering(&mut self, s: usize, t: usize) -> Option<CTLFormulae> {
+        if let Some(f) = self.memo.get(&(s, t)) {
+            return Some(f.clone());
+        }
+
+        let k = self.split(s, t)?;
+        let f = if k == 0 {
+            self.literal(s, t)
+        } else {
+            let previous = self.levels[k - 1].clone();
+            let (of_s, of_t) = (self.exits(&previous, s), self.exits(&previous, t));
+            match of_s.difference(&of_t).next() {
+                Some(exit) => self.escape(&previous, s, t, *exit)?,
+                None => {
+                    let exit = *of_t.difference(&of_s).next()?;
+                    CTLFormulae::Not(Box::new(self.escape(&previous, t, s, exit)?))
+                }
+            }
+        };
+        self.memo.insert((s, t), f.clone());
+        Some(f)
+    }
+
+    /// A formula holding in `s`, which can reach `exit` within its block of
+    /// `level`, and not in `t`, which cannot.
+    fn escape(
+        &mut self,
+        level: &[usize],
+        s: usize,
+        t: usize,
+        exit: Option<usize>,
+    ) -> Option<CTLFormulae> {
+        // a path of s within its block to the exit
+        let reached = self.inert(level, s);
+        let mut parent = HashMap::new();
+        for x in &reached {
+            for y in &self.dense.post[*x] {
+                if level[*y] == level[s] && !parent.contains_key(y) && *y != s {
+                    parent.insert(*y, *x);
+                }
+            }
+        }
+        let (last, target) = reached
+            .iter()
+            .find_map(|x| match exit {
+                Some(block) => self.dense.post[*x]
+                    .iter()
+                    .find(|y| level[**y] == block)
+                    .map(|y| (*x, Some(*y))),
+                None => self.divergent[*x].then_some((*x, None)),
+            })
+            .unwrap();
+        let mut path = vec![last];
+        while let Some(x) = parent.get(path.last().unwrap()) {
+            path.push(*x);
+        }
+        if target.is_none() {
+            // the cycle within the block
+            path.extend(
+                self.inert(level, last)
+                    .into_iter()
+                    .filter(|y| self.inert(level, *y).contains(&last)),
+            );
+        }
+
+        // where t can be until it leaves its block
+        let within = self.inert(level, t);
+        let leaving = within
+            .iter()
+            .flat_map(|x| self.dense.post[*x].iter())
+            .filter(|y| level[**y] != level[t])
+            .cloned()
+            .collect::<BTreeSet<usize>>();
+
+        let stay = leaving
+            .iter()
+            .map(|e| {
+                let each = path
+                    .iter()
+                    .map(|b| self.stuttering(*b, *e))
+                    .collect::<Option<_>>()?;
+                Some(disjunction(each))
+            })
+            .collect::<Option<_>>()?;
+        let stay = conjunction(stay);
+        match target {
+            Some(c) => {
+                let each = within
+                    .iter()
+                    .chain(&leaving)
+                    .map(|e| self.stuttering(c, *e))
+                    .collect::<Option<_>>()?;
+                Some(CTLFormulae::Exist(Box::new(CTLFormulae::Until(
+                    Box::new(stay),
+                    Box::new(conjunction(each)),
+                ))))
+            }
+            None => Some(CTLFormulae::Exist(Box::new(CTLFormulae::Globally(
+                Box::new(stay),
+            )))),
+        }
+    }
+
+    /// A formula without universal quantifier holding in `s` and not in `t`,
+    /// if `s` is not simulated by `t`.
+    fn simulation(
+        &mut self,
+        removed: &[Vec<Option<usize>>],
+        s: usize,
+        t: usize,
+    ) -> Option<CTLFormulae> {
+        if let Some(f) = self.memo.get(&(s, t)) {
+            return Some(f.clone());
+        }
+
+        let k = removed[s][t]?;
+        let f = if k == 0 {
+            self.literal(s, t)
+        } else {
+            let post = self.dense.post.clone();
+            let y = post[s].iter().find(|y| {
+                post[t]
+                    .iter()
+                    .all(|z| removed[**y][*z].is_some_and(|j| j < k))
+            })?;
+            let each = post[t]
+                .iter()
+                .map(|z| self.simulation(removed, *y, *z))
+                .collect::<Option<_>>()?;
+            exist_next(conjunction(each))
+        };
+        self.memo.insert((s, t), f.clone());
+        Some(f)
+    }
+}
+
+fn exist_next(f: CTLFormulae) -> CTLFormulae {
+    CTLFormulae::Exist(Box::new(CTLFormulae::Next(Box::new(f))))
+}
+
+/// The partitions by valuation, then by the blocks of the successors.
+fn strong_levels(distinguisher: &Distinguisher) -> Vec<Vec<usize>> {
+    refine(distinguisher, |level, x| {
+        distinguisher.dense.post[x]
+            .iter()
+            .map(|y| Some(level[*y]))
+            .collect()
+    })
+}
+
+/// The partitions by valuation, then by the blocks reachable after
+/// stuttering within the block, and divergence.
+fn stuttering_levels(distinguisher: &Distinguisher) -> Vec<Vec<usize>> {
+    refine(distinguisher, |level, x| distinguisher.exits(level, x))
+}
+
+fn valuation_classes(valuations: &[BTreeSet<&str>]) -> Vec<usize> {
+    let mut classes = HashMap::new();
+    valuations
+        .iter()
+        .map(|valuation| {
+            let next = classes.len();
+            *classes.entry(valuation).or_insert(next)
+        })
+        .collect()
+}
+
+/// Refine the partition by valuation with `signature` until it is stable.
+fn refine<F>(distinguisher: &Distinguisher, signature: F) -> Vec<Vec<usize>>
+where
+    F: Fn(&[usize], usize) -> BTreeSet<Option<usize>>,
+{
+    let mut levels = vec![valuation_classes(&distinguisher.valuations)];
+    loop {
+        let level = levels.last().unwrap();
+        let mut blocks = HashMap::new();
+        let next = (0..level.len())
+            .map(|x| {
+                let key = (level[x], signature(level, x));
+                let id = blocks.len();
+                *blocks.entry(key).or_insert(id)
+            })
+            .collect::<Vec<usize>>();
+        let before = level.iter().collect::<HashSet<_>>().len();
+        if blocks.len() == before {
+            return levels;
+        }
+        levels.push(next);
+    }
+}
+
+/// Whether each state lies on a cycle of states of the same class.
+fn divergent_states(dense: &Dense, classes: &[usize]) -> Vec<bool> {
+    (0..dense.ids.len())
+        .map(|x| {
+            let mut seen = HashSet::new();
+            let mut stack = vec![x];
+            while let Some(y) = stack.pop() {
+                for z in &dense.post[y] {
+                    if classes[*z] == classes[x] {
+                        if *z == x {
+                            return true;
+                        }
+                        if seen.insert(*z) {
+                            stack.push(*z);
+                        }
+                    }
+                }
+            }
+            false
+        })
+        .collect()
+}
+
+/// The greatest simulation, as the round each pair is removed in, if it is.
+fn simulation(distinguisher: &Distinguisher) -> Vec<Vec<Option<usize>>> {
+    let post = &distinguisher.dense.post;
+    let n = post.len();
+    let valuations = &distinguisher.valuations;
+    let mut removed = (0..n)
+        .map(|s| {
+            (0..n)
+                .map(|t| (valuations[s] != valuations[t]).then_some(0))
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+
+    for round in 1.. {
+        let next = (0..n)
+            .flat_map(|s| (0..n).map(move |t| (s, t)))
+            .filter(|(s, t)| {
+                removed[*s][*t].is_none()
+                    && post[*s]
+                        .iter()
+                        .any(|y| post[*t].iter().all(|z| removed[*y][*z].is_some()))
+            })
+            .collect::<Vec<_>>();
+        if next.is_empty() {
+            break;
+        }
+        for (s, t) in next {
+            removed[s][t] = Some(round);
+        }
+    }
+    removed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algorithm::{
+        bisimulation::strong_bisimulation, ctl_checking, stuttering::stuttering_bisimulation,
+    };
+
+    fn kripke(states: &[(&[&str], &[usize])]) -> Kripke {
+        let mut k = Kripke::new(0);
+        for (id, (labels, next)) in states.iter().enumerate() {
+            k.add_state(id, &format!("s{}", id), labels);
+            for to in *next {
+                k.add_transition(id, *to);
+            }
+        }
+        k
+    }
+
+    /// Check that `formula` holds in `left` and not in `right`.
+    fn distinguishes(formula: &CTLFormulae, left: Kripke, right: Kripke) {
+        let (mut left, mut right) = (left, right);
+        for label in ["p", "q", "r"] {
+            left.get_label_id_or_add(label);
+            right.get_label_id_or_add(label);
+        }
+        assert!(
+            ctl_checking(&mut left, formula.clone()).unwrap(),
+            "{}",
+            formula
+        );
+        assert!(
+            !ctl_checking(&mut right, formula.clone()).unwrap(),
+            "{}",
+            formula
+        );
+    }
+
+    /// The classic vending machines: choosing after or before the coin.
+    fn late() -> Kripke {
+        kripke(&[
+            (&[], &[1]),
+            (&["p"], &[2, 3]),
+            (&["q"], &[0]),
+            (&["r"], &[0]),
+        ])
+    }
+
+    fn early() -> Kripke {
+        kripke(&[
+            (&[], &[1, 4]),
+            (&["p"], &[2]),
+            (&["q"], &[0]),
+            (&["r"], &[0]),
+            (&["p"], &[3]),
+        ])
+    }
+
+    #[test]
+    fn test_bisimilar() {
+        // a cycle unrolled twice
+        let once = kripke(&[(&["p"], &[1]), (&["q"], &[0])]);
+        let twice = kripke(&[
+            (&["p"], &[1]),
+            (&["q"], &[2]),
+            (&["p"], &[3]),
+            (&["q"], &[0]),
+        ]);
+        for relation in [
+            Relation::Bisimulation,
+            Relation::Stuttering,
+            Relation::Simulation,
+        ] {
+            assert!(check_equivalence(&once, &twice, relation, None)
+                .unwrap()
+                .is_none());
+            assert!(check_equivalence(&twice, &once, relation, None)
+                .unwrap()
+                .is_none());
+        }
+    }
+
+    #[test]
+    fn test_not_bisimilar() {
+        let f = check_equivalence(&late(), &early(), Relation::Bisimulation, None)
+            .unwrap()
+            .unwrap();
+        distinguishes(&f, late(), early());
+        let f = check_equivalence(&early(), &late(), Relation::Bisimulation, None)
+            .unwrap()
+            .unwrap();
+        distinguishes(&f, early(), late());
+
+        // only comparing p, they are bisimilar
+        assert!(
+            check_equivalence(&late(), &early(), Relation::Bisimulation, Some(&["p"]))
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_simulation() {
+        // the early machine is simulated by the late one, not conversely
+        assert!(
+            check_equivalence(&early(), &late(), Relation::Simulation, None)
+                .unwrap()
+                .is_none()
+        );
+        let f = check_equivalence(&late(), &early(), Relation::Simulation, None)
+            .unwrap()
+            .unwrap();
+        assert!(!f.get_str().contains('A') && !f.get_str().contains('!'));
+        distinguishes(&f, late(), early());
+    }
+
+    #[test]
+    fn test_stuttering() {
+        let slow = || kripke(&[(&[], &[1]), (&[], &[2]), (&["p"], &[3]), (&["q"], &[3])]);
+        let fast = || kripke(&[(&[], &[1]), (&["p"], &[2]), (&["q"], &[2])]);
+        assert!(
+            check_equivalence(&slow(), &fast(), Relation::Stuttering, None)
+                .unwrap()
+                .is_none()
+        );
+        let f = check_equivalence(&slow(), &fast(), Relation::Bisimulation, None)
+            .unwrap()
+            .unwrap();
+        distinguishes(&f, slow(), fast());
+
+        // waiting for p may take forever
+        let lazy = || kripke(&[(&[], &[0, 1]), (&["p"], &[1])]);
+        let eager = || kripke(&[(&[], &[1]), (&["p"], &[1])]);
+        let f = check_equivalence(&lazy(), &eager(), Relation::Stuttering, None)
+            .unwrap()
+            .unwrap();
+        assert!(f.is_next_free());
+        distinguishes(&f, lazy(), eager());
+        let f = check_equivalence(&eager(), &lazy(), Relation::Stuttering, None)
+            .unwrap()
+            .unwrap();
+        distinguishes(&f, eager(), lazy());
+
+        // a choice made before or after stuttering
+        let branch = || kripke(&[(&[], &[1, 2]), (&[], &[3]), (&["p"], &[2]), (&["q"], &[3])]);
+        let linear = || kripke(&[(&[], &[1]), (&[], &[2, 3]), (&["p"], &[2]), (&["q"], &[3])]);
+        let f = check_equivalence(&branch(), &linear(), Relation::Stuttering, None)
+            .unwrap()
+            .unwrap();
+        assert!(f.is_next_free());
+        distinguishes(&f, branch(), linear());
+        let f = check_equivalence(&linear(), &branch(), Relation::Stuttering, None)
+            .unwrap()
+            .unwrap();
+        distinguishes(&f, linear(), branch());
+    }
+
+    #[test]
+    fn test_missing_initial_state() {
+        let mut missing = early();
+        missing.initial_state = 7;
+        assert_eq!(
+            check_equivalence(&early(), &missing, Relation::Bisimulation, None).unwrap_err(),
+            "the initial state 7 of the right model is not one of its states"
+        );
+    }
+
+    #[test]
+    fn test_verdict_matches_partition() {
+        let deadlock = || kripke(&[(&[], &[1]), (&["p"], &[])]);
+        let lazy = || kripke(&[(&[], &[0, 1]), (&["p"], &[1])]);
+        let models: [&dyn Fn() -> Kripke; 4] = [&late, &early, &deadlock, &lazy];
+        for left in models {
+            for right in models {
+                let (union, s, t) = disjoint_union(&left(), &right());
+                let related = |partition: Vec<Vec<usize>>| {
+                    partition
+                        .iter()
+                        .any(|block| block.contains(&s) && block.contains(&t))
+                };
+                let strong =
+                    check_equivalence(&left(), &right(), Relation::Bisimulation, None).unwrap();
+                assert_eq!(strong.is_none(), related(strong_bisimulation(&union, None)));
+                let stuttering =
+                    check_equivalence(&left(), &right(), Relation::Stuttering, None).unwrap();
+                assert_eq!(
+                    stuttering.is_none(),
+                    related(stuttering_bisimulation(&union, None))
+                );
+                if let Some(f) = stuttering {
+                    distinguishes(&f, left(), right());
+                }
+            }
+        }
+    }
+}
@@ -5,75 +5,93 @@ use crate::{
     model::kripke::Kripke,
 };
 
-pub fn explicit_state_checking(model: &mut Kripke, formula: CTLFormulae) {
+/// Label `model` with `formula` and all of its subformulae.
+///
+/// Returns the index of the label of `formula`, `usize::MAX` meaning true.
+/// Fails when `formula` is not a state formula or has an atomic proposition
+/// which is not a label of `model`.
+pub fn explicit_state_checking(model: &mut Kripke, formula: CTLFormulae) -> Result<usize, String> {
+    formula.check_state_form()?;
     let normal = to_normal_form(formula);
-    println!("Normal form: {}", normal);
-    process(model, &normal);
+    process(model, &normal)
 }
 
 /// Process the formulae and return the index of the label
 ///
 /// `usize::MAX` means true, which holds for all states
-fn process(model: &mut Kripke, f: &CTLFormulae) -> usize {
+fn process(model: &mut Kripke, f: &CTLFormulae) -> Result<usize, String> {
     if let Some(id) = model.contains_label(&f.get_str()) {
-        return id;
+        return Ok(id);
     }
 
     match f {
-        CTLFormulae::True => usize::MAX,
-        CTLFormulae::Atomic(atomic) => {
-            if let Some(id) = model.contains_label(atomic) {
-                id
-            } else {
-                panic!("Atomic formulae {} is not defined", atomic)
-            }
-        }
+        CTLFormulae::True => Ok(usize::MAX),
+        CTLFormulae::Atomic(atomic) => model
+            .contains_label(atomic)
+            .ok_or_else(|| format!("atomic proposition {} is not defined", atomic)),
         CTLFormulae::Not(f) => {
             let not_id = model.get_label_id_or_add(&format!("!{}", f.get_str()));
-            check_not(model, not_id, f);
-            not_id
+            check_not(model, not_id, f)?;
+            Ok(not_id)
         }
         CTLFormulae::Or(f1, f2) => {
             let or_id = model.get_label_id_or_add(&format!("{}||{}", f1.get_str(), f2.get_str()));
-            check_or(model, or_id, f1, f2);
-            or_id
+            check_or(model, or_id, f1, f2)?;
+            Ok(or_id)
         }
         CTLFormulae::And(f1, f2) => {
             let and_id = model.get_label_id_or_add(&format!("{}&&{}", f1.get_str(), f2.get_str()));
-            check_and(model, and_id, f1, f2);
-            and_id
+            check_and(model, and_id, f1, f2)?;
+            Ok(and_id)
         }
         CTLFormulae::Exist(f) => match &**f {
             CTLFormulae::Next(n) => {
                 let en_id = model.get_label_id_or_add(&format!("E{}", f.get_str()));
-                check_exist_next(model, en_id, n);
-                en_id
+                check_exist_next(model, en_id, n)?;
+                Ok(en_id)
             }
             CTLFormulae::Globally(g) => {
                 let eg_id = model.get_label_id_or_add(&format!("E{}", f.get_str()));
-                check_exist_globally(model, eg_id, g);
-                eg_id
+                check_exist_globally(model, eg_id, g)?;
+                Ok(eg_id)
             }
             CTLFormulae::Until(f1, f2) => {
                 let eu_id = model.get_label_id_or_add(&format!("E{}", f.get_str()));
-                check_exist_until(model, eu_id, f1, f2);
-                eu_id
+                check_exist_until(model, eu_id, f1, f2)?;
+                Ok(eu_id)
             }
-            _ => {
-                let en_id = model.get_label_id_or_add(&format!("E{}", f.get_str()));
-                check_exist_next(model, en_id, f);
-                en_id
+            CTLFormulae::BoundedGlobally(a, b, g) => {
+                let eg_id = model.get_label_id_or_add(&format!("E{}", f.get_str()));
+                check_bounded_globally(model, eg_id, *a, *b, g)?;
+                Ok(eg_id)
+            }
+            CTLFormulae::BoundedUntil(a, b, f1, f2) => {
+                let eu_id = model.get_label_id_or_add(&format!("E{}", f.get_str()));
+                check_bounded_until(model, eu_id, false, *a, *b, f1, f2)?;
+                Ok(eu_id)
             }
+            _ => Err(format!("E{} is not in normal form", f.get_str())),
         },
-        _ => {
-            panic!("Not in normal form {}", f.get_str());
-        }
+        CTLFormulae::All(f) => match &**f {
+            CTLFormulae::BoundedUntil(a, b, f1, f2) => {
+                let au_id = model.get_label_id_or_add(&format!("A{}", f.get_str()));
+                check_bounded_until(model, au_id, true, *a, *b, f1, f2)?;
+                Ok(au_id)
+            }
+            _ => Err(format!("A{} is not in normal form", f.get_str())),
+        },
+        _ => Err(format!("{} is not in normal form", f.get_str())),
     }
 }
 
-fn check_and(model: &mut Kripke, and_id: usize, f1: &CTLFormulae, f2: &CTLFormulae) {
-    let f1_idx = process(model, f1);
-    let f2_idx = process(model, f2);
+fn check_and(
+    model: &mut Kripke,
+    and_id: usize,
+    f1: &CTLFormulae,
+    f2: &CTLFormulae,
+) -> Result<(), String> {
+    let f1_idx = process(model, f1)?;
+    let f2_idx = process(model, f2)?;
     let s1 = model.get_state_with_label_as_set(f1_idx);
     let s2 = model.get_state_with_label_as_set(f2_idx);
 
@@ -81,10 +99,11 @@ fn check_and(model: &mut Kripke, and_id: usize, f1: &CTLFormulae, f2: &CTLFormul
     for s in intersection {
         model.add_state_for_label(and_id, s);
     }
+    Ok(())
 }
 
-fn check_not(model: &mut Kripke, not_id: usize, f: &CTLFormulae) {
-    let f_idx = process(model, f);
+fn check_not(model: &mut Kripke, not_id: usize, f: &CTLFormulae) -> Result<(), String> {
+    let f_idx = process(model, f)?;
     let s_prime = model.get_state_with_label(f_idx);
     // get complement of s_prime
     let mut s_prime_complement = HashSet::new();
@@ -96,11 +115,17 @@ fn check_not(model: &mut Kripke, not_id: usize, f: &CTLFormulae) {
     for s in s_prime_complement {
         model.add_state_for_label(not_id, s);
     }
+    Ok(())
 }
 
-fn check_or(model: &mut Kripke, or_id: usize, f1: &CTLFormulae, f2: &CTLFormulae) {
-    let f1_idx = process(model, f1);
-    let f2_idx = process(model, f2);
+fn check_or(
+    model: &mut Kripke,
+    or_id: usize,
+    f1: &CTLFormulae,
+    f2: &CTLFormulae,
+) -> Result<(), String> {
+    let f1_idx = process(model, f1)?;
+    let f2_idx = process(model, f2)?;
     let s1 = model.get_state_with_label_as_set(f1_idx);
     let s2 = model.get_state_with_label_as_set(f2_idx);
 
@@ -109,11 +134,12 @@ fn check_or(model: &mut Kripke, or_id: usize, f1: &CTLFormulae, f2: &CTLFormulae
     for s in union {
         model.add_state_for_label(or_id, s);
     }
+    Ok(())
 }
 
-fn check_exist_next(model: &mut Kripke, en_id: usize, f: &CTLFormulae) {
+fn check_exist_next(model: &mut Kripke, en_id: usize, f: &CTLFormulae) -> Result<(), String> {
     // label every state that has a successor that satisfies f
-    let f_idx = process(model, f);
+    let f_idx = process(model, f)?;
     let states = model.get_state_with_label(f_idx);
     let transitions = model.transitions.clone();
     for (s, t) in transitions {
@@ -122,17 +148,23 @@ fn check_exist_next(model: &mut Kripke, en_id: usize, f: &CTLFormulae) {
             model.add_state_for_label(en_id, s);
         }
     }
+    Ok(())
 }
 
-fn check_exist_until(model: &mut Kripke, eu_id: usize, f1: &CTLFormulae, f2: &CTLFormulae) {
-    let f1_idx = process(model, f1);
-    let f2_idx = process(model, f2);
+fn check_exist_until(
+    model: &mut Kripke,
+    eu_id: usize,
+    f1: &CTLFormulae,
+    f2: &CTLFormulae,
+) -> Result<(), String> {
+    let f1_idx = process(model, f1)?;
+    let f2_idx = process(model, f2)?;
 
-    let mut T = model.get_state_with_label(f2_idx);
-    for i in &T {
+    let mut t_set = model.get_state_with_label(f2_idx);
+    for i in &t_set {
         model.add_state_for_label(eu_id, *i);
     }
-    while let Some(s) = T.pop() {
+    while let Some(s) = t_set.pop() {
         for t in &model.transitable_to(s) {
             let label_t = model.state_to_labels.get(t).unwrap();
             let condition2 = if f1_idx == usize::MAX {
@@ -142,14 +174,15 @@ fn check_exist_until(model: &mut Kripke, eu_id: usize, f1: &CTLFormulae, f2: &CT
             };
             if !label_t.contains(&eu_id) && condition2 {
                 model.add_state_for_label(eu_id, *t);
-                T.push(*t);
+                t_set.push(*t);
             }
         }
     }
+    Ok(())
 }
 
-fn check_exist_globally(model: &mut Kripke, eg_id: usize, f: &CTLFormulae) {
-    let f_idx = process(model, f);
+fn check_exist_globally(model: &mut Kripke, eg_id: usize, f: &CTLFormulae) -> Result<(), String> {
+    let f_idx = process(model, f)?;
     let s_prime = model.get_state_with_label(f_idx);
     let sccs = model.non_trivial_scc_of(&s_prime);
     // union sccs into one set
@@ -176,11 +209,91 @@ fn check_exist_globally(model: &mut Kripke, eg_id: usize, f: &CTLFormulae) {
             }
         }
     }
+    Ok(())
+}
+
+/// The states with a successor in `states`, or with all of their successors
+/// in `states` when `universal`.
+fn pre(model: &Kripke, states: &HashSet<usize>, universal: bool) -> HashSet<usize> {
+    let empty = HashSet::new();
+    model
+        .states
+        .keys()
+        .filter(|s| {
+            let mut next = model.transitions.get(s).unwrap_or(&empty).iter();
+            if universal {
+                next.all(|t| states.contains(t))
+            } else {
+                next.any(|t| states.contains(t))
+            }
+        })
+        .cloned()
+        .collect()
+}
+
+fn check_bounded_until(
+    model: &mut Kripke,
+    bu_id: usize,
+    universal: bool,
+    a: usize,
+    b: usize,
+    f1: &CTLFormulae,
+    f2: &CTLFormulae,
+) -> Result<(), String> {
+    let f1_idx = process(model, f1)?;
+    let f2_idx = process(model, f2)?;
+    let s1 = model.get_state_with_label_as_set(f1_idx);
+    let s2 = model.get_state_with_label_as_set(f2_idx);
+
+    // the states satisfying the formula from step k, for k from b down to 0
+    let mut z = s2.clone();
+    for k in (0..b).rev() {
+        let later = s1
+            .intersection(&pre(model, &z, universal))
+            .cloned()
+            .collect();
+        z = if k >= a {
+            s2.union(&later).cloned().collect()
+        } else {
+            later
+        };
+    }
+    for s in z {
+        model.add_state_for_label(bu_id, s);
+    }
+    Ok(())
+}
+
+fn check_bounded_globally(
+    model: &mut Kripke,
+    eg_id: usize,
+    a: usize,
+    b: usize,
+    f: &CTLFormulae,
+) -> Result<(), String> {
+    let f_idx = process(model, f)?;
+    let s_prime = model.get_state_with_label_as_set(f_idx);
+
+    // the states with a path from them satisfying f from step k to b
+    let mut z = s_prime.clone();
+    for k in (0..b).rev() {
+        let later = pre(model, &z, false);
+        z = if k >= a {
+            s_prime.intersection(&later).cloned().collect()
+        } else {
+            later
+        };
+    }
+    for s in z {
+        model.add_state_for_label(eg_id, s);
+    }
+    Ok(())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::parser::ctl_parser::parse_ctl;
 
     #[test]
     fn test_explicit() {
@@ -196,7 +309,46 @@ mod tests {
         ],
         "initial_state": 0
     }"#;
-        let k = Kripke::from_json(data).unwrap();
+        let mut k = Kripke::from_json(data).unwrap();
         println!("{:?}", k);
+        assert_eq!(
+            explicit_state_checking(&mut k, parse_ctl("EX b").unwrap()),
+            Err("atomic proposition b is not defined".to_string())
+        );
+        assert_eq!(
+            explicit_state_checking(&mut k, parse_ctl("AG X a").unwrap()),
+            Err("Xa is not a CTL state formula".to_string())
+        );
+    }
+
+    #[test]
+    fn test_bounded() {
+        // a chain to p, and a branch without it
+        let data = r#"
+        {
+            "states": [
+                { "id": 0, "name": "s0", "labels": ["q"], "transit_to": [1, 4] },
+                { "id": 1, "name": "s1", "labels": ["q"], "transit_to": [2] },
+                { "id": 2, "name": "s2", "labels": ["p"], "transit_to": [3] },
+                { "id": 3, "name": "s3", "labels": [], "transit_to": [3] },
+                { "id": 4, "name": "s4", "labels": [], "transit_to": [4] }
+            ],
+            "initial_state": 0
+        }"#;
+        let check = |formula: &str| {
+            let mut model = Kripke::from_json(data).unwrap();
+            let id = explicit_state_checking(&mut model, parse_ctl(formula).unwrap()).unwrap();
+            let mut states = model.get_state_with_label(id);
+            states.sort();
+            states
+        };
+        assert_eq!(check("EF[2,3] p"), vec![0]);
+        assert_eq!(check("AF[2,3] p"), Vec::<usize>::new());
+        assert_eq!(check("EF[0,1] p"), vec![1, 2]);
+        assert_eq!(check("AF[1,1] p"), vec![1]);
+        assert_eq!(check("EG[0,2] !p"), vec![0, 3, 4]);
+        assert_eq!(check("AG[1,2] !p"), vec![2, 3, 4]);
+        assert_eq!(check("E (q U[1,2] p)"), vec![0, 1]);
+        assert_eq!(check("A (q U[1,2] p)"), vec![1]);
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,408 @@
+use std::{cmp::Reverse, collections::BinaryHeap};
+
+use crate::{
+    expr::{BinaryOp, Expr},
+    model::{
+        guarded::GuardedProgram,
+        symbolic::{Cube, Encoding, Frame},
+    },
+    sat::{Lit, Solver},
+};
+
+/// An inductive invariant proving that `property` always holds: it holds in
+/// the initial state, is kept by every transition, and implies `property`.
+///
+/// It is `property` where none of the `cubes` hold.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Invariant {
+    pub property: Expr,
+    pub cubes: Vec<Cube>,
+}
+
+impl Invariant {
+    pub fn holds(&self, state: &[i64]) -> bool {
+        self.property.holds(state) == Ok(true)
+            && self
+                .cubes
+                .iter()
+                .all(|cube| cube.iter().any(|(var, value)| state[*var] != *value))
+    }
+
+    pub fn to_expr(&self) -> Expr {
+        let and = |lhs, rhs| Expr::Binary(BinaryOp::And, Box::new(lhs), Box::new(rhs));
+        self.cubes
+            .iter()
+            .fold(self.property.clone(), |result, cube| {
+                let cube = cube
+                    .iter()
+                    .map(|(var, value)| {
+                        Expr::Binary(
+                            BinaryOp::Eq,
+                            Box::new(Expr::Var(*var)),
+                            Box::new(Expr::Int(*value)),
+                        )
+                    })
+                    .reduce(and)
+                    .unwrap_or(Expr::Int(1));
+                and(result, Expr::Not(Box::new(cube)))
+            })
+    }
+
+    /// Check that the invariant is indeed inductive for `program`.
+    pub fn verify(&self, program: &GuardedProgram) -> bool {
+        let encoding = Encoding::new(program);
+        let mut solver = Solver::new();
+        let (now, next) = (encoding.frame(&mut solver), encoding.frame(&mut solver));
+        encoding.transition(&mut solver, &now, &next);
+        let expr = self.to_expr();
+        let (before, after) = (
+            encoding.predicate(&mut solver, &now, &expr),
+            encoding.predicate(&mut solver, &next, &expr),
+        );
+        self.holds(&program.initial_state()) && !solver.solve(&[before, !after])
+    }
+}
+
+/// A proof obligation: the state of `node` must be blocked at `level`.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+struct Obligation {
+    level: usize,
+    node: usize,
+}
+
+struct Ic3<'a> {
+    encoding: Encoding<'a>,
+    solver: Solver,
+    now: Frame,
+    next: Frame,
+    initial: Vec<i64>,
+    init: Lit,
+    /// The activation literal of each frame, the first one being unused as
+    /// the frame 0 is the initial state.
+    frames: Vec<Lit>,
+    /// The cubes blocked up to each frame.
+    cubes: Vec<Vec<Cube>>,
+    /// The states of the obligations and the index of their successor toward
+    /// a violation of the property.
+    trace: Vec<(Vec<i64>, Option<usize>)>,
+}
+
+impl Ic3<'_> {
+    /// The assumptions restricting the current state to frame `level`.
+    fn frame(&self, level: usize) -> Vec<Lit> {
+        if level == 0 {
+            vec![self.init]
+        } else {
+            self.frames[level..].to_vec()
+        }
+    }
+
+    fn intersects_init(&self, cube: &[(usize, i64)]) -> bool {
+        cube.iter().all(|(var, value)| self.initial[*var] == *value)
+    }
+
+    /// A state of frame `level` outside of `cube` with a successor in it.
+    fn predecessor(&mut self, level: usize, cube: &[(usize, i64)]) -> Option<Vec<i64>> {
+        let outside = self.solver.new_var();
+        self.encoding
+            .block(&mut self.solver, &self.now, cube, Some(outside));
+        let mut assumptions = self.frame(level);
+        assumptions.push(outside);
+        assumptions.extend(self.encoding.cube(&self.next, cube));
+        let result = self
+            .solver
+            .solve(&assumptions)
+            .then(|| self.encoding.state(&self.solver, &self.now));
+        self.solver.add_clause(&[!outside]);
+        result
+    }
+
+    /// Drop literals of a cube blocked at `level` while it stays so.
+    fn generalize(&mut self, level: usize, mut cube: Cube) -> Cube {
+        let mut i = 0;
+        while i < cube.len() {
+            let mut smaller = cube.clone();
+            smaller.remove(i);
+            if !self.intersects_init(&smaller) && self.predecessor(level - 1, &smaller).is_none() {
+                cube = smaller;
+            } else {
+                i += 1;
+            }
+        }
+        cube
+    }
+
+    fn add_cube(&mut self, level: usize, cube: Cube) {
+        self.encoding
+            .block(&mut self.solver, &self.now, &cube, Some(self.frames[level]));
+        self.cubes[level].push(cube);
+    }
+
+    /// Block the state of `node` at `level`, returning the path to a
+    /// violation of the property when it cannot be.
+    fn block(&mut self, level: usize, node: usize) -> Result<(), Vec<Vec<i64>>> {
+        let mut obligations = BinaryHeap::from([Reverse(Obligation { level, node })]);
+        while let Some(Reverse(obligation)) = obligations.pop() {
+            let (level, node) = (obligation.level, obligation.node);
+            let state = &self.trace[node].0;
+            if *state == self.initial {
+                return Err(self.path(node));
+            }
+            let cube = state.iter().cloned().enumerate().collect::<Cube>();
+            if self.blocked(level, &cube) {
+                continue;
+            }
+            match self.predecessor(level - 1, &cube) {
+                Some(predecessor) => {
+                    self.trace.push((predecessor, Some(node)));
+                    let pushed = self.trace.len() - 1;
+                    obligations.push(Reverse(Obligation {
+                        level: level - 1,
+                        node: pushed,
+                    }));
+                    obligations.push(Reverse(obligation));
+                }
+                None => {
+                    let cube = self.generalize(level, cube);
+                    self.add_cube(level, cube);
+                    // the state may still be reached later
+                    if level + 1 < self.frames.len() {
+                        obligations.push(Reverse(Obligation {
+                            level: level + 1,
+                            node,
+                        }));
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether `cube` is already excluded from frame `level`.
+    fn blocked(&self, level: usize, cube: &[(usize, i64)]) -> bool {
+        self.cubes[level..].iter().flatten().any(|blocked| {
+            blocked
+                .iter()
+                .all(|literal| cube.binary_search(literal).is_ok())
+        })
+    }
+
+    fn path(&self, mut node: usize) -> Vec<Vec<i64>> {
+        let mut path = vec![self.trace[node].0.clone()];
+        while let Some(next) = self.trace[node].1 {
+            path.push(self.trace[next].0.clone());
+            node = next;
+        }
+        path
+    }
+
+    /// Push the cubes of each frame to the next one when they stay
+    /// blocked, returning the level of a frame equal to the next one.
+    fn propagate(&mut self) -> Option<usize> {
+        for level in 1..self.frames.len() - 1 {
+            let mut kept = Vec::new();
+            for cube in self.cubes[level].clone() {
+                let mut assumptions = self.frame(level);
+                assumptions.extend(self.encoding.cube(&self.next, &cube));
+                if self.solver.solve(&assumptions) {
+                    kept.push(cube);
+                } else {
+                    self.add_cube(level + 1, cube);
+                }
+            }
+            self.cubes[level] = kept;
+            if self.cubes[level].is_empty() {
+                return Some(level);
+            }
+        }
+        None
+    }
+
+    fn new_frame(&mut self) {
+        let frame = self.solver.new_var();
+        self.frames.push(frame);
+        self.cubes.push(Vec::new());
+    }
+}
+
+/// Check that `property` holds in every reachable state of `program` by
+/// property directed reachability (IC3).
+///
+/// The frames over-approximate the states reachable in as many steps. The
+/// states of the last frame violating the property are blocked by
+/// recursively blocking their predecessors in the previous frames, which
+/// fails when the initial state is reached. Blocked states are generalized
+/// to cubes, which are then pushed to later frames, until two frames are
+/// equal, which makes them an inductive invariant.
+///
+/// Returns the invariant found, or a path from the initial state to a state
+/// violating the property, or where it cannot be evaluated.
+pub fn ic3(program: &GuardedProgram, property: &Expr) -> Result<Invariant, Vec<Vec<i64>>> {
+    let initial = program.initial_state();
+    if property.holds(&initial) != Ok(true) {
+        return Err(vec![initial]);
+    }
+
+    let encoding = Encoding::new(program);
+    let mut solver = Solver::new();
+    let (now, next) = (encoding.frame(&mut solver), encoding.frame(&mut solver));
+    encoding.transition(&mut solver, &now, &next);
+    let init = solver.new_var();
+    encoding.init(&mut solver, &now, Some(init));
+    let good = encoding.predicate(&mut solver, &now, property);
+
+    let mut ic3 = Ic3 {
+        encoding,
+        solver,
+        now,
+        next,
+        initial,
+        init,
+        frames: Vec::new(),
+        cubes: Vec::new(),
+        trace: Vec::new(),
+    };
+    ic3.new_frame();
+    ic3.new_frame();
+    loop {
+        let last = ic3.frames.len() - 1;
+        loop {
+            let mut assumptions = ic3.frame(last);
+            assumptions.push(!good);
+            if !ic3.solver.solve(&assumptions) {
+                break;
+            }
+            let bad = ic3.encoding.state(&ic3.solver, &ic3.now);
+            ic3.trace.push((bad, None));
+            ic3.block(last, ic3.trace.len() - 1)?;
+        }
+
+        ic3.new_frame();
+        if let Some(level) = ic3.propagate() {
+            return Ok(Invariant {
+                property: property.clone(),
+                cubes: ic3.cubes[level + 1..].concat(),
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::transition_system::TransitionSystem;
+
+    /// Two processes incrementing a shared counter by reading it into a
+    /// register first, so that increments can be lost.
+    const COUNTER: &str = r#"
+    {
+        "variables": [
+            { "name": "x", "min": 0, "max": 2, "init": 0 },
+            { "name": "pc", "min": 0, "max": 2, "init": 0 },
+            { "name": "qc", "min": 0, "max": 2, "init": 0 },
+            { "name": "r", "min": 0, "max": 2, "init": 0 },
+            { "name": "s", "min": 0, "max": 2, "init": 0 }
+        ],
+        "processes": [
+            {
+                "name": "p",
+                "commands": [
+                    { "guard": "pc == 0", "updates": { "r": "x", "pc": "1" } },
+                    { "guard": "pc == 1", "updates": { "x": "r + 1", "pc": "2" } }
+                ]
+            },
+            {
+                "name": "q",
+                "commands": [
+                    { "guard": "qc == 0", "updates": { "s": "x", "qc": "1" } },
+                    { "guard": "qc == 1", "updates": { "x": "s + 1", "qc": "2" } }
+                ]
+            }
+        ],
+        "labels": {}
+    }"#;
+
+    fn reachable(program: &GuardedProgram) -> Vec<Vec<i64>> {
+        let mut states = vec![program.initial_state()];
+        let mut i = 0;
+        while i < states.len() {
+            for next in program.successors(&states[i]) {
+                if !states.contains(&next) {
+                    states.push(next);
+                }
+            }
+            i += 1;
+        }
+        states
+    }
+
+    fn check(program: &GuardedProgram, property: &str) {
+        let property = program.parse(property).unwrap();
+        let states = reachable(program);
+        match ic3(program, &property) {
+            Ok(invariant) => {
+                assert!(states.iter().all(|s| property.holds(s) == Ok(true)));
+                assert!(states.iter().all(|s| invariant.holds(s)));
+                assert!(
+                    invariant.verify(program),
+                    "{} {:?}",
+                    property,
+                    invariant.cubes
+                );
+            }
+            Err(path) => {
+                assert_eq!(path[0], program.initial_state());
+                assert!(path
+                    .windows(2)
+                    .all(|w| program.successors(&w[0]).contains(&w[1])));
+                assert!(property.holds(path.last().unwrap()) == Ok(false));
+                assert!(states.iter().any(|s| property.holds(s) == Ok(false)));
+            }
+        }
+    }
+
+    #[test]
+    fn test_ic3_proves() {
+        let program = GuardedProgram::from_json(COUNTER).unwrap();
+        // the counter reaches 2 only when p reads it after q is done, or the
+        // converse
+        let property = program.parse("x <= 1 || (pc == 2 && qc == 2)").unwrap();
+        let invariant = ic3(&program, &property).unwrap();
+        assert!(invariant.verify(&program));
+        // the property alone is not inductive
+        let weak = Invariant {
+            property,
+            cubes: Vec::new(),
+        };
+        assert!(!weak.verify(&program));
+    }
+
+    #[test]
+    fn test_ic3_counterexample() {
+        let program = GuardedProgram::from_json(COUNTER).unwrap();
+        // an increment is lost when both processes read the counter first
+        let property = program.parse("!(pc == 2 && qc == 2) || x == 2").unwrap();
+        let path = ic3(&program, &property).unwrap_err();
+        assert_eq!(path[0], program.initial_state());
+        assert!(path
+            .windows(2)
+            .all(|w| program.successors(&w[0]).contains(&w[1])));
+        assert_eq!(path.last().unwrap()[..3], [1, 2, 2]);
+    }
+
+    #[test]
+    fn test_ic3_verdicts() {
+        let program = GuardedProgram::from_json(COUNTER).unwrap();
+        for property in [
+            "x <= 2",
+            "x >= 1 || pc + qc < 4",
+            "pc < 2 || r + 1 == x || qc == 2",
+            "r <= s || pc == 0 || qc == 0",
+            "x < 2",
+            "r == 0",
+            "x + r + s < 5",
+        ] {
+            check(&program, property);
+        }
+    }
+}
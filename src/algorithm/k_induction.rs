@@ -0,0 +1,182 @@
+use crate::{
+    expr::Expr,
+    model::{
+        guarded::GuardedProgram,
+        symbolic::{Encoding, Frame},
+    },
+    sat::{Lit, Solver},
+};
+
+/// Paths of a program unrolled in a solver, with the property encoded in
+/// each of their states.
+struct Unrolling<'a> {
+    encoding: Encoding<'a>,
+    property: &'a Expr,
+    solver: Solver,
+    frames: Vec<Frame>,
+    good: Vec<Lit>,
+    simple: bool,
+}
+
+impl<'a> Unrolling<'a> {
+    /// Paths from the initial state when `initial`, or from any state.
+    fn new(program: &'a GuardedProgram, property: &'a Expr, initial: bool, simple: bool) -> Self {
+        let encoding = Encoding::new(program);
+        let mut solver = Solver::new();
+        let frame = encoding.frame(&mut solver);
+        if initial {
+            encoding.init(&mut solver, &frame, None);
+        }
+        let good = encoding.predicate(&mut solver, &frame, property);
+        Unrolling {
+            encoding,
+            property,
+            solver,
+            frames: vec![frame],
+            good: vec![good],
+            simple,
+        }
+    }
+
+    /// Add a step to the paths, the states of simple paths being pairwise
+    /// distinct.
+    fn extend(&mut self) {
+        let frame = self.encoding.frame(&mut self.solver);
+        let last = self.frames.last().unwrap();
+        self.encoding.transition(&mut self.solver, last, &frame);
+        if self.simple {
+            for previous in &self.frames {
+                self.encoding.distinct(&mut self.solver, previous, &frame);
+            }
+        }
+        let good = self
+            .encoding
+            .predicate(&mut self.solver, &frame, self.property);
+        self.frames.push(frame);
+        self.good.push(good);
+    }
+
+    /// A path violating the property in its last state, after which the
+    /// property is assumed to hold there.
+    fn violated(&mut self) -> Option<Vec<Vec<i64>>> {
+        let good = *self.good.last().unwrap();
+        let path = self.solver.solve(&[!good]).then(|| {
+            self.frames
+                .iter()
+                .map(|frame| self.encoding.state(&self.solver, frame))
+                .collect()
+        });
+        self.solver.add_clause(&[good]);
+        path
+    }
+}
+
+/// Look for a path of at most `bound` steps from the initial state of
+/// `program` to a state violating `property`.
+///
+/// Returns the shortest one found.
+pub fn bmc(program: &GuardedProgram, property: &Expr, bound: usize) -> Result<(), Vec<Vec<i64>>> {
+    let mut base = Unrolling::new(program, property, true, false);
+    for depth in 0..=bound {
+        if depth > 0 {
+            base.extend();
+        }
+        if let Some(path) = base.violated() {
+            return Err(path);
+        }
+    }
+    Ok(())
+}
+
+/// Prove that `property` holds in every reachable state of `program` by
+/// k-induction.
+///
+/// For increasing k, the base case looks for a violation of the property
+/// in k steps from the initial state, as bounded model checking does, and
+/// the induction step for a simple path of k states satisfying the property
+/// followed by one violating it. The property holds when there is no such
+/// path and no violation in less than k steps. As simple paths are no longer
+/// than the number of states, this terminates.
+///
+/// Returns the k for which the induction step succeeded, or the path to a
+/// violation found by the base case.
+pub fn k_induction(program: &GuardedProgram, property: &Expr) -> Result<usize, Vec<Vec<i64>>> {
+    let mut base = Unrolling::new(program, property, true, false);
+    let mut step = Unrolling::new(program, property, false, true);
+    for k in 0.. {
+        if k > 0 {
+            base.extend();
+            step.extend();
+        }
+        if step.violated().is_none() {
+            return Ok(k);
+        }
+        if let Some(path) = base.violated() {
+            return Err(path);
+        }
+    }
+    unreachable!()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::transition_system::TransitionSystem;
+
+    /// A toggling counter, with an unreachable chain of states leading to 5
+    /// and an unreachable loop on 6 leading to it too.
+    const CHAIN: &str = r#"
+    {
+        "variables": [
+            { "name": "x", "min": 0, "max": 6, "init": 0 },
+            { "name": "y", "min": 0, "max": 1, "init": 0 }
+        ],
+        "processes": [
+            {
+                "name": "p",
+                "commands": [
+                    { "guard": "x == 0", "updates": { "x": "1" } },
+                    { "guard": "x == 1", "updates": { "x": "0", "y": "1 - y" } },
+                    { "guard": "x >= 2 && x < 5", "updates": { "x": "x + 1" } },
+                    { "guard": "x == 6", "updates": { "x": "6" } },
+                    { "guard": "x == 6", "updates": { "x": "5" } }
+                ]
+            }
+        ],
+        "labels": {}
+    }"#;
+
+    fn is_path(program: &GuardedProgram, path: &[Vec<i64>]) -> bool {
+        path[0] == program.initial_state()
+            && path
+                .windows(2)
+                .all(|w| program.successors(&w[0]).contains(&w[1]))
+    }
+
+    #[test]
+    fn test_k_induction_proves() {
+        let program = GuardedProgram::from_json(CHAIN).unwrap();
+        let property = program.parse("x != 5").unwrap();
+        // 2, 3, 4 and 5 form the longest simple path to a violation
+        assert_eq!(k_induction(&program, &property), Ok(4));
+        assert_eq!(bmc(&program, &property, 10), Ok(()));
+
+        let property = program.parse("x <= 1").unwrap();
+        assert_eq!(k_induction(&program, &property), Ok(1));
+        let property = program.parse("y <= 1").unwrap();
+        assert_eq!(k_induction(&program, &property), Ok(0));
+    }
+
+    #[test]
+    fn test_k_induction_counterexample() {
+        let program = GuardedProgram::from_json(CHAIN).unwrap();
+        let property = program.parse("x == 0 || y == 0").unwrap();
+        let path = k_induction(&program, &property).unwrap_err();
+        assert_eq!(path.len(), 4);
+        assert!(is_path(&program, &path));
+        assert!(property.holds(path.last().unwrap()) == Ok(false));
+
+        assert_eq!(bmc(&program, &property, 2), Ok(()));
+        assert_eq!(bmc(&program, &property, 3), Err(path));
+    }
+}
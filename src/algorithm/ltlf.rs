@@ -0,0 +1,162 @@
+use std::collections::{hash_map::Entry, HashMap, HashSet, VecDeque};
+
+use crate::{
+    automata::dfa::Dfa,
+    ltl::LTLFormulae,
+    model::{kripke::Kripke, transition_system::TransitionSystem},
+};
+
+/// The positions of `trace` where `formula` holds, on finite traces.
+fn positions(trace: &[HashSet<String>], formula: &LTLFormulae) -> Vec<bool> {
+    let n = trace.len();
+    match formula {
+        LTLFormulae::True => vec![true; n],
+        LTLFormulae::Atomic(a) => trace.iter().map(|labels| labels.contains(a)).collect(),
+        LTLFormulae::Not(f) => positions(trace, f).into_iter().map(|v| !v).collect(),
+        LTLFormulae::And(f, g) => {
+            let g = positions(trace, g);
+            positions(trace, f)
+                .into_iter()
+                .zip(g)
+                .map(|(f, g)| f && g)
+                .collect()
+        }
+        // there is no next position at the end
+        LTLFormulae::Next(f) => {
+            let mut values = positions(trace, f);
+            values.remove(0);
+            values.push(false);
+            values
+        }
+        LTLFormulae::Until(f, g) => {
+            let (f, g) = (positions(trace, f), positions(trace, g));
+            let mut values = vec![false; n];
+            for i in (0..n).rev() {
+                values[i] = g[i] || (f[i] && i + 1 < n && values[i + 1]);
+            }
+            values
+        }
+        LTLFormulae::BoundedUntil(_, _, _, _) => positions(trace, &formula.unroll_bounded()),
+        LTLFormulae::Yesterday(f) => {
+            let mut values = positions(trace, f);
+            values.pop();
+            values.insert(0, false);
+            values
+        }
+        LTLFormulae::Since(f, g) => {
+            let (f, g) = (positions(trace, f), positions(trace, g));
+            let mut values = vec![false; n];
+            for i in 0..n {
+                values[i] = g[i] || (f[i] && i > 0 && values[i - 1]);
+            }
+            values
+        }
+    }
+}
+
+/// Whether the finite trace of labels `trace` satisfies `formula`, with the
+/// semantics of LTLf: the next operator does not hold in the last position,
+/// and `F f` requires `f` before the end. No formula holds on the empty
+/// trace.
+pub fn holds_finite(trace: &[HashSet<String>], formula: &LTLFormulae) -> bool {
+    !trace.is_empty() && positions(trace, formula)[0]
+}
+
+/// Check that every finite path of `model` from its initial state to one of
+/// the `terminal` states satisfies the future formula `formula`, with the
+/// semantics of LTLf. A path may go through a terminal state without ending
+/// there.
+///
+/// The model is explored with an automaton for the negation of `formula`,
+/// returning a shortest path violating it, if any. Fails when `formula` has
+/// no automaton, such as with past operators.
+pub fn check_ltlf(
+    model: &Kripke,
+    formula: &LTLFormulae,
+    terminal: &HashSet<usize>,
+) -> Result<Option<Vec<usize>>, String> {
+    let dfa = Dfa::from_ltlf(&LTLFormulae::Not(Box::new(formula.clone())))?;
+    let step = |q: usize, state: &usize| dfa.transitions[q][dfa.letter(&model.labels(state))];
+
+    let initial = (model.initial_state, step(dfa.initial, &model.initial_state));
+    let mut parents: HashMap<(usize, usize), Option<(usize, usize)>> =
+        HashMap::from([(initial, None)]);
+    let mut queue = VecDeque::from([initial]);
+    while let Some((state, q)) = queue.pop_front() {
+        if terminal.contains(&state) && dfa.accepting.contains(&q) {
+            let mut path = vec![state];
+            let mut current = (state, q);
+            while let Some(Some(parent)) = parents.get(&current) {
+                path.push(parent.0);
+                current = *parent;
+            }
+            path.reverse();
+            return Ok(Some(path));
+        }
+        for next in model.successors(&state) {
+            let product = (next, step(q, &next));
+            if let Entry::Vacant(entry) = parents.entry(product) {
+                entry.insert(Some((state, q)));
+                queue.push_back(product);
+            }
+        }
+    }
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::ltl_parser::parse_ltl;
+
+    #[test]
+    fn test_check_ltlf() {
+        // a workflow is submitted, reviewed until approved or rejected, and
+        // approved ones are archived
+        let data = r#"
+        {
+            "states": [
+                { "id": 0, "name": "submit", "labels": ["submitted"], "transit_to": [1] },
+                { "id": 1, "name": "review", "labels": ["review"], "transit_to": [1, 2, 3] },
+                { "id": 2, "name": "approve", "labels": ["approved"], "transit_to": [4] },
+                { "id": 3, "name": "reject", "labels": ["rejected"], "transit_to": [] },
+                { "id": 4, "name": "archive", "labels": ["archived"], "transit_to": [] }
+            ],
+            "initial_state": 0
+        }"#;
+        let model = Kripke::from_json(data).unwrap();
+        let terminal = HashSet::from([3, 4]);
+        let check =
+            |formula: &str| check_ltlf(&model, &parse_ltl(formula).unwrap(), &terminal).unwrap();
+
+        assert_eq!(check("F (approved || rejected)"), None);
+        assert_eq!(check("G (approved -> X archived)"), None);
+        assert_eq!(check("review U (approved || rejected) || submitted"), None);
+        // on finite traces, the last position has no next one
+        assert_eq!(check("F !X true"), None);
+        assert!(check("G X true").is_some());
+        assert_eq!(check("F archived"), Some(vec![0, 1, 3]));
+        assert_eq!(check("!F (review && X review)"), Some(vec![0, 1, 1, 3]));
+
+        // ending in the review state is not a complete workflow
+        let review = HashSet::from([1]);
+        let result = check_ltlf(&model, &parse_ltl("F archived").unwrap(), &review).unwrap();
+        assert_eq!(result, Some(vec![0, 1]));
+
+        // past operators have no automaton here
+        let past = parse_ltl("G (approved -> Y review)").unwrap();
+        assert!(check_ltlf(&model, &past, &terminal).is_err());
+
+        let trace = [
+            HashSet::from(["submitted".to_string()]),
+            HashSet::from(["review".to_string()]),
+        ];
+        assert!(holds_finite(&trace, &parse_ltl("G !approved").unwrap()));
+        assert!(!holds_finite(&trace, &parse_ltl("X X true").unwrap()));
+        assert!(holds_finite(
+            &trace,
+            &parse_ltl("F (review && Y submitted)").unwrap()
+        ));
+        assert!(!holds_finite(&[], &parse_ltl("true").unwrap()));
+    }
+}
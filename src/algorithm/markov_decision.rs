@@ -0,0 +1,975 @@
+use std::collections::{HashMap, HashSet};
+
+use petgraph::graph::DiGraph;
+use serde::Serialize;
+
+use crate::{
+    algorithm::{
+        explicit_state::explicit_state_checking,
+        probabilistic::{backward, Method, Numerics},
+    },
+    model::mdp::Mdp,
+    pctl::{Bound, PCTLFormulae, PathFormulae, RewardFormulae},
+};
+
+/// Whether the scheduler maximizes or minimizes the probability.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Optimum {
+    Max,
+    Min,
+}
+
+impl Optimum {
+    fn opposite(self) -> Optimum {
+        match self {
+            Optimum::Max => Optimum::Min,
+            Optimum::Min => Optimum::Max,
+        }
+    }
+
+    /// The best of the `candidates`, values with the action giving them.
+    fn best(self, candidates: impl Iterator<Item = (usize, f64)>) -> Option<(usize, f64)> {
+        candidates.fold(None, |best, (a, v)| match (self, best) {
+            (Optimum::Max, Some((_, b))) if v <= b => best,
+            (Optimum::Min, Some((_, b))) if v >= b => best,
+            _ => Some((a, v)),
+        })
+    }
+}
+
+/// The optimal probabilities of a path formula, or the optimal expected
+/// rewards, with a scheduler achieving them.
+#[derive(Debug, Clone, Serialize)]
+pub struct Solution {
+    /// The value from each state, from below.
+    pub lower: HashMap<usize, f64>,
+    /// The probability from each state from above, with interval iteration.
+    /// Rewards are not bounded from above.
+    pub upper: Option<HashMap<usize, f64>>,
+    /// The name of the action chosen in each state.
+    ///
+    /// The scheduler is memoryless. For bounded formulae, which may need
+    /// memory, it is the choice when all the steps are left.
+    pub scheduler: HashMap<usize, String>,
+}
+
+/// A decision process over dense state indices.
+struct Process {
+    ids: Vec<usize>,
+    names: Vec<Vec<String>>,
+    actions: Vec<Vec<Vec<(usize, f64)>>>,
+    /// The expected reward of a step with each action.
+    rewards: Vec<Vec<f64>>,
+    pre: Vec<Vec<usize>>,
+}
+
+impl Process {
+    fn new(mdp: &Mdp) -> Process {
+        let mut ids = mdp.kripke.states.keys().cloned().collect::<Vec<usize>>();
+        ids.sort();
+        let index = ids
+            .iter()
+            .enumerate()
+            .map(|(i, id)| (*id, i))
+            .collect::<HashMap<usize, usize>>();
+        let mut names = vec![Vec::new(); ids.len()];
+        let mut actions = vec![Vec::new(); ids.len()];
+        let mut rewards = vec![Vec::new(); ids.len()];
+        let mut pre = vec![Vec::new(); ids.len()];
+        for (i, id) in ids.iter().enumerate() {
+            let state_reward = mdp.state_rewards.get(id).cloned().unwrap_or(0.0);
+            for action in &mdp.actions[id] {
+                let mut row = action
+                    .distribution
+                    .iter()
+                    .map(|(to, p)| (index[to], *p))
+                    .collect::<Vec<(usize, f64)>>();
+                row.sort_by_key(|(to, _)| *to);
+                for (to, _) in &row {
+                    if !pre[*to].contains(&i) {
+                        pre[*to].push(i);
+                    }
+                }
+                names[i].push(action.name.clone());
+                actions[i].push(row);
+                rewards[i].push(state_reward + action.reward());
+            }
+        }
+        Process {
+            ids,
+            names,
+            actions,
+            rewards,
+            pre,
+        }
+    }
+
+    fn indicator(&self, states: &HashSet<usize>) -> Vec<bool> {
+        self.ids.iter().map(|id| states.contains(id)).collect()
+    }
+
+    fn by_id<T>(&self, values: Vec<T>) -> HashMap<usize, T> {
+        self.ids.iter().cloned().zip(values).collect()
+    }
+
+    fn solution(&self, values: Values) -> Solution {
+        let scheduler = values
+            .choice
+            .iter()
+            .enumerate()
+            .map(|(s, a)| self.names[s][*a].clone())
+            .collect();
+        Solution {
+            lower: self.by_id(values.lower),
+            upper: values.upper.map(|upper| self.by_id(upper)),
+            scheduler: self.by_id(scheduler),
+        }
+    }
+
+    /// The expected value of `x` after action `a` of state `s`.
+    fn value(&self, s: usize, a: usize, x: &[f64]) -> f64 {
+        self.actions[s][a].iter().map(|(t, p)| p * x[*t]).sum()
+    }
+
+    /// The expected reward of action `a` of state `s`, followed by `x`.
+    fn gain(&self, s: usize, a: usize, x: &[f64]) -> f64 {
+        self.rewards[s][a] + self.value(s, a, x)
+    }
+
+    /// The best action of `s` for the values `x`, with its value.
+    fn best(&self, optimum: Optimum, s: usize, x: &[f64]) -> (usize, f64) {
+        optimum
+            .best((0..self.actions[s].len()).map(|a| (a, self.value(s, a, x))))
+            .unwrap()
+    }
+
+    /// The best action of `s` for the rewards followed by `x`, with its
+    /// reward.
+    fn best_gain(&self, optimum: Optimum, s: usize, x: &[f64]) -> (usize, f64) {
+        optimum
+            .best((0..self.actions[s].len()).map(|a| (a, self.gain(s, a, x))))
+            .unwrap()
+    }
+
+    /// The actions of each state, as pairs of a state and an action.
+    fn own_actions(&self) -> Vec<Vec<(usize, usize)>> {
+        (0..self.ids.len())
+            .map(|s| (0..self.actions[s].len()).map(|a| (s, a)).collect())
+            .collect()
+    }
+}
+
+/// The values computed over the dense indices, with the index of the action
+/// chosen in each state.
+struct Values {
+    lower: Vec<f64>,
+    upper: Option<Vec<f64>>,
+    choice: Vec<usize>,
+}
+
+/// The states from which every scheduler reaches a `psi` state through
+/// `phi` states with a positive probability.
+fn forced(process: &Process, phi: &[bool], psi: &[bool]) -> Vec<bool> {
+    let mut reached = psi.to_vec();
+    loop {
+        let mut changed = false;
+        for s in 0..reached.len() {
+            if !reached[s]
+                && phi[s]
+                && process.actions[s]
+                    .iter()
+                    .all(|row| row.iter().any(|(t, _)| reached[*t]))
+            {
+                reached[s] = true;
+                changed = true;
+            }
+        }
+        if !changed {
+            return reached;
+        }
+    }
+}
+
+/// The states reaching a `target` state with probability 1 under some
+/// scheduler.
+///
+/// The candidates are narrowed down to the states reaching the target with
+/// actions staying among them, until none is removed.
+fn almost_sure(process: &Process, target: &[bool]) -> Vec<bool> {
+    let mut candidates = vec![true; target.len()];
+    loop {
+        let mut reached = target.to_vec();
+        loop {
+            let mut changed = false;
+            for s in 0..reached.len() {
+                if !reached[s]
+                    && candidates[s]
+                    && process.actions[s].iter().any(|row| {
+                        row.iter().all(|(t, _)| candidates[*t])
+                            && row.iter().any(|(t, _)| reached[*t])
+                    })
+                {
+                    reached[s] = true;
+                    changed = true;
+                }
+            }
+            if !changed {
+                break;
+            }
+        }
+        if reached == candidates {
+            return reached;
+        }
+        candidates = reached;
+    }
+}
+
+/// The maximal end components within `states` made of the `allowed`
+/// actions, as the component of each state in one of them.
+///
+/// The actions leaving the strongly connected components are removed, then
+/// the states left without actions, until the components are closed.
+fn end_components(
+    process: &Process,
+    states: &[bool],
+    allowed: impl Fn(usize, usize) -> bool,
+) -> Vec<Option<usize>> {
+    let mut inside = states.to_vec();
+    let mut allowed = process
+        .actions
+        .iter()
+        .enumerate()
+        .map(|(s, actions)| {
+            actions
+                .iter()
+                .enumerate()
+                .map(|(a, row)| allowed(s, a) && row.iter().all(|(t, _)| states[*t]))
+                .collect::<Vec<bool>>()
+        })
+        .collect::<Vec<_>>();
+    loop {
+        let mut graph = DiGraph::<(), ()>::new();
+        let nodes = (0..inside.len())
+            .map(|_| graph.add_node(()))
+            .collect::<Vec<_>>();
+        for s in (0..inside.len()).filter(|s| inside[*s]) {
+            for (a, row) in process.actions[s].iter().enumerate() {
+                if allowed[s][a] {
+                    for (t, _) in row {
+                        graph.add_edge(nodes[s], nodes[*t], ());
+                    }
+                }
+            }
+        }
+        let mut component = vec![None; inside.len()];
+        for (c, scc) in petgraph::algo::tarjan_scc(&graph).into_iter().enumerate() {
+            for node in scc {
+                if inside[node.index()] {
+                    component[node.index()] = Some(c);
+                }
+            }
+        }
+
+        let mut changed = false;
+        for s in 0..inside.len() {
+            if !inside[s] {
+                continue;
+            }
+            for (a, row) in process.actions[s].iter().enumerate() {
+                if allowed[s][a] && row.iter().any(|(t, _)| component[*t] != component[s]) {
+                    allowed[s][a] = false;
+                    changed = true;
+                }
+            }
+            if !allowed[s].contains(&true) {
+                inside[s] = false;
+                changed = true;
+            }
+        }
+        if !changed {
+            return component;
+        }
+    }
+}
+
+/// The actions of each state when the maximal end components of the
+/// `allowed` actions within `states` are collapsed, as pairs of a state and
+/// an action.
+///
+/// Each component is seen as a single state, with the actions of its states
+/// leaving it, as the value of staying in it forever is not the one sought.
+fn collapse(
+    process: &Process,
+    states: &[bool],
+    allowed: impl Fn(usize, usize) -> bool,
+) -> Vec<Vec<(usize, usize)>> {
+    let own = process.own_actions();
+    let component = end_components(process, states, allowed);
+    let mut leaving = HashMap::<usize, Vec<(usize, usize)>>::new();
+    for s in 0..states.len() {
+        if let Some(c) = component[s] {
+            let actions = own[s].iter().filter(|(s, a)| {
+                process.actions[*s][*a]
+                    .iter()
+                    .any(|(t, _)| component[*t] != Some(c))
+            });
+            leaving.entry(c).or_default().extend(actions);
+        }
+    }
+    (0..states.len())
+        .map(|s| match component[s] {
+            Some(c) => leaving[&c].clone(),
+            None => own[s].clone(),
+        })
+        .collect()
+}
+
+/// The optimal probability of `phi U psi` from each state.
+fn until(
+    process: &Process,
+    optimum: Optimum,
+    phi: &[bool],
+    psi: &[bool],
+    numerics: &Numerics,
+) -> Values {
+    // the states with probability 0: those which cannot reach psi when
+    // maximizing, and those where some scheduler avoids it when minimizing
+    let positive = match optimum {
+        Optimum::Max => backward(&process.pre, psi, phi),
+        Optimum::Min => forced(process, phi, psi),
+    };
+    let maybe = (0..psi.len())
+        .map(|s| positive[s] && !psi[s])
+        .collect::<Vec<bool>>();
+    // when maximizing, a scheduler can stay in an end component forever, so
+    // that iterating from above would not leave 1 there
+    let interval = numerics.method == Method::IntervalIteration;
+    let exits = match (interval, optimum) {
+        (false, _) => Vec::new(),
+        (true, Optimum::Max) => collapse(process, &maybe, |_, _| true),
+        (true, Optimum::Min) => process.own_actions(),
+    };
+
+    let mut lower = psi
+        .iter()
+        .map(|p| if *p { 1.0 } else { 0.0 })
+        .collect::<Vec<f64>>();
+    let mut upper = (0..psi.len())
+        .map(|s| if positive[s] { 1.0 } else { 0.0 })
+        .collect::<Vec<f64>>();
+    for _ in 0..numerics.max_iterations {
+        let previous = lower.clone();
+        let mut change = 0.0_f64;
+        for s in (0..lower.len()).filter(|s| maybe[*s]) {
+            let source = match numerics.method {
+                Method::ValueIteration => &previous,
+                _ => &lower,
+            };
+            let (_, value) = process.best(optimum, s, source);
+            change = change.max((value - lower[s]).abs());
+            lower[s] = value;
+            if interval {
+                upper[s] = optimum
+                    .best(
+                        exits[s]
+                            .iter()
+                            .map(|(u, a)| (0, process.value(*u, *a, &upper))),
+                    )
+                    .map_or(0.0, |(_, v)| v);
+            }
+        }
+        let done = if interval {
+            (0..lower.len()).all(|s| upper[s] - lower[s] < numerics.epsilon)
+        } else {
+            change < numerics.epsilon
+        };
+        if done {
+            break;
+        }
+    }
+
+    let choice = match optimum {
+        Optimum::Max => {
+            let tolerance = numerics.epsilon.sqrt();
+            let choice = (0..lower.len())
+                .map(|s| process.best(optimum, s, &lower).0)
+                .collect();
+            attractor(process, psi, &maybe, choice, |s, a| {
+                process.value(s, a, &lower) >= lower[s] - tolerance
+            })
+        }
+        Optimum::Min => (0..lower.len())
+            .map(|s| match (maybe[s], positive[s]) {
+                (true, _) => process.best(optimum, s, &lower).0,
+                // stay where psi cannot be reached
+                (false, false) => process.actions[s]
+                    .iter()
+                    .position(|row| row.iter().all(|(t, _)| !positive[*t]))
+                    .unwrap_or(0),
+                (false, true) => 0,
+            })
+            .collect(),
+    };
+    Values {
+        lower,
+        upper: interval.then_some(upper),
+        choice,
+    }
+}
+
+/// The choices of a scheduler reaching `target` from the `states`, among
+/// the `optimal` actions.
+///
+/// Choosing any optimal action could stay in an end component forever, so
+/// the states take the optimal actions getting closer to `target`,
+/// backwards from it. The other states keep their `choice`. The values are
+/// only approximate, so an action is optimal when its value is close enough
+/// to the optimum.
+fn attractor(
+    process: &Process,
+    target: &[bool],
+    states: &[bool],
+    mut choice: Vec<usize>,
+    optimal: impl Fn(usize, usize) -> bool,
+) -> Vec<usize> {
+    let mut attracted = target.to_vec();
+    loop {
+        let mut changed = false;
+        for s in 0..attracted.len() {
+            if !states[s] || attracted[s] {
+                continue;
+            }
+            let closer = (0..process.actions[s].len()).find(|a| {
+                optimal(s, *a) && process.actions[s][*a].iter().any(|(t, _)| attracted[*t])
+            });
+            if let Some(a) = closer {
+                choice[s] = a;
+                attracted[s] = true;
+                changed = true;
+            }
+        }
+        if !changed {
+            return choice;
+        }
+    }
+}
+
+/// The optimal probability of `phi U<=k psi` from each state.
+fn bounded_until(
+    process: &Process,
+    optimum: Optimum,
+    phi: &[bool],
+    psi: &[bool],
+    k: usize,
+) -> Values {
+    let mut x = psi
+        .iter()
+        .map(|p| if *p { 1.0 } else { 0.0 })
+        .collect::<Vec<f64>>();
+    let mut choice = vec![0; x.len()];
+    for _ in 0..k {
+        let previous = x.clone();
+        for s in (0..x.len()).filter(|s| phi[*s] && !psi[*s]) {
+            (choice[s], x[s]) = process.best(optimum, s, &previous);
+        }
+    }
+    Values {
+        lower: x,
+        upper: None,
+        choice,
+    }
+}
+
+/// The optimal expected reward accumulated until reaching a `target` state,
+/// from each state.
+fn reachability_reward(
+    process: &Process,
+    optimum: Optimum,
+    target: &[bool],
+    numerics: &Numerics,
+) -> Values {
+    // the reward is infinite where the target may be missed: under some
+    // scheduler when maximizing, and under every scheduler when minimizing
+    let all = vec![true; target.len()];
+    let infinite = match optimum {
+        Optimum::Max => {
+            let avoided = forced(process, &all, target)
+                .iter()
+                .map(|f| !f)
+                .collect::<Vec<bool>>();
+            let before = target.iter().map(|t| !t).collect::<Vec<bool>>();
+            backward(&process.pre, &avoided, &before)
+        }
+        Optimum::Min => almost_sure(process, target).iter().map(|a| !a).collect(),
+    };
+    let finite = (0..target.len())
+        .map(|s| !infinite[s] && !target[s])
+        .collect::<Vec<bool>>();
+    // when minimizing, staying forever in an end component without reward
+    // would look free, while it never reaches the target
+    let exits = match optimum {
+        Optimum::Max => process.own_actions(),
+        Optimum::Min => collapse(process, &finite, |s, a| process.rewards[s][a] == 0.0),
+    };
+
+    let mut x = infinite
+        .iter()
+        .map(|i| if *i { f64::INFINITY } else { 0.0 })
+        .collect::<Vec<f64>>();
+    for _ in 0..numerics.max_iterations {
+        let previous = x.clone();
+        let mut change = 0.0_f64;
+        for s in (0..x.len()).filter(|s| finite[*s]) {
+            let source = match numerics.method {
+                Method::ValueIteration => &previous,
+                _ => &x,
+            };
+            let (_, value) = optimum
+                .best(
+                    exits[s]
+                        .iter()
+                        .map(|(u, a)| (0, process.gain(*u, *a, source))),
+                )
+                .unwrap();
+            change = change.max((value - x[s]).abs());
+            x[s] = value;
+        }
+        if change < numerics.epsilon {
+            break;
+        }
+    }
+
+    let choice = match optimum {
+        // the scheduler minimizing the probability of reaching the target
+        // misses it where the reward is infinite
+        Optimum::Max => {
+            let eventually = until(process, Optimum::Min, &all, target, numerics);
+            (0..x.len())
+                .map(|s| match infinite[s] {
+                    true => eventually.choice[s],
+                    false => process.best_gain(optimum, s, &x).0,
+                })
+                .collect()
+        }
+        Optimum::Min => {
+            let tolerance = numerics.epsilon.sqrt();
+            let choice = (0..x.len())
+                .map(|s| process.best_gain(optimum, s, &x).0)
+                .collect();
+            attractor(process, target, &finite, choice, |s, a| {
+                process.gain(s, a, &x) <= x[s] + tolerance
+            })
+        }
+    };
+    Values {
+        lower: x,
+        upper: None,
+        choice,
+    }
+}
+
+/// The optimal expected reward accumulated in the first `k` steps, from
+/// each state.
+fn cumulative_reward(process: &Process, optimum: Optimum, k: usize) -> Values {
+    let mut x = vec![0.0; process.ids.len()];
+    let mut choice = vec![0; x.len()];
+    for _ in 0..k {
+        let previous = x.clone();
+        for s in 0..x.len() {
+            (choice[s], x[s]) = process.best_gain(optimum, s, &previous);
+        }
+    }
+    Values {
+        lower: x,
+        upper: None,
+        choice,
+    }
+}
+
+/// The optimal probability of the paths from each state satisfying `path`,
+/// with a scheduler achieving it.
+pub fn optimal_probabilities(
+    model: &mut Mdp,
+    optimum: Optimum,
+    path: &PathFormulae,
+    numerics: &Numerics,
+) -> Result<Solution, String> {
+    let process = Process::new(model);
+    let mut indicator = |f: &PCTLFormulae| {
+        let states = satisfying_states(model, f, numerics)?;
+        Ok::<_, String>(process.indicator(&states))
+    };
+    let exact = |values: Values| Values {
+        upper: (numerics.method == Method::IntervalIteration).then(|| values.lower.clone()),
+        ..values
+    };
+    let values = match path {
+        PathFormulae::Next(f) => {
+            let phi = indicator(f)?
+                .iter()
+                .map(|p| if *p { 1.0 } else { 0.0 })
+                .collect::<Vec<f64>>();
+            let (choice, lower) = (0..phi.len())
+                .map(|s| process.best(optimum, s, &phi))
+                .unzip();
+            exact(Values {
+                lower,
+                upper: None,
+                choice,
+            })
+        }
+        PathFormulae::Until(f, g, bound) => {
+            let (phi, psi) = (indicator(f)?, indicator(g)?);
+            match bound {
+                Some(k) => exact(bounded_until(&process, optimum, &phi, &psi, *k)),
+                None => until(&process, optimum, &phi, &psi, numerics),
+            }
+        }
+        PathFormulae::Globally(f, bound) => {
+            // G f holds on the paths where F !f does not, so the scheduler
+            // optimizes F !f the other way
+            let phi = vec![true; process.ids.len()];
+            let psi = indicator(f)?.iter().map(|s| !s).collect::<Vec<bool>>();
+            let opposite = optimum.opposite();
+            let eventually = match bound {
+                Some(k) => exact(bounded_until(&process, opposite, &phi, &psi, *k)),
+                None => until(&process, opposite, &phi, &psi, numerics),
+            };
+            let complement = |x: &Vec<f64>| x.iter().map(|p| 1.0 - p).collect::<Vec<f64>>();
+            Values {
+                lower: complement(eventually.upper.as_ref().unwrap_or(&eventually.lower)),
+                upper: eventually
+                    .upper
+                    .as_ref()
+                    .map(|_| complement(&eventually.lower)),
+                choice: eventually.choice,
+            }
+        }
+    };
+
+    Ok(process.solution(values))
+}
+
+/// The optimal expected reward of `rewards` from each state, with a
+/// scheduler achieving it.
+pub fn optimal_rewards(
+    model: &mut Mdp,
+    optimum: Optimum,
+    rewards: &RewardFormulae,
+    numerics: &Numerics,
+) -> Result<Solution, String> {
+    let process = Process::new(model);
+    let values = match rewards {
+        RewardFormulae::Reachability(f) => {
+            let target = process.indicator(&satisfying_states(model, f, numerics)?);
+            reachability_reward(&process, optimum, &target, numerics)
+        }
+        RewardFormulae::Cumulative(k) => cumulative_reward(&process, optimum, *k),
+    };
+    Ok(process.solution(values))
+}
+
+/// The states satisfying `formula` under every scheduler.
+///
+/// An upper bound on a probability or a reward is checked against the
+/// maximal value, and a lower bound against the minimal one.
+///
+/// Fails if the formula is a query, has a steady-state operator, or a CTL
+/// formula which cannot be checked, see [`explicit_state_checking`].
+pub fn satisfying_states(
+    model: &mut Mdp,
+    formula: &PCTLFormulae,
+    numerics: &Numerics,
+) -> Result<HashSet<usize>, String> {
+    let all = || {
+        model
+            .kripke
+            .states
+            .keys()
+            .cloned()
+            .collect::<HashSet<usize>>()
+    };
+    Ok(match formula {
+        PCTLFormulae::Ctl(f) => {
+            let label = explicit_state_checking(&mut model.kripke, f.clone())?;
+            model
+                .kripke
+                .get_state_with_label(label)
+                .into_iter()
+                .collect()
+        }
+        PCTLFormulae::Not(f) => {
+            let states = all();
+            let sat = satisfying_states(model, f, numerics)?;
+            states.difference(&sat).cloned().collect()
+        }
+        PCTLFormulae::And(f, g) => {
+            let sat = satisfying_states(model, f, numerics)?;
+            let other = satisfying_states(model, g, numerics)?;
+            sat.intersection(&other).cloned().collect()
+        }
+        PCTLFormulae::Or(f, g) => {
+            let mut sat = satisfying_states(model, f, numerics)?;
+            sat.extend(satisfying_states(model, g, numerics)?);
+            sat
+        }
+        PCTLFormulae::Probability(bound, path) => {
+            let optimum = worst(formula, bound)?;
+            let solution = optimal_probabilities(model, optimum, path, numerics)?;
+            within(bound, solution.lower)
+        }
+        PCTLFormulae::Reward(bound, rewards) => {
+            let optimum = worst(formula, bound)?;
+            let solution = optimal_rewards(model, optimum, rewards, numerics)?;
+            within(bound, solution.lower)
+        }
+        PCTLFormulae::SteadyState(..) => {
+            return Err(format!(
+                "{}: steady-state probabilities are not defined for decision processes",
+                formula
+            ))
+        }
+    })
+}
+
+/// The scheduler most likely to violate `bound`, the bound of `formula`.
+fn worst(formula: &PCTLFormulae, bound: &Bound) -> Result<Optimum, String> {
+    match bound {
+        Bound::Less(_) | Bound::LessEq(_) => Ok(Optimum::Max),
+        Bound::Greater(_) | Bound::GreaterEq(_) => Ok(Optimum::Min),
+        _ => Err(format!("{} is a query, which has no truth value", formula)),
+    }
+}
+
+fn within(bound: &Bound, values: HashMap<usize, f64>) -> HashSet<usize> {
+    values
+        .into_iter()
+        .filter(|(_, v)| bound.holds(*v))
+        .map(|(s, _)| s)
+        .collect()
+}
+
+/// Check whether `formula` holds in the initial state of `model`, under
+/// every scheduler.
+pub fn pctl_checking(
+    model: &mut Mdp,
+    formula: &PCTLFormulae,
+    numerics: &Numerics,
+) -> Result<bool, String> {
+    let initial = model.kripke.initial_state;
+    Ok(satisfying_states(model, formula, numerics)?.contains(&initial))
+}
+
+/// The probability or the reward asked by a query, such as `Pmax=? [...]`
+/// or `Rmin=? [...]`, in the initial state of `model`.
+///
+/// Fails if the formula is not such a query, or as [`satisfying_states`]
+/// does.
+pub fn pctl_query(
+    model: &mut Mdp,
+    formula: &PCTLFormulae,
+    numerics: &Numerics,
+) -> Result<f64, String> {
+    let initial = model.kripke.initial_state;
+    let error = || format!("{} is not a query for a maximum or a minimum", formula);
+    let optimum = |bound: &Bound| match bound {
+        Bound::Max => Ok(Optimum::Max),
+        Bound::Min => Ok(Optimum::Min),
+        _ => Err(error()),
+    };
+    let solution = match formula {
+        PCTLFormulae::Probability(bound, path) => {
+            optimal_probabilities(model, optimum(bound)?, path, numerics)?
+        }
+        PCTLFormulae::Reward(bound, rewards) => {
+            optimal_rewards(model, optimum(bound)?, rewards, numerics)?
+        }
+        _ => return Err(error()),
+    };
+    Ok(solution.lower[&initial])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        algorithm::probabilistic, model::dtmc::MarkovError, parser::pctl_parser::parse_pctl,
+    };
+
+    /// A robot in `s0` either gambles, or walks to `s1` where it may wait,
+    /// walk back, or try the safer way to the goal. `s0` and `s1` form an
+    /// end component, in which moving is free.
+    const ROBOT: &str = r#"
+    {
+        "states": [
+            { "id": 0, "name": "s0", "labels": ["init"], "actions": [
+                { "name": "walk", "transit_to": [1], "probabilities": [1.0] },
+                { "name": "gamble", "transit_to": [2, 3], "probabilities": [0.5, 0.5], "rewards": [1, 1] }
+            ] },
+            { "id": 1, "name": "s1", "labels": [], "actions": [
+                { "name": "wait", "transit_to": [1], "probabilities": [1.0] },
+                { "name": "back", "transit_to": [0], "probabilities": [1.0] },
+                { "name": "try", "transit_to": [2, 3], "probabilities": [0.8, 0.2], "rewards": [2, 2] }
+            ] },
+            { "id": 2, "name": "goal", "labels": ["goal"] },
+            { "id": 3, "name": "fail", "labels": ["fail"] }
+        ],
+        "initial_state": 0
+    }"#;
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-6
+    }
+
+    #[test]
+    fn test_optimal_probabilities() {
+        for method in [
+            Method::GaussSeidel,
+            Method::ValueIteration,
+            Method::IntervalIteration,
+        ] {
+            let numerics = Numerics {
+                method,
+                ..Numerics::default()
+            };
+            let mut robot = Mdp::from_json(ROBOT).unwrap();
+            let queries = [
+                ("Pmax=? [F goal]", 0.8),
+                ("Pmin=? [F goal]", 0.0),
+                ("Pmax=? [F fail]", 0.5),
+                ("Pmin=? [G !fail]", 0.5),
+                ("Pmax=? [G !fail]", 1.0),
+                ("Pmax=? [X goal]", 0.5),
+                ("Pmax=? [F<=1 goal]", 0.5),
+                ("Pmax=? [F<=2 goal]", 0.8),
+                ("Pmin=? [!fail U goal]", 0.0),
+            ];
+            for (query, expected) in queries {
+                let query = parse_pctl(query).unwrap();
+                assert!(close(
+                    pctl_query(&mut robot, &query, &numerics).unwrap(),
+                    expected
+                ));
+            }
+            let formula = parse_pctl("P<=0.8 [F goal] && P>=0.5 [G !fail]").unwrap();
+            assert!(pctl_checking(&mut robot, &formula, &numerics).unwrap());
+            let formula = parse_pctl("P>0 [F goal]").unwrap();
+            assert!(!pctl_checking(&mut robot, &formula, &numerics).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_interval_iteration() {
+        let numerics = Numerics {
+            method: Method::IntervalIteration,
+            ..Numerics::default()
+        };
+        let mut robot = Mdp::from_json(ROBOT).unwrap();
+        let path = PathFormulae::Until(
+            PCTLFormulae::Ctl(crate::ctl::CTLFormulae::True),
+            PCTLFormulae::Ctl(crate::ctl::CTLFormulae::Atomic("goal".to_string())),
+            None,
+        );
+        // the end component of s0 and s1 alone would keep the upper bound
+        // at 1
+        let solution = optimal_probabilities(&mut robot, Optimum::Max, &path, &numerics).unwrap();
+        let upper = solution.upper.unwrap();
+        for s in [0, 1] {
+            assert!(solution.lower[&s] <= upper[&s]);
+            assert!(upper[&s] - solution.lower[&s] < numerics.epsilon);
+            assert!(close(upper[&s], 0.8));
+        }
+    }
+
+    #[test]
+    fn test_scheduler() {
+        let numerics = Numerics::default();
+        let mut robot = Mdp::from_json(ROBOT).unwrap();
+        let query = parse_pctl("Pmax=? [F goal]").unwrap();
+        let PCTLFormulae::Probability(_, path) = &query else {
+            unreachable!()
+        };
+        // waiting in s1 is as good as trying, but never reaches the goal
+        let solution = optimal_probabilities(&mut robot, Optimum::Max, path, &numerics).unwrap();
+        assert_eq!(solution.scheduler[&0], "walk");
+        assert_eq!(solution.scheduler[&1], "try");
+        let mut chain = robot.induced(&solution.scheduler).unwrap();
+        let query = parse_pctl("P=? [F goal]").unwrap();
+        assert!(close(
+            probabilistic::pctl_query(&mut chain, &query, &numerics).unwrap(),
+            0.8
+        ));
+
+        let solution = optimal_probabilities(&mut robot, Optimum::Min, path, &numerics).unwrap();
+        assert!(["wait", "back"].contains(&solution.scheduler[&1].as_str()));
+        let json = serde_json::to_value(&solution.scheduler).unwrap();
+        assert_eq!(json["0"], "walk");
+    }
+
+    #[test]
+    fn test_rewards() {
+        let numerics = Numerics::default();
+        let mut robot = Mdp::from_json(ROBOT).unwrap();
+        let queries = [
+            // waiting in s1 is free but never ends
+            ("Rmin=? [F (goal || fail)]", 1.0),
+            ("Rmax=? [F (goal || fail)]", f64::INFINITY),
+            ("Rmin=? [F goal]", f64::INFINITY),
+            ("Rmax=? [C<=3]", 2.0),
+            ("Rmin=? [C<=3]", 0.0),
+        ];
+        for (query, expected) in queries {
+            let query = parse_pctl(query).unwrap();
+            let value = pctl_query(&mut robot, &query, &numerics).unwrap();
+            assert!(value == expected || close(value, expected));
+        }
+        let formula = parse_pctl("R>=1 [F (goal || fail)] && R<=2 [C<=5]").unwrap();
+        assert!(pctl_checking(&mut robot, &formula, &numerics).unwrap());
+
+        let query = parse_pctl("Rmin=? [F (goal || fail)]").unwrap();
+        let PCTLFormulae::Reward(_, rewards) = &query else {
+            unreachable!()
+        };
+        let solution = optimal_rewards(&mut robot, Optimum::Min, rewards, &numerics).unwrap();
+        assert_eq!(solution.scheduler[&0], "gamble");
+        assert_eq!(solution.scheduler[&1], "back");
+        let mut chain = robot.induced(&solution.scheduler).unwrap();
+        let query = parse_pctl("R=? [F (goal || fail)]").unwrap();
+        assert!(close(
+            probabilistic::pctl_query(&mut chain, &query, &numerics).unwrap(),
+            1.0
+        ));
+    }
+
+    #[test]
+    fn test_rejected() {
+        let numerics = Numerics::default();
+        let mut robot = Mdp::from_json(ROBOT).unwrap();
+        let check = |robot: &mut Mdp, formula: &str| {
+            pctl_checking(robot, &parse_pctl(formula).unwrap(), &numerics)
+        };
+        assert!(check(&mut robot, "S>=0.5 [goal]").is_err());
+        assert!(check(&mut robot, "Pmax=? [F goal]").is_err());
+        let query = parse_pctl("P=? [F goal]").unwrap();
+        assert!(pctl_query(&mut robot, &query, &numerics).is_err());
+        let formula = parse_pctl("P>=0.5 [F goal]").unwrap();
+        assert!(pctl_query(&mut robot, &formula, &numerics).is_err());
+
+        let PCTLFormulae::Probability(_, path) = &parse_pctl("Pmax=? [F goal]").unwrap() else {
+            unreachable!()
+        };
+        let mut scheduler = optimal_probabilities(&mut robot, Optimum::Max, path, &numerics)
+            .unwrap()
+            .scheduler;
+        scheduler.insert(1, "fly".to_string());
+        assert!(matches!(
+            robot.induced(&scheduler),
+            Err(MarkovError::UnknownAction(1, _))
+        ));
+        scheduler.remove(&1);
+        assert!(matches!(
+            robot.induced(&scheduler),
+            Err(MarkovError::Unscheduled(1))
+        ));
+    }
+}
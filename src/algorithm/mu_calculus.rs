@@ -0,0 +1,514 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    algorithm::parity::zielonka,
+    model::{
+        kripke::Kripke,
+        parity_game::{ParityGame, Player},
+    },
+    mu_calculus::{MuError, MuFormulae},
+};
+
+/// Evaluation of fixpoints in the style of Emerson and Lei.
+///
+/// A fixpoint nested in fixpoints of the same kind only grows (for `mu`) or
+/// shrinks (for `nu`) while the enclosing ones are computed, so that it is
+/// started again from its last value instead of from scratch. It is only
+/// reset when an enclosing fixpoint of the other kind changes.
+struct Evaluator<'a> {
+    model: &'a Kripke,
+    all: HashSet<usize>,
+    /// The values of the bound variables.
+    environment: HashMap<String, HashSet<usize>>,
+    /// The last value of each fixpoint subformula, keyed by its address, as
+    /// the formula is not modified during the evaluation.
+    approximants: HashMap<*const MuFormulae, HashSet<usize>>,
+}
+
+/// The fixpoints in `formula`, outside of `formula` itself.
+fn nested_fixpoints<'f>(formula: &'f MuFormulae, fixpoints: &mut Vec<&'f MuFormulae>) {
+    match formula {
+        MuFormulae::True | MuFormulae::False | MuFormulae::Atomic(_) | MuFormulae::Variable(_) => {}
+        MuFormulae::Not(f) | MuFormulae::Diamond(f) | MuFormulae::Square(f) => {
+            nested_fixpoints(f, fixpoints)
+        }
+        MuFormulae::And(f, g) | MuFormulae::Or(f, g) => {
+            nested_fixpoints(f, fixpoints);
+            nested_fixpoints(g, fixpoints);
+        }
+        MuFormulae::Mu(_, f) | MuFormulae::Nu(_, f) => {
+            fixpoints.push(formula);
+            nested_fixpoints(f, fixpoints);
+        }
+    }
+}
+
+impl Evaluator<'_> {
+    /// The first approximant of a fixpoint.
+    fn bottom(&self, fixpoint: &MuFormulae) -> HashSet<usize> {
+        match fixpoint {
+            MuFormulae::Mu(_, _) => HashSet::new(),
+            _ => self.all.clone(),
+        }
+    }
+
+    /// The states with some successor in `states`, or with all of them when
+    /// `every`.
+    fn pre(&self, states: &HashSet<usize>, every: bool) -> HashSet<usize> {
+        self.all
+            .iter()
+            .filter(|s| {
+                let mut next = self.model.transitions[s].iter();
+                if every {
+                    next.all(|t| states.contains(t))
+                } else {
+                    next.any(|t| states.contains(t))
+                }
+            })
+            .cloned()
+            .collect()
+    }
+
+    fn eval(&mut self, formula: &MuFormulae) -> HashSet<usize> {
+        match formula {
+            MuFormulae::True => self.all.clone(),
+            MuFormulae::False => HashSet::new(),
+            MuFormulae::Atomic(a) => match self.model.contains_label(a) {
+                Some(id) => self.model.get_state_with_label_as_set(id),
+                None => HashSet::new(),
+            },
+            MuFormulae::Variable(x) => self.environment[x].clone(),
+            MuFormulae::Not(f) => {
+                let states = self.eval(f);
+                self.all.difference(&states).cloned().collect()
+            }
+            MuFormulae::And(f, g) => {
+                let states = self.eval(f);
+                states.intersection(&self.eval(g)).cloned().collect()
+            }
+            MuFormulae::Or(f, g) => {
+                let mut states = self.eval(f);
+                states.extend(self.eval(g));
+                states
+            }
+            MuFormulae::Diamond(f) => {
+                let states = self.eval(f);
+                self.pre(&states, false)
+            }
+            MuFormulae::Square(f) => {
+                let states = self.eval(f);
+                self.pre(&states, true)
+            }
+            MuFormulae::Mu(x, f) | MuFormulae::Nu(x, f) => {
+                let mut nested = Vec::new();
+                nested_fixpoints(f, &mut nested);
+                // only the fixpoints of the other kind restart
+                let restarted = nested
+                    .into_iter()
+                    .filter(|g| {
+                        matches!(g, MuFormulae::Mu(_, _)) != matches!(formula, MuFormulae::Mu(_, _))
+                    })
+                    .collect::<Vec<&MuFormulae>>();
+
+                let key = formula as *const MuFormulae;
+                let mut current = match self.approximants.get(&key) {
+                    Some(states) => states.clone(),
+                    None => self.bottom(formula),
+                };
+                let outer = self.environment.remove(x);
+                loop {
+                    for g in &restarted {
+                        let first = self.bottom(g);
+                        self.approximants.insert(*g as *const MuFormulae, first);
+                    }
+                    self.environment.insert(x.clone(), current.clone());
+                    let next = self.eval(f);
+                    if next == current {
+                        break;
+                    }
+                    current = next;
+                }
+                match outer {
+                    Some(states) => self.environment.insert(x.clone(), states),
+                    None => self.environment.remove(x),
+                };
+                self.approximants.insert(key, current.clone());
+                current
+            }
+        }
+    }
+}
+
+/// The states of `model` satisfying `formula`, which must have no free
+/// variable. The atomic propositions which are not labels of the model
+/// never hold.
+pub fn satisfying_states(model: &Kripke, formula: &MuFormulae) -> Result<HashSet<usize>, MuError> {
+    formula.validate()?;
+    let mut evaluator = Evaluator {
+        model,
+        all: model.states.keys().cloned().collect(),
+        environment: HashMap::new(),
+        approximants: HashMap::new(),
+    };
+    // the restarts of the nested fixpoints rely on their kind, which
+    // depends on the negations around them
+    Ok(evaluator.eval(&positive(formula, false)))
+}
+
+/// `formula`, or its negation when `negated`, with the negations pushed to
+/// the atomic propositions. The variables occur positively, so that they
+/// are left as they are.
+fn positive(formula: &MuFormulae, negated: bool) -> MuFormulae {
+    let sub = |f: &MuFormulae| Box::new(positive(f, negated));
+    match (formula, negated) {
+        (MuFormulae::True, false) | (MuFormulae::False, true) => MuFormulae::True,
+        (MuFormulae::True, true) | (MuFormulae::False, false) => MuFormulae::False,
+        (MuFormulae::Atomic(_), false) | (MuFormulae::Variable(_), _) => formula.clone(),
+        (MuFormulae::Atomic(_), true) => MuFormulae::Not(Box::new(formula.clone())),
+        (MuFormulae::Not(f), _) => positive(f, !negated),
+        (MuFormulae::And(f, g), false) | (MuFormulae::Or(f, g), true) => {
+            MuFormulae::And(sub(f), sub(g))
+        }
+        (MuFormulae::Or(f, g), false) | (MuFormulae::And(f, g), true) => {
+            MuFormulae::Or(sub(f), sub(g))
+        }
+        (MuFormulae::Diamond(f), false) | (MuFormulae::Square(f), true) => {
+            MuFormulae::Diamond(sub(f))
+        }
+        (MuFormulae::Square(f), false) | (MuFormulae::Diamond(f), true) => {
+            MuFormulae::Square(sub(f))
+        }
+        (MuFormulae::Mu(x, f), false) | (MuFormulae::Nu(x, f), true) => {
+            MuFormulae::Mu(x.clone(), sub(f))
+        }
+        (MuFormulae::Nu(x, f), false) | (MuFormulae::Mu(x, f), true) => {
+            MuFormulae::Nu(x.clone(), sub(f))
+        }
+    }
+}
+
+/// The subformulae of a formula in positive normal form, referring to each
+/// other by index.
+struct Subformulae<'f> {
+    formulae: Vec<&'f MuFormulae>,
+    /// The subformulae of each subformula, or its fixpoint for a variable.
+    children: Vec<Vec<usize>>,
+    /// The number of fixpoints around each fixpoint.
+    depths: Vec<Option<usize>>,
+}
+
+impl<'f> Subformulae<'f> {
+    /// Add `formula` and its subformulae, with the fixpoints of `scope`
+    /// around it, returning its index.
+    fn add(&mut self, formula: &'f MuFormulae, scope: &mut Vec<(&'f str, usize)>) -> usize {
+        let index = self.formulae.len();
+        self.formulae.push(formula);
+        self.children.push(Vec::new());
+        self.depths.push(None);
+        let children = match formula {
+            MuFormulae::Variable(x) => {
+                let (_, fixpoint) = scope.iter().rev().find(|(y, _)| y == x).unwrap();
+                vec![*fixpoint]
+            }
+            MuFormulae::True | MuFormulae::False | MuFormulae::Atomic(_) | MuFormulae::Not(_) => {
+                Vec::new()
+            }
+            MuFormulae::Diamond(f) | MuFormulae::Square(f) => vec![self.add(f, scope)],
+            MuFormulae::And(f, g) | MuFormulae::Or(f, g) => {
+                vec![self.add(f, scope), self.add(g, scope)]
+            }
+            MuFormulae::Mu(x, f) | MuFormulae::Nu(x, f) => {
+                self.depths[index] = Some(scope.len());
+                scope.push((x, index));
+                let body = self.add(f, scope);
+                scope.pop();
+                vec![body]
+            }
+        };
+        self.children[index] = children;
+        index
+    }
+}
+
+/// The model checking game of a mu-calculus formula, whose positions are
+/// pairs of a state and a subformula.
+///
+/// Even claims that the subformula holds in the state, choosing the
+/// disjuncts and the successors of diamonds, while Odd chooses the conjuncts
+/// and the successors of boxes. Unfolding a fixpoint has a priority larger
+/// than the fixpoints inside it, odd for `mu` and even for `nu`, so that the
+/// outermost fixpoint unfolded infinitely often decides the winner.
+#[derive(Debug)]
+pub struct CheckingGame {
+    pub game: ParityGame,
+    /// The position of the whole formula in each state.
+    ///
+    /// key: state, value: vertex
+    pub roots: HashMap<usize, usize>,
+}
+
+/// The model checking game of `formula` on `model`, which Even wins from the
+/// states satisfying it.
+pub fn checking_game(model: &Kripke, formula: &MuFormulae) -> Result<CheckingGame, MuError> {
+    formula.validate()?;
+    let formula = positive(formula, false);
+    let mut subformulae = Subformulae {
+        formulae: Vec::new(),
+        children: Vec::new(),
+        depths: Vec::new(),
+    };
+    subformulae.add(&formula, &mut Vec::new());
+    let nesting = subformulae
+        .depths
+        .iter()
+        .flatten()
+        .max()
+        .map_or(0, |d| d + 1);
+
+    let mut states = model.states.keys().cloned().collect::<Vec<usize>>();
+    states.sort();
+    let position = states
+        .iter()
+        .enumerate()
+        .map(|(i, s)| (*s, i))
+        .collect::<HashMap<usize, usize>>();
+    let vertex = |f: usize, s: usize| f * states.len() + position[&s];
+
+    let mut game = ParityGame::new();
+    for (f, formula) in subformulae.formulae.iter().enumerate() {
+        let priority = match (formula, subformulae.depths[f]) {
+            (MuFormulae::Mu(_, _), Some(depth)) => 2 * (nesting - depth) + 1,
+            (MuFormulae::Nu(_, _), Some(depth)) => 2 * (nesting - depth),
+            _ => 0,
+        };
+        for s in &states {
+            let owner = match formula {
+                MuFormulae::True => Player::Odd,
+                MuFormulae::False => Player::Even,
+                MuFormulae::Atomic(a) => match model.contains_label(a) {
+                    Some(id) if model.get_state_with_label_as_set(id).contains(s) => Player::Odd,
+                    _ => Player::Even,
+                },
+                MuFormulae::Not(a) => match &**a {
+                    MuFormulae::Atomic(a) => match model.contains_label(a) {
+                        Some(id) if model.get_state_with_label_as_set(id).contains(s) => {
+                            Player::Even
+                        }
+                        _ => Player::Odd,
+                    },
+                    _ => unreachable!(),
+                },
+                MuFormulae::And(_, _) | MuFormulae::Square(_) => Player::Odd,
+                _ => Player::Even,
+            };
+            let name = format!("{} ⊨ {}", model.states[s], formula);
+            game.add_vertex(owner, priority, name);
+        }
+    }
+    for (f, formula) in subformulae.formulae.iter().enumerate() {
+        for s in &states {
+            let next = match formula {
+                MuFormulae::Diamond(_) | MuFormulae::Square(_) => model.transitions[s]
+                    .iter()
+                    .map(|t| vertex(subformulae.children[f][0], *t))
+                    .collect(),
+                _ => subformulae.children[f]
+                    .iter()
+                    .map(|g| vertex(*g, *s))
+                    .collect::<Vec<usize>>(),
+            };
+            for to in next {
+                game.add_edge(vertex(f, *s), to);
+            }
+        }
+    }
+    let roots = states.iter().map(|s| (*s, vertex(0, *s))).collect();
+    Ok(CheckingGame { game, roots })
+}
+
+/// Why a formula holds or fails in the initial state of a model, from a
+/// winning strategy in the model checking game.
+#[derive(Debug)]
+pub struct Evidence {
+    pub holds: bool,
+    /// The choices of the winner, in the positions reachable when it follows
+    /// its strategy, whatever the choices of the opponent.
+    ///
+    /// key: position, value: chosen position
+    pub moves: Vec<(String, String)>,
+}
+
+/// Check `formula` in the initial state of `model` by solving its model
+/// checking game.
+pub fn game_checking(model: &Kripke, formula: &MuFormulae) -> Result<Evidence, MuError> {
+    let CheckingGame { game, roots } = checking_game(model, formula)?;
+    let solution = zielonka(&game);
+    let root = roots[&model.initial_state];
+    let winner = solution.winners[root];
+
+    let mut moves = Vec::new();
+    let mut visited = HashSet::from([root]);
+    let mut queue = vec![root];
+    while let Some(v) = queue.pop() {
+        let next = if game.owners[v] == winner {
+            let chosen = solution.strategy[v].unwrap();
+            if game.successors[v].len() > 1 {
+                moves.push((game.names[v].clone(), game.names[chosen].clone()));
+            }
+            vec![chosen]
+        } else {
+            game.successors[v].clone()
+        };
+        for t in next {
+            if visited.insert(t) {
+                queue.push(t);
+            }
+        }
+    }
+    moves.sort();
+    Ok(Evidence {
+        holds: winner == Player::Even,
+        moves,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        algorithm::{explicit_state::explicit_state_checking, parity::priority_promotion},
+        parser::{ctl_parser::parse_ctl, mu_parser::parse_mu},
+    };
+
+    fn states(model: &Kripke, formula: &str) -> HashSet<usize> {
+        satisfying_states(model, &parse_mu(formula).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_fixpoints() {
+        // p holds on the even steps only, with a branch to a sink
+        let data = r#"
+        {
+            "states": [
+                { "id": 0, "name": "s0", "labels": ["p"], "transit_to": [1] },
+                { "id": 1, "name": "s1", "labels": [], "transit_to": [0, 2] },
+                { "id": 2, "name": "s2", "labels": [], "transit_to": [2] }
+            ],
+            "initial_state": 0
+        }"#;
+        let model = Kripke::from_json(data).unwrap();
+        // p on every even step, which only holds until the sink is reached
+        assert_eq!(states(&model, "nu X. p && [] [] X"), HashSet::new());
+        assert_eq!(states(&model, "nu X. p && [] <> X"), HashSet::from([0]));
+        assert_eq!(states(&model, "mu X. p || <> X"), HashSet::from([0, 1]));
+        // some path visits p infinitely often, with alternating fixpoints
+        assert_eq!(
+            states(&model, "nu X. mu Y. (p && <> X) || <> Y"),
+            HashSet::from([0, 1])
+        );
+        // every path visits p finitely often
+        assert_eq!(
+            states(&model, "mu X. nu Y. (!p || [] X) && [] Y"),
+            HashSet::from([2])
+        );
+        assert_eq!(
+            satisfying_states(&model, &parse_mu("mu X. !X").unwrap()),
+            Err(MuError::NotMonotone("X".to_string()))
+        );
+
+        // p holds forever after the first step
+        let data = r#"
+        {
+            "states": [
+                { "id": 0, "name": "s0", "labels": [], "transit_to": [1] },
+                { "id": 1, "name": "s1", "labels": ["p"], "transit_to": [1] }
+            ],
+            "initial_state": 0
+        }"#;
+        let model = Kripke::from_json(data).unwrap();
+        // the negated least fixpoint is a greatest one, which must not restart
+        assert_eq!(
+            states(&model, "mu X. p || [] !(mu Y. !X || <> Y)"),
+            HashSet::from([0, 1])
+        );
+    }
+
+    #[test]
+    fn test_ctl_agrees() {
+        let data = r#"
+        {
+            "states": [
+                { "id": 0, "name": "s0", "labels": ["p"], "transit_to": [1, 2] },
+                { "id": 1, "name": "s1", "labels": ["p", "q"], "transit_to": [1, 3] },
+                { "id": 2, "name": "s2", "labels": ["q"], "transit_to": [0] },
+                { "id": 3, "name": "s3", "labels": [], "transit_to": [3, 0] }
+            ],
+            "initial_state": 0
+        }"#;
+        for formula in [
+            "EX q",
+            "AX p",
+            "EF (q && !p)",
+            "AF q",
+            "EG p",
+            "AG (p || q) || EF AG !q",
+            "E (p U q)",
+            "A (p U q)",
+            "E (q R p)",
+            "A (!q R (p || !q))",
+            "AG EF p && EG AF q",
+            "EF[1,3] (q && !p)",
+            "AF[0,2] q",
+            "EG[1,2] p",
+            "AG[2,4] (p || q)",
+            "E (p U[1,2] q)",
+            "A (p U[0,3] !q)",
+        ] {
+            let mut model = Kripke::from_json(data).unwrap();
+            let ctl = parse_ctl(formula).unwrap();
+            let mu = MuFormulae::from_ctl(&ctl).unwrap();
+            let id = explicit_state_checking(&mut model, ctl).unwrap();
+            let expected = model.get_state_with_label_as_set(id);
+            assert_eq!(satisfying_states(&model, &mu).unwrap(), expected, "{}", mu);
+        }
+    }
+
+    #[test]
+    fn test_game_checking() {
+        let data = r#"
+        {
+            "states": [
+                { "id": 0, "name": "s0", "labels": ["p"], "transit_to": [1] },
+                { "id": 1, "name": "s1", "labels": [], "transit_to": [0, 2] },
+                { "id": 2, "name": "s2", "labels": [], "transit_to": [2] }
+            ],
+            "initial_state": 0
+        }"#;
+        let model = Kripke::from_json(data).unwrap();
+        for formula in [
+            "nu X. p && [] [] X",
+            "nu X. p && [] <> X",
+            "nu X. mu Y. (p && <> X) || <> Y",
+            "mu X. nu Y. (!p || [] X) && [] Y",
+            "!(mu X. p || <> (q && X)) && nu Y. <> Y",
+        ] {
+            let formula = parse_mu(formula).unwrap();
+            let expected = satisfying_states(&model, &formula).unwrap();
+            let CheckingGame { game, roots } = checking_game(&model, &formula).unwrap();
+            let winners = zielonka(&game).winners;
+            assert_eq!(priority_promotion(&game), winners);
+            for (state, root) in roots {
+                assert_eq!(winners[root] == Player::Even, expected.contains(&state));
+            }
+        }
+
+        let evidence = game_checking(&model, &parse_mu("<> <> !p").unwrap()).unwrap();
+        assert!(evidence.holds);
+        let moves = [("s1 ⊨ <>¬p".to_string(), "s2 ⊨ ¬p".to_string())];
+        assert_eq!(evidence.moves, moves);
+        let evidence = game_checking(&model, &parse_mu("[] [] p").unwrap()).unwrap();
+        assert!(!evidence.holds);
+        let moves = [("s1 ⊨ []p".to_string(), "s2 ⊨ p".to_string())];
+        assert_eq!(evidence.moves, moves);
+    }
+}
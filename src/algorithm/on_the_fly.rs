@@ -0,0 +1,322 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    fmt::Display,
+};
+
+use crate::{
+    algorithm::emptiness::{couvreur, nested_dfs, Emptiness},
+    automata::buchi::{Buchi, BuchiProduct},
+    ltl::LTLFormulae,
+    model::{history::History, transition_system::TransitionSystem},
+};
+
+/// An infinite path `prefix · cycle^ω`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Lasso<S> {
+    pub prefix: Vec<S>,
+    pub cycle: Vec<S>,
+}
+
+/// Why an LTL formula does not hold on a system.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LtlError<S> {
+    /// The formula cannot be checked on the system, with the reason.
+    Unsupported(String),
+    /// A path of the system violating the formula.
+    Counterexample(Lasso<S>),
+}
+
+impl<S> LtlError<S> {
+    /// The path violating the formula, if it could be checked.
+    pub fn counterexample(self) -> Option<Lasso<S>> {
+        match self {
+            LtlError::Unsupported(_) => None,
+            LtlError::Counterexample(lasso) => Some(lasso),
+        }
+    }
+}
+
+impl<S: std::fmt::Debug> Display for LtlError<S> {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LtlError::Unsupported(reason) => write!(formatter, "{}", reason),
+            LtlError::Counterexample(lasso) => write!(
+                formatter,
+                "counterexample {:?} ({:?})^ω",
+                lasso.prefix, lasso.cycle
+            ),
+        }
+    }
+}
+
+/// Search breadth-first for a state satisfying `target`.
+///
+/// Returns a shortest path from an initial state to the first such state
+/// found, without generating the states behind it.
+pub fn find_reachable<T, F>(system: &T, target: F) -> Option<Vec<T::State>>
+where
+    T: TransitionSystem,
+    F: Fn(&T::State) -> bool,
+{
+    let mut parents: HashMap<T::State, Option<T::State>> = HashMap::new();
+    let mut queue = VecDeque::new();
+
+    for init in system.initial_states() {
+        if !parents.contains_key(&init) {
+            parents.insert(init.clone(), None);
+            queue.push_back(init);
+        }
+    }
+
+    while let Some(state) = queue.pop_front() {
+        if target(&state) {
+            return Some(path_to(&parents, state));
+        }
+        for next in system.successors(&state) {
+            if !parents.contains_key(&next) {
+                parents.insert(next.clone(), Some(state.clone()));
+                queue.push_back(next);
+            }
+        }
+    }
+
+    None
+}
+
+/// Check that `invariant` holds in every reachable state.
+///
+/// On failure, returns a shortest path to a violating state.
+pub fn check_invariant<T, F>(system: &T, invariant: F) -> Result<(), Vec<T::State>>
+where
+    T: TransitionSystem,
+    F: Fn(&T::State) -> bool,
+{
+    match find_reachable(system, |s| !invariant(s)) {
+        Some(path) => Err(path),
+        None => Ok(()),
+    }
+}
+
+/// Check that every infinite path of `system` satisfies `formula`.
+///
+/// The product of the system with an automaton for the negation of `formula`
+/// is explored on the fly with a nested depth-first search, which stops at
+/// the first accepting lasso, returned as a counterexample. The past
+/// subformulae are kept as labels of the states, with their history, so
+/// fails when one of them has a future operator.
+pub fn check_ltl<T: TransitionSystem>(
+    system: &T,
+    formula: &LTLFormulae,
+) -> Result<(), LtlError<T::State>> {
+    check_ltl_with(system, formula, Emptiness::default())
+}
+
+/// Same as [`check_ltl`] with the given emptiness check.
+pub fn check_ltl_with<T: TransitionSystem>(
+    system: &T,
+    formula: &LTLFormulae,
+    emptiness: Emptiness,
+) -> Result<(), LtlError<T::State>> {
+    if formula.is_future() {
+        return match accepting_lasso(system, formula, emptiness) {
+            Some(lasso) => Err(LtlError::Counterexample(lasso)),
+            None => Ok(()),
+        };
+    }
+    let (formula, past) = formula.eliminate_past().map_err(LtlError::Unsupported)?;
+    let history = History { system, past };
+    match accepting_lasso(&history, &formula, emptiness) {
+        Some(lasso) => Err(LtlError::Counterexample(Lasso {
+            prefix: project(&lasso.prefix),
+            cycle: project(&lasso.cycle),
+        })),
+        None => Ok(()),
+    }
+}
+
+/// A path of `system` violating the future formula `formula`.
+fn accepting_lasso<T: TransitionSystem>(
+    system: &T,
+    formula: &LTLFormulae,
+    emptiness: Emptiness,
+) -> Option<Lasso<T::State>> {
+    let negation = LTLFormulae::Not(Box::new(formula.clone()));
+    let automaton = Buchi::from_ltl(&negation);
+
+    let lasso = match emptiness {
+        Emptiness::NestedDfs => {
+            let automaton = automaton.degeneralize();
+            let product = BuchiProduct::new(system, &automaton);
+            nested_dfs(&product, |s| product.is_accepting(s, 0))
+        }
+        Emptiness::Couvreur => {
+            let product = BuchiProduct::new(system, &automaton);
+            couvreur(&product, automaton.acceptance.len(), |s, i| {
+                product.is_accepting(s, i)
+            })
+        }
+    };
+    lasso.map(|lasso| Lasso {
+        prefix: project(&lasso.prefix),
+        cycle: project(&lasso.cycle),
+    })
+}
+
+fn path_to<S: Clone + Eq + std::hash::Hash>(parents: &HashMap<S, Option<S>>, state: S) -> Vec<S> {
+    let mut path = vec![state];
+    while let Some(Some(parent)) = parents.get(path.last().unwrap()) {
+        path.push(parent.clone());
+    }
+    path.reverse();
+    path
+}
+
+fn project<S: Clone, T>(states: &[(S, T)]) -> Vec<S> {
+    states.iter().map(|(s, _)| s.clone()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        model::{
+            kripke::Kripke,
+            product::{Composition, Product},
+        },
+        parser::ltl_parser::parse_ltl,
+    };
+
+    /// A request `r` is eventually granted `g`, unless the system takes the
+    /// detour through `s2` forever.
+    fn model() -> Kripke {
+        let data = r#"
+        {
+            "states": [
+                { "id": 0, "name": "s0", "labels": [], "transit_to": [1] },
+                { "id": 1, "name": "s1", "labels": ["r"], "transit_to": [2, 3] },
+                { "id": 2, "name": "s2", "labels": ["w"], "transit_to": [2] },
+                { "id": 3, "name": "s3", "labels": ["g"], "transit_to": [0] }
+            ],
+            "initial_state": 0
+        }"#;
+        Kripke::from_json(data).unwrap()
+    }
+
+    #[test]
+    fn test_past() {
+        let k = model();
+        let check = |formula: &str| check_ltl(&k, &parse_ltl(formula).unwrap());
+        assert!(check("G (g -> Y r)").is_ok());
+        assert!(check("G (g -> O r)").is_ok());
+        assert!(check("G (w -> (w S r))").is_ok());
+        assert!(check("!Y true && X Y !r").is_ok());
+
+        let lasso = check("G (r -> Y g)").unwrap_err().counterexample().unwrap();
+        assert_eq!(lasso.prefix[..2], [0, 1]);
+        // a grant of an earlier round is remembered on the detour
+        let lasso = check("G (w -> H !g)")
+            .unwrap_err()
+            .counterexample()
+            .unwrap();
+        assert!(lasso.prefix.contains(&3));
+        assert_eq!(lasso.cycle, vec![2]);
+
+        assert_eq!(
+            check("G (p -> Y F p)"),
+            Err(LtlError::Unsupported(
+                "Y(true U p) has a future operator under a past operator".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn test_reachability() {
+        let k = model();
+        let path = find_reachable(&k, |s| k.has_label(s, "g")).unwrap();
+        assert_eq!(path, vec![0, 1, 3]);
+        assert!(find_reachable(&k, |s| k.has_label(s, "x")).is_none());
+
+        assert!(check_invariant(&k, |s| !k.has_label(s, "x")).is_ok());
+        assert_eq!(
+            check_invariant(&k, |s| !(k.has_label(s, "w"))),
+            Err(vec![0, 1, 2])
+        );
+    }
+
+    #[test]
+    fn test_ltl() {
+        let k = model();
+        assert!(check_ltl(&k, &parse_ltl("G (r -> X (w || g))").unwrap()).is_ok());
+        assert!(check_ltl(&k, &parse_ltl("G (w -> G w)").unwrap()).is_ok());
+        assert!(check_ltl(&k, &parse_ltl("G (r -> F[1,1] (w || g))").unwrap()).is_ok());
+        assert!(check_ltl(&k, &parse_ltl("G (r -> F[1,3] g)").unwrap()).is_err());
+
+        let lasso = check_ltl(&k, &parse_ltl("G (r -> F g)").unwrap())
+            .unwrap_err()
+            .counterexample()
+            .unwrap();
+        assert_eq!(lasso.cycle, vec![2]);
+        assert_eq!(lasso.prefix, vec![0, 1]);
+
+        let lasso = check_ltl(&k, &parse_ltl("F w").unwrap())
+            .unwrap_err()
+            .counterexample()
+            .unwrap();
+        assert!(lasso.prefix.is_empty());
+        assert_eq!(lasso.cycle, vec![0, 1, 3]);
+    }
+
+    #[test]
+    fn test_emptiness_checks_agree() {
+        let k = model();
+        for formula in [
+            "G (r -> F g)",
+            "G F g && G F r",
+            "F G w",
+            "G (r -> X (w || g))",
+            "(!g U r) || G !g",
+        ] {
+            let formula = parse_ltl(formula).unwrap();
+            let nested = check_ltl_with(&k, &formula, Emptiness::NestedDfs);
+            let scc = check_ltl_with(&k, &formula, Emptiness::Couvreur);
+            assert_eq!(nested.is_ok(), scc.is_ok(), "{}", formula);
+        }
+        let lasso = check_ltl_with(
+            &k,
+            &parse_ltl("G F g && G F r").unwrap(),
+            Emptiness::Couvreur,
+        );
+        assert_eq!(lasso.unwrap_err().counterexample().unwrap().cycle, vec![2]);
+    }
+
+    #[test]
+    fn test_product_on_the_fly() {
+        let data = r#"
+        {
+            "states": [
+                { "id": 0, "name": "idle", "labels": [], "transit_to": [0, 1] },
+                { "id": 1, "name": "crit", "labels": ["crit"], "transit_to": [0] }
+            ],
+            "initial_state": 0
+        }"#;
+        let k = Kripke::from_json(data).unwrap();
+        let product = Product::new(&k, &k, ("p", "q"), &[], &[], Composition::Asynchronous);
+
+        let path = find_reachable(&product, |s| {
+            product.has_label(s, "p.crit") && product.has_label(s, "q.crit")
+        })
+        .unwrap();
+        assert_eq!(path.len(), 3);
+
+        assert!(check_ltl(
+            &product,
+            &parse_ltl("G (p.crit && q.crit -> X (p.crit || q.crit))").unwrap()
+        )
+        .is_ok());
+        let lasso = check_ltl(&product, &parse_ltl("G F p.crit").unwrap())
+            .unwrap_err()
+            .counterexample()
+            .unwrap();
+        assert!(lasso.cycle.iter().all(|s| !product.has_label(s, "p.crit")));
+    }
+}
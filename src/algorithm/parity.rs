@@ -0,0 +1,327 @@
+use crate::model::parity_game::{ParityGame, Player};
+
+/// The winner of each vertex of a parity game, with their strategies.
+#[derive(Debug)]
+pub struct Solution {
+    pub winners: Vec<Player>,
+    /// The successor chosen in each vertex by its owner, when it is also its
+    /// winner. Playing these moves wins from every vertex of the winning
+    /// region.
+    pub strategy: Vec<Option<usize>>,
+}
+
+/// Subgames are sets of vertices, as one flag per vertex of the game.
+struct Solver<'a> {
+    game: &'a ParityGame,
+    predecessors: Vec<Vec<usize>>,
+}
+
+fn index(player: Player) -> usize {
+    match player {
+        Player::Even => 0,
+        Player::Odd => 1,
+    }
+}
+
+fn minus(set: &[bool], removed: &[bool]) -> Vec<bool> {
+    set.iter().zip(removed).map(|(a, b)| *a && !b).collect()
+}
+
+impl<'a> Solver<'a> {
+    fn new(game: &'a ParityGame) -> Self {
+        let mut predecessors = vec![Vec::new(); game.len()];
+        for (from, successors) in game.successors.iter().enumerate() {
+            for to in successors {
+                predecessors[*to].push(from);
+            }
+        }
+        Solver { game, predecessors }
+    }
+
+    /// The vertices of `subgame` from which `player` can force the play into
+    /// `target`, which is part of the subgame. The opponent loses when stuck,
+    /// so that its vertices without successors are attracted too.
+    ///
+    /// The moves of `player` towards `target` are recorded in `strategy`.
+    fn attractor(
+        &self,
+        subgame: &[bool],
+        target: &[bool],
+        player: Player,
+        strategy: &mut [Option<usize>],
+    ) -> Vec<bool> {
+        let game = self.game;
+        let mut region = target.to_vec();
+        let mut queue = (0..game.len())
+            .filter(|v| target[*v])
+            .collect::<Vec<usize>>();
+        // the successors of the opponent vertices left out of the region
+        let mut remaining = (0..game.len())
+            .map(|v| game.successors[v].iter().filter(|t| subgame[**t]).count())
+            .collect::<Vec<usize>>();
+        for v in 0..game.len() {
+            if subgame[v] && !region[v] && game.owners[v] != player && remaining[v] == 0 {
+                region[v] = true;
+                queue.push(v);
+            }
+        }
+        while let Some(v) = queue.pop() {
+            for &u in &self.predecessors[v] {
+                if !subgame[u] || region[u] {
+                    continue;
+                }
+                if game.owners[u] == player {
+                    strategy[u] = Some(v);
+                } else {
+                    remaining[u] -= 1;
+                    if remaining[u] > 0 {
+                        continue;
+                    }
+                }
+                region[u] = true;
+                queue.push(u);
+            }
+        }
+        region
+    }
+
+    /// Remove the vertices where a player is stuck, or can be forced to
+    /// be, returning what is left with the winners of the removed vertices.
+    fn dead_ends(&self, winners: &mut [Player], strategy: &mut [Option<usize>]) -> Vec<bool> {
+        let all = vec![true; self.game.len()];
+        let none = vec![false; self.game.len()];
+        let odd = self.attractor(&all, &none, Player::Odd, strategy);
+        let rest = minus(&all, &odd);
+        let even = self.attractor(&rest, &none, Player::Even, strategy);
+        for (v, winner) in winners.iter_mut().enumerate() {
+            if odd[v] {
+                *winner = Player::Odd;
+            }
+        }
+        minus(&rest, &even)
+    }
+
+    /// Zielonka's recursive algorithm on a subgame where every vertex has a
+    /// successor, returning the winning region of each player.
+    fn zielonka(&self, subgame: &[bool], strategy: &mut [Option<usize>]) -> [Vec<bool>; 2] {
+        let game = self.game;
+        let vertices = (0..game.len()).filter(|v| subgame[*v]);
+        let Some(top) = vertices.map(|v| game.priorities[v]).max() else {
+            return [vec![false; game.len()], vec![false; game.len()]];
+        };
+        let player = Player::of(top);
+        let target = (0..game.len())
+            .map(|v| subgame[v] && game.priorities[v] == top)
+            .collect::<Vec<bool>>();
+        // the player wins if the top priority is seen infinitely often
+        for v in (0..game.len()).filter(|v| target[*v] && game.owners[*v] == player) {
+            strategy[v] = game.successors[v].iter().find(|t| subgame[**t]).cloned();
+        }
+        let attracted = self.attractor(subgame, &target, player, strategy);
+        let mut winning = self.zielonka(&minus(subgame, &attracted), strategy);
+        let opponent = winning[index(player.opponent())].clone();
+        if !opponent.contains(&true) {
+            let mut regions = [subgame.to_vec(), subgame.to_vec()];
+            regions[index(player.opponent())] = vec![false; game.len()];
+            return regions;
+        }
+
+        // the opponent wins from where it can force the play to its region
+        let lost = self.attractor(subgame, &opponent, player.opponent(), strategy);
+        winning = self.zielonka(&minus(subgame, &lost), strategy);
+        for (v, won) in winning[index(player.opponent())].iter_mut().enumerate() {
+            *won |= lost[v];
+        }
+        winning
+    }
+
+    /// Search a dominion of the subgame, a set of vertices which one player
+    /// wins without leaving it, by promoting priorities.
+    ///
+    /// The region of a priority is the attractor of its vertices in the
+    /// vertices of lower regions. When its player cannot be kept in it, the
+    /// next lower region is computed; otherwise, the opponent can only escape
+    /// to higher regions of the player, and the region is merged with the
+    /// lowest of them, resetting those below.
+    fn search_dominion(&self, subgame: &[bool]) -> (Vec<bool>, Player) {
+        let game = self.game;
+        let n = game.len();
+        let mut regions = game.priorities.clone();
+        let mut strategy = vec![None; n];
+        let mut priority = (0..n)
+            .filter(|v| subgame[*v])
+            .map(|v| game.priorities[v])
+            .max()
+            .unwrap();
+        loop {
+            let player = Player::of(priority);
+            let lower = (0..n)
+                .map(|v| subgame[v] && regions[v] <= priority)
+                .collect::<Vec<bool>>();
+            let target = (0..n)
+                .map(|v| lower[v] && regions[v] == priority)
+                .collect::<Vec<bool>>();
+            let region = self.attractor(&lower, &target, player, &mut strategy);
+            let open = (0..n).filter(|v| target[*v]).any(|v| {
+                let mut next = game.successors[v].iter();
+                if game.owners[v] == player {
+                    !next.any(|t| region[*t])
+                } else {
+                    next.any(|t| lower[*t] && !region[*t])
+                }
+            });
+            if open {
+                for v in (0..n).filter(|v| region[*v]) {
+                    regions[v] = priority;
+                }
+                priority = (0..n)
+                    .filter(|v| subgame[*v] && regions[*v] < priority)
+                    .map(|v| regions[v])
+                    .max()
+                    .unwrap();
+                continue;
+            }
+
+            let escape = (0..n)
+                .filter(|v| region[*v] && game.owners[*v] != player)
+                .flat_map(|v| game.successors[v].iter())
+                .filter(|t| subgame[**t] && regions[**t] > priority)
+                .map(|t| regions[*t])
+                .min();
+            match escape {
+                None => return (region, player),
+                Some(higher) => {
+                    for v in 0..n {
+                        if region[v] {
+                            regions[v] = higher;
+                        } else if regions[v] < higher {
+                            regions[v] = game.priorities[v];
+                        }
+                    }
+                    priority = higher;
+                }
+            }
+        }
+    }
+}
+
+/// Solve `game` with Zielonka's recursive algorithm.
+pub fn zielonka(game: &ParityGame) -> Solution {
+    let solver = Solver::new(game);
+    let mut winners = vec![Player::Even; game.len()];
+    let mut strategy = vec![None; game.len()];
+    let rest = solver.dead_ends(&mut winners, &mut strategy);
+    let winning = solver.zielonka(&rest, &mut strategy);
+    for (v, winner) in winners.iter_mut().enumerate() {
+        if winning[index(Player::Odd)][v] {
+            *winner = Player::Odd;
+        }
+    }
+    for v in 0..game.len() {
+        if game.owners[v] != winners[v] {
+            strategy[v] = None;
+        }
+    }
+    Solution { winners, strategy }
+}
+
+/// The winner of each vertex of `game`, with the priority promotion
+/// algorithm of Benerecetti, Dell'Erba and Mogavero.
+///
+/// The dominions found are removed with their attractors until the game is
+/// solved. No strategy is computed, as `zielonka` does.
+pub fn priority_promotion(game: &ParityGame) -> Vec<Player> {
+    let solver = Solver::new(game);
+    let mut winners = vec![Player::Even; game.len()];
+    let mut strategy = vec![None; game.len()];
+    let mut rest = solver.dead_ends(&mut winners, &mut strategy);
+    while rest.contains(&true) {
+        let (dominion, player) = solver.search_dominion(&rest);
+        let won = solver.attractor(&rest, &dominion, player, &mut strategy);
+        for v in (0..game.len()).filter(|v| won[*v]) {
+            winners[v] = player;
+        }
+        rest = minus(&rest, &won);
+    }
+    winners
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn game(vertices: &[(Player, usize, &[usize])]) -> ParityGame {
+        let mut game = ParityGame::new();
+        for (v, (owner, priority, _)) in vertices.iter().enumerate() {
+            game.add_vertex(*owner, *priority, format!("v{}", v));
+        }
+        for (v, (_, _, successors)) in vertices.iter().enumerate() {
+            for t in successors.iter() {
+                game.add_edge(v, *t);
+            }
+        }
+        game
+    }
+
+    #[test]
+    fn test_zielonka() {
+        use Player::*;
+        // Odd escapes to the dead end of Even from v1, but not from v3
+        let game = game(&[
+            (Even, 2, &[1, 3]),
+            (Odd, 1, &[0, 2]),
+            (Even, 0, &[]),
+            (Odd, 3, &[4]),
+            (Even, 4, &[3, 4]),
+        ]);
+        let solution = zielonka(&game);
+        assert_eq!(solution.winners, vec![Even, Odd, Odd, Even, Even]);
+        assert_eq!(
+            solution.strategy,
+            vec![Some(3), Some(2), None, None, Some(3)]
+        );
+        assert_eq!(priority_promotion(&game), solution.winners);
+    }
+
+    #[test]
+    fn test_solvers_agree() {
+        let mut seed: u64 = 7;
+        let mut random = |n: u64| {
+            seed = seed
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            (seed >> 33) % n
+        };
+        for _ in 0..300 {
+            let size = 1 + random(12) as usize;
+            let mut game = ParityGame::new();
+            for v in 0..size {
+                let owner = if random(2) == 0 {
+                    Player::Even
+                } else {
+                    Player::Odd
+                };
+                game.add_vertex(owner, random(6) as usize, format!("v{}", v));
+            }
+            for v in 0..size {
+                for _ in 0..random(4) {
+                    game.add_edge(v, random(size as u64) as usize);
+                }
+            }
+            let solution = zielonka(&game);
+            assert_eq!(priority_promotion(&game), solution.winners, "{:?}", game);
+            // the strategies keep the play in the winning regions
+            for v in 0..size {
+                let winner = solution.winners[v];
+                let mut moves = game.successors[v].iter();
+                if game.owners[v] == winner {
+                    let next = solution.strategy[v].unwrap();
+                    assert!(game.successors[v].contains(&next));
+                    assert_eq!(solution.winners[next], winner);
+                } else {
+                    assert!(moves.all(|t| solution.winners[*t] == winner));
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,353 @@
+use std::{
+    cell::{Cell, RefCell},
+    collections::{BTreeSet, HashMap, HashSet},
+};
+
+use crate::{
+    algorithm::on_the_fly::{check_ltl, LtlError},
+    ltl::LTLFormulae,
+    model::{guarded::GuardedProgram, transition_system::TransitionSystem},
+};
+
+/// Statistics of an exploration with partial order reduction.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PorReport {
+    /// Whether the reduction was applied, it is not for formulae with next.
+    pub reduced: bool,
+    /// The states whose successors were computed.
+    pub expanded_states: usize,
+    /// The expanded states where only an ample set of commands was executed.
+    pub reduced_states: usize,
+    /// The successors skipped in reduced states that were never reached.
+    pub pruned_states: usize,
+}
+
+/// A guarded program explored with ample sets.
+///
+/// In each state, the enabled commands of a single process are executed
+/// instead of all enabled commands when
+///
+/// - they are independent of every command of the other processes, and the
+///   disabled commands of the process cannot be enabled by the others (C1),
+/// - they do not change the variables of the visible labels (C2),
+/// - none of their successors has been expanded before, so that every cycle
+///   contains a fully expanded state (C3).
+///
+/// The choices are memoized so that the successors of a state never change,
+/// which keeps the reduced system consistent for nested searches.
+pub struct ReducedProgram<'a> {
+    program: &'a GuardedProgram,
+    reduce: bool,
+    /// The variables the visible labels depend on.
+    visible: BTreeSet<usize>,
+    /// Whether each command is independent of the commands of other processes.
+    independent: Vec<bool>,
+    /// The variables written by the processes other than each process.
+    written_by_others: Vec<BTreeSet<usize>>,
+    successors: RefCell<HashMap<Vec<i64>, Vec<Vec<i64>>>>,
+    generated: RefCell<HashSet<Vec<i64>>>,
+    skipped: RefCell<HashSet<Vec<i64>>>,
+    reduced_states: Cell<usize>,
+}
+
+impl<'a> ReducedProgram<'a> {
+    /// Reduce `program` for checking properties over the labels `visible`.
+    pub fn new(program: &'a GuardedProgram, visible: &HashSet<String>) -> ReducedProgram<'a> {
+        let commands = 0..program.commands.len();
+        let reads = commands
+            .clone()
+            .map(|c| program.reads(c))
+            .collect::<Vec<_>>();
+        let writes = commands
+            .clone()
+            .map(|c| program.writes(c))
+            .collect::<Vec<_>>();
+
+        let dependent = |c: usize, d: usize| {
+            !writes[c].is_disjoint(&reads[d])
+                || !writes[c].is_disjoint(&writes[d])
+                || !writes[d].is_disjoint(&reads[c])
+        };
+        let independent = commands
+            .clone()
+            .map(|c| {
+                commands.clone().all(|d| {
+                    program.commands[c].process == program.commands[d].process || !dependent(c, d)
+                })
+            })
+            .collect();
+
+        let written_by_others = (0..program.processes.len())
+            .map(|p| {
+                commands
+                    .clone()
+                    .filter(|d| program.commands[*d].process != p)
+                    .flat_map(|d| writes[d].iter().cloned())
+                    .collect()
+            })
+            .collect();
+
+        let visible = visible
+            .iter()
+            .filter_map(|label| program.label(label))
+            .flat_map(|expr| expr.variables())
+            .collect();
+
+        ReducedProgram {
+            program,
+            reduce: true,
+            visible,
+            independent,
+            written_by_others,
+            successors: RefCell::new(HashMap::new()),
+            generated: RefCell::new(HashSet::new()),
+            skipped: RefCell::new(HashSet::new()),
+            reduced_states: Cell::new(0),
+        }
+    }
+
+    /// The same exploration, without reduction.
+    pub fn full(program: &'a GuardedProgram) -> ReducedProgram<'a> {
+        ReducedProgram {
+            reduce: false,
+            ..ReducedProgram::new(program, &HashSet::new())
+        }
+    }
+
+    pub fn report(&self) -> PorReport {
+        let generated = self.generated.borrow();
+        PorReport {
+            reduced: self.reduce,
+            expanded_states: self.successors.borrow().len(),
+            reduced_states: self.reduced_states.get(),
+            pruned_states: self
+                .skipped
+                .borrow()
+                .iter()
+                .filter(|s| !generated.contains(*s))
+                .count(),
+        }
+    }
+
+    /// The successors through an ample set of commands of one process, if
+    /// there is one smaller than the enabled commands.
+    fn ample(&self, state: &Vec<i64>, enabled: &[usize]) -> Option<Vec<Vec<i64>>> {
+        let program = self.program;
+        let expanded = self.successors.borrow();
+
+        (0..program.processes.len()).find_map(|p| {
+            let ample = enabled
+                .iter()
+                .filter(|c| program.commands[**c].process == p)
+                .cloned()
+                .collect::<Vec<usize>>();
+            if ample.is_empty() || ample.len() == enabled.len() {
+                return None;
+            }
+
+            // C1 and C2 for the enabled commands
+            let safe = ample
+                .iter()
+                .all(|c| self.independent[*c] && program.writes(*c).is_disjoint(&self.visible));
+            // C1 for the disabled commands: a false part of their guard only
+            // depends on variables the other processes do not write
+            let stuck = (0..program.commands.len())
+                .filter(|c| program.commands[*c].process == p && !ample.contains(c))
+                .all(|c| {
+                    program.commands[c]
+                        .guard
+                        .conjuncts()
+                        .iter()
+                        .any(|conjunct| {
+                            conjunct.holds(state) == Ok(false)
+                                && conjunct.variables().is_disjoint(&self.written_by_others[p])
+                        })
+                });
+            if !safe || !stuck {
+                return None;
+            }
+
+            // C3
+            let next = ample
+                .iter()
+                .map(|c| program.apply(*c, state))
+                .collect::<Result<Vec<Vec<i64>>, _>>()
+                .ok()?;
+            if next.iter().any(|s| s == state || expanded.contains_key(s)) {
+                return None;
+            }
+            Some(next)
+        })
+    }
+}
+
+impl TransitionSystem for ReducedProgram<'_> {
+    type State = Vec<i64>;
+
+    fn initial_states(&self) -> Vec<Vec<i64>> {
+        let initial = self.program.initial_states();
+        self.generated.borrow_mut().extend(initial.iter().cloned());
+        initial
+    }
+
+    /// Panics when a command cannot be executed, as the program does.
+    fn successors(&self, state: &Vec<i64>) -> Vec<Vec<i64>> {
+        if let Some(next) = self.successors.borrow().get(state) {
+            return next.clone();
+        }
+
+        let full = self.program.successors(state);
+        let enabled = self
+            .program
+            .enabled(state)
+            .unwrap_or_else(|e| panic!("{}", e));
+        let reduced = if self.reduce {
+            self.ample(state, &enabled)
+        } else {
+            None
+        };
+
+        let mut next = match reduced {
+            Some(next) => {
+                self.reduced_states.set(self.reduced_states.get() + 1);
+                let mut skipped = self.skipped.borrow_mut();
+                skipped.extend(full.into_iter().filter(|s| !next.contains(s)));
+                next
+            }
+            None => full,
+        };
+        next.sort();
+        next.dedup();
+
+        self.generated.borrow_mut().extend(next.iter().cloned());
+        self.successors
+            .borrow_mut()
+            .insert(state.clone(), next.clone());
+        next
+    }
+
+    fn labels(&self, state: &Vec<i64>) -> HashSet<String> {
+        self.program.labels(state)
+    }
+
+    fn has_label(&self, state: &Vec<i64>, label: &str) -> bool {
+        self.program.has_label(state, label)
+    }
+
+    fn state_name(&self, state: &Vec<i64>) -> String {
+        self.program.state_name(state)
+    }
+}
+
+/// Check an LTL formula on `program` with partial order reduction.
+///
+/// The reduction preserves formulae without next, so it is only applied to
+/// those, and the report tells whether it was.
+pub fn check_ltl_por(
+    program: &GuardedProgram,
+    formula: &LTLFormulae,
+) -> (Result<(), LtlError<Vec<i64>>>, PorReport) {
+    let system = if formula.is_next_free() {
+        ReducedProgram::new(program, &formula.atomics())
+    } else {
+        ReducedProgram::full(program)
+    };
+    let result = check_ltl(&system, formula);
+    (result, system.report())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::ltl_parser::parse_ltl;
+
+    /// Three processes counting to 2 on their own variable, and a lock shared
+    /// by the last two.
+    const COUNTERS: &str = r#"
+    {
+        "variables": [
+            { "name": "a", "min": 0, "max": 2, "init": 0 },
+            { "name": "b", "min": 0, "max": 2, "init": 0 },
+            { "name": "c", "min": 0, "max": 2, "init": 0 },
+            { "name": "lock", "min": 0, "max": 1, "init": 0 }
+        ],
+        "processes": [
+            {
+                "name": "p",
+                "commands": [ { "guard": "a < 2", "updates": { "a": "a + 1" } } ]
+            },
+            {
+                "name": "q",
+                "commands": [
+                    { "guard": "b == 0 && lock == 0", "updates": { "b": "1", "lock": "1" } },
+                    { "guard": "b == 1", "updates": { "b": "2", "lock": "0" } }
+                ]
+            },
+            {
+                "name": "r",
+                "commands": [
+                    { "guard": "c == 0 && lock == 0", "updates": { "c": "1", "lock": "1" } },
+                    { "guard": "c == 1", "updates": { "c": "2", "lock": "0" } }
+                ]
+            }
+        ],
+        "labels": {
+            "done": "a == 2 && b == 2 && c == 2",
+            "half": "a == 1",
+            "both": "b == 1 && c == 1",
+            "crit": "b == 1"
+        }
+    }"#;
+
+    #[test]
+    fn test_por_reduces() {
+        let program = GuardedProgram::from_json(COUNTERS).unwrap();
+        let formula = parse_ltl("G (crit -> F !crit)").unwrap();
+        let (result, report) = check_ltl_por(&program, &formula);
+        assert!(result.is_ok());
+        assert!(report.reduced);
+        assert!(report.reduced_states > 0);
+        assert!(report.pruned_states > 0);
+
+        let full = ReducedProgram::full(&program);
+        assert!(check_ltl(&full, &formula).is_ok());
+        assert!(report.expanded_states < full.report().expanded_states);
+    }
+
+    #[test]
+    fn test_por_preserves_verdicts() {
+        let program = GuardedProgram::from_json(COUNTERS).unwrap();
+        for formula in [
+            "F done",
+            "G !both",
+            "G (crit -> F !crit)",
+            "G !half",
+            "F G half",
+            "G (half -> F done)",
+            "!crit U half",
+            "F crit",
+        ] {
+            let formula = parse_ltl(formula).unwrap();
+            let (reduced, _) = check_ltl_por(&program, &formula);
+            let full = check_ltl(&program, &formula);
+            assert_eq!(reduced.is_ok(), full.is_ok(), "{}", formula);
+            if let Err(LtlError::Counterexample(lasso)) = reduced {
+                // the counterexample is a real path of the program
+                let path = lasso.prefix.iter().chain(&lasso.cycle).collect::<Vec<_>>();
+                assert_eq!(*path[0], program.initial_state());
+                assert!(path
+                    .windows(2)
+                    .all(|w| program.successors(w[0]).contains(w[1])));
+            }
+        }
+    }
+
+    #[test]
+    fn test_por_disabled_with_next() {
+        let program = GuardedProgram::from_json(COUNTERS).unwrap();
+        let (result, report) = check_ltl_por(&program, &parse_ltl("X half").unwrap());
+        assert!(result.is_err());
+        assert!(!report.reduced);
+        assert_eq!(report.reduced_states, 0);
+    }
+}
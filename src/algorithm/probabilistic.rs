@@ -0,0 +1,647 @@
+use std::collections::{HashMap, HashSet};
+
+use petgraph::graph::DiGraph;
+
+use crate::{
+    algorithm::explicit_state::explicit_state_checking,
+    model::dtmc::Dtmc,
+    pctl::{Bound, PCTLFormulae, PathFormulae, RewardFormulae},
+};
+
+/// The iterative method solving the linear equation systems.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Method {
+    /// Update the solution in place, using the new values at once.
+    #[default]
+    GaussSeidel,
+    /// Compute each new solution from the previous one only.
+    ValueIteration,
+    /// Iterate from below and from above until both meet, which bounds the
+    /// error soundly. Markov chains are solved as with Gauss-Seidel.
+    IntervalIteration,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Numerics {
+    pub method: Method,
+    /// The iteration stops when no value changes by more than this.
+    pub epsilon: f64,
+    pub max_iterations: usize,
+}
+
+impl Default for Numerics {
+    fn default() -> Self {
+        Numerics {
+            method: Method::default(),
+            epsilon: 1e-10,
+            max_iterations: 100_000,
+        }
+    }
+}
+
+/// A Markov chain over dense state indices.
+pub(crate) struct Chain {
+    pub ids: Vec<usize>,
+    pub rows: Vec<Vec<(usize, f64)>>,
+    pub pre: Vec<Vec<usize>>,
+}
+
+impl Chain {
+    pub fn new(model: &Dtmc) -> Chain {
+        let mut ids = model.kripke.states.keys().cloned().collect::<Vec<usize>>();
+        ids.sort();
+        let index = ids
+            .iter()
+            .enumerate()
+            .map(|(i, id)| (*id, i))
+            .collect::<HashMap<usize, usize>>();
+        let mut rows = vec![Vec::new(); ids.len()];
+        let mut pre = vec![Vec::new(); ids.len()];
+        for (i, id) in ids.iter().enumerate() {
+            let mut row = model.probabilities[id]
+                .iter()
+                .map(|(to, p)| (index[to], *p))
+                .collect::<Vec<(usize, f64)>>();
+            row.sort_by_key(|(to, _)| *to);
+            for (to, _) in &row {
+                pre[*to].push(i);
+            }
+            rows[i] = row;
+        }
+        Chain { ids, rows, pre }
+    }
+
+    pub fn indicator(&self, states: &HashSet<usize>) -> Vec<bool> {
+        self.ids.iter().map(|id| states.contains(id)).collect()
+    }
+
+    pub fn by_id(&self, values: Vec<f64>) -> HashMap<usize, f64> {
+        self.ids.iter().cloned().zip(values).collect()
+    }
+}
+
+/// The states from which a `target` state can be reached through `through`
+/// states.
+pub(crate) fn backward(pre: &[Vec<usize>], target: &[bool], through: &[bool]) -> Vec<bool> {
+    let mut reached = target.to_vec();
+    let mut stack = (0..target.len())
+        .filter(|s| target[*s])
+        .collect::<Vec<usize>>();
+    while let Some(s) = stack.pop() {
+        for p in &pre[s] {
+            if !reached[*p] && through[*p] {
+                reached[*p] = true;
+                stack.push(*p);
+            }
+        }
+    }
+    reached
+}
+
+/// Solve `x[s] = reward[s] + Σ P(s, t) x[t]` for the `unknown` states, the
+/// others keeping their value in `x`.
+pub(crate) fn solve(
+    chain: &Chain,
+    unknown: &[bool],
+    reward: &[f64],
+    x: &mut [f64],
+    numerics: &Numerics,
+) {
+    for _ in 0..numerics.max_iterations {
+        let previous = x.to_vec();
+        let mut change = 0.0_f64;
+        for s in (0..x.len()).filter(|s| unknown[*s]) {
+            let source = match numerics.method {
+                Method::GaussSeidel | Method::IntervalIteration => &*x,
+                Method::ValueIteration => &previous,
+            };
+            let value = reward[s]
+                + chain.rows[s]
+                    .iter()
+                    .map(|(t, p)| p * source[*t])
+                    .sum::<f64>();
+            change = change.max((value - x[s]).abs());
+            x[s] = value;
+        }
+        if change < numerics.epsilon {
+            break;
+        }
+    }
+}
+
+/// The probability of `phi U psi` from each state.
+fn until(chain: &Chain, phi: &[bool], psi: &[bool], numerics: &Numerics) -> Vec<f64> {
+    // the states with probability 0, which cannot reach psi, and 1, which
+    // cannot reach a state with probability 0 before psi
+    let positive = backward(&chain.pre, psi, phi);
+    let zero = positive.iter().map(|p| !p).collect::<Vec<bool>>();
+    let before = (0..psi.len())
+        .map(|s| phi[s] && !psi[s])
+        .collect::<Vec<bool>>();
+    let not_one = backward(&chain.pre, &zero, &before);
+
+    let mut x = not_one
+        .iter()
+        .map(|n| if *n { 0.0 } else { 1.0 })
+        .collect::<Vec<f64>>();
+    let unknown = (0..x.len())
+        .map(|s| positive[s] && not_one[s])
+        .collect::<Vec<bool>>();
+    solve(chain, &unknown, &vec![0.0; x.len()], &mut x, numerics);
+    x
+}
+
+/// The probability of `phi U<=k psi` from each state.
+fn bounded_until(chain: &Chain, phi: &[bool], psi: &[bool], k: usize) -> Vec<f64> {
+    let mut x = psi
+        .iter()
+        .map(|p| if *p { 1.0 } else { 0.0 })
+        .collect::<Vec<f64>>();
+    for _ in 0..k {
+        x = (0..x.len())
+            .map(|s| match (psi[s], phi[s]) {
+                (true, _) => 1.0,
+                (false, true) => chain.rows[s].iter().map(|(t, p)| p * x[*t]).sum(),
+                (false, false) => 0.0,
+            })
+            .collect();
+    }
+    x
+}
+
+/// The expected reward accumulated until reaching a `target` state, from
+/// each state.
+///
+/// The reward is infinite from the states which may never reach a target
+/// state.
+fn reachability_reward(
+    chain: &Chain,
+    reward: &[f64],
+    target: &[bool],
+    numerics: &Numerics,
+) -> Vec<f64> {
+    let all = vec![true; target.len()];
+    let missed = backward(&chain.pre, target, &all)
+        .iter()
+        .map(|r| !r)
+        .collect::<Vec<bool>>();
+    let before = target.iter().map(|t| !t).collect::<Vec<bool>>();
+    let infinite = backward(&chain.pre, &missed, &before);
+
+    let mut x = infinite
+        .iter()
+        .map(|i| if *i { f64::INFINITY } else { 0.0 })
+        .collect::<Vec<f64>>();
+    let unknown = (0..x.len())
+        .map(|s| !infinite[s] && !target[s])
+        .collect::<Vec<bool>>();
+    solve(chain, &unknown, reward, &mut x, numerics);
+    x
+}
+
+/// The expected reward accumulated in the first `k` steps, from each state.
+fn cumulative_reward(chain: &Chain, reward: &[f64], k: usize) -> Vec<f64> {
+    let mut x = vec![0.0; reward.len()];
+    for _ in 0..k {
+        x = (0..x.len())
+            .map(|s| reward[s] + chain.rows[s].iter().map(|(t, p)| p * x[*t]).sum::<f64>())
+            .collect();
+    }
+    x
+}
+
+/// The long-run probability of being in a `phi` state from each state.
+///
+/// Each bottom strongly connected component is eventually reached and never
+/// left, so this is the probability of reaching each of them, weighted by
+/// the part of `phi` states in their stationary distribution.
+fn steady_state(chain: &Chain, phi: &[bool], numerics: &Numerics) -> Vec<f64> {
+    let mut graph = DiGraph::<(), ()>::new();
+    let nodes = (0..chain.ids.len())
+        .map(|_| graph.add_node(()))
+        .collect::<Vec<_>>();
+    for (s, row) in chain.rows.iter().enumerate() {
+        for (t, _) in row {
+            graph.add_edge(nodes[s], nodes[*t], ());
+        }
+    }
+
+    let mut x = vec![0.0; chain.ids.len()];
+    let mut unknown = vec![true; chain.ids.len()];
+    for scc in petgraph::algo::tarjan_scc(&graph) {
+        let members = scc.iter().map(|n| n.index()).collect::<HashSet<usize>>();
+        let bottom = members
+            .iter()
+            .all(|s| chain.rows[*s].iter().all(|(t, _)| members.contains(t)));
+        if !bottom {
+            continue;
+        }
+        let members = members.into_iter().collect::<Vec<usize>>();
+        let pi = stationary(chain, &members, numerics);
+        let value = members
+            .iter()
+            .zip(&pi)
+            .filter(|(s, _)| phi[**s])
+            .map(|(_, p)| p)
+            .sum::<f64>();
+        for s in members {
+            x[s] = value;
+            unknown[s] = false;
+        }
+    }
+    solve(chain, &unknown, &vec![0.0; x.len()], &mut x, numerics);
+    x
+}
+
+/// The stationary distribution of a bottom strongly connected component.
+///
+/// The iteration is on the chain staying in place with probability 1/2,
+/// which has the same stationary distribution and is aperiodic, so that it
+/// converges.
+fn stationary(chain: &Chain, members: &[usize], numerics: &Numerics) -> Vec<f64> {
+    let position = members
+        .iter()
+        .enumerate()
+        .map(|(i, s)| (*s, i))
+        .collect::<HashMap<usize, usize>>();
+    let mut pre = vec![Vec::new(); members.len()];
+    for (i, s) in members.iter().enumerate() {
+        for (t, p) in &chain.rows[*s] {
+            pre[position[t]].push((i, p / 2.0));
+        }
+    }
+
+    let mut pi = vec![1.0 / members.len() as f64; members.len()];
+    for _ in 0..numerics.max_iterations {
+        let previous = pi.clone();
+        for j in 0..pi.len() {
+            let source = match numerics.method {
+                Method::GaussSeidel | Method::IntervalIteration => &pi,
+                Method::ValueIteration => &previous,
+            };
+            pi[j] = source[j] / 2.0 + pre[j].iter().map(|(i, p)| source[*i] * p).sum::<f64>();
+        }
+        let total = pi.iter().sum::<f64>();
+        pi.iter_mut().for_each(|p| *p /= total);
+        let change = pi
+            .iter()
+            .zip(&previous)
+            .map(|(a, b)| (a - b).abs())
+            .fold(0.0, f64::max);
+        if change < numerics.epsilon {
+            break;
+        }
+    }
+    pi
+}
+
+/// The probability of the paths from each state satisfying `path`.
+pub fn path_probabilities(
+    model: &mut Dtmc,
+    path: &PathFormulae,
+    numerics: &Numerics,
+) -> Result<HashMap<usize, f64>, String> {
+    let chain = Chain::new(model);
+    let mut indicator = |f: &PCTLFormulae| {
+        let states = satisfying_states(model, f, numerics)?;
+        Ok::<_, String>(chain.indicator(&states))
+    };
+    let values = match path {
+        PathFormulae::Next(f) => {
+            let phi = indicator(f)?;
+            chain
+                .rows
+                .iter()
+                .map(|row| row.iter().filter(|(t, _)| phi[*t]).map(|(_, p)| p).sum())
+                .collect()
+        }
+        PathFormulae::Until(f, g, bound) => {
+            let (phi, psi) = (indicator(f)?, indicator(g)?);
+            match bound {
+                Some(k) => bounded_until(&chain, &phi, &psi, *k),
+                None => until(&chain, &phi, &psi, numerics),
+            }
+        }
+        PathFormulae::Globally(f, bound) => {
+            // G f holds on the paths where F !f does not
+            let phi = vec![true; chain.ids.len()];
+            let psi = indicator(f)?.iter().map(|s| !s).collect::<Vec<bool>>();
+            let eventually = match bound {
+                Some(k) => bounded_until(&chain, &phi, &psi, *k),
+                None => until(&chain, &phi, &psi, numerics),
+            };
+            eventually.iter().map(|p| 1.0 - p).collect()
+        }
+    };
+    Ok(chain.by_id(values))
+}
+
+/// The long-run probability of being in a state satisfying `formula`, from
+/// each state.
+pub fn steady_state_probabilities(
+    model: &mut Dtmc,
+    formula: &PCTLFormulae,
+    numerics: &Numerics,
+) -> Result<HashMap<usize, f64>, String> {
+    let chain = Chain::new(model);
+    let phi = chain.indicator(&satisfying_states(model, formula, numerics)?);
+    Ok(chain.by_id(steady_state(&chain, &phi, numerics)))
+}
+
+/// The expected reward of `rewards` from each state.
+pub fn expected_rewards(
+    model: &mut Dtmc,
+    rewards: &RewardFormulae,
+    numerics: &Numerics,
+) -> Result<HashMap<usize, f64>, String> {
+    let chain = Chain::new(model);
+    let reward = chain
+        .ids
+        .iter()
+        .map(|id| model.reward(*id))
+        .collect::<Vec<f64>>();
+    let values = match rewards {
+        RewardFormulae::Reachability(f) => {
+            let target = chain.indicator(&satisfying_states(model, f, numerics)?);
+            reachability_reward(&chain, &reward, &target, numerics)
+        }
+        RewardFormulae::Cumulative(k) => cumulative_reward(&chain, &reward, *k),
+    };
+    Ok(chain.by_id(values))
+}
+
+/// The states satisfying `formula`.
+///
+/// Fails if the formula is a query or a CTL formula cannot be checked, see
+/// [`explicit_state_checking`].
+pub fn satisfying_states(
+    model: &mut Dtmc,
+    formula: &PCTLFormulae,
+    numerics: &Numerics,
+) -> Result<HashSet<usize>, String> {
+    let all = || {
+        model
+            .kripke
+            .states
+            .keys()
+            .cloned()
+            .collect::<HashSet<usize>>()
+    };
+    if formula.is_query() {
+        return Err(format!("{} is a query, which has no truth value", formula));
+    }
+    Ok(match formula {
+        PCTLFormulae::Ctl(f) => {
+            let label = explicit_state_checking(&mut model.kripke, f.clone())?;
+            model
+                .kripke
+                .get_state_with_label(label)
+                .into_iter()
+                .collect()
+        }
+        PCTLFormulae::Not(f) => {
+            let states = all();
+            let sat = satisfying_states(model, f, numerics)?;
+            states.difference(&sat).cloned().collect()
+        }
+        PCTLFormulae::And(f, g) => {
+            let sat = satisfying_states(model, f, numerics)?;
+            let other = satisfying_states(model, g, numerics)?;
+            sat.intersection(&other).cloned().collect()
+        }
+        PCTLFormulae::Or(f, g) => {
+            let mut sat = satisfying_states(model, f, numerics)?;
+            sat.extend(satisfying_states(model, g, numerics)?);
+            sat
+        }
+        PCTLFormulae::Probability(bound, path) => {
+            within(bound, path_probabilities(model, path, numerics)?)
+        }
+        PCTLFormulae::SteadyState(bound, f) => {
+            within(bound, steady_state_probabilities(model, f, numerics)?)
+        }
+        PCTLFormulae::Reward(bound, rewards) => {
+            within(bound, expected_rewards(model, rewards, numerics)?)
+        }
+    })
+}
+
+/// The states whose value is within `bound`, which must not be a query.
+fn within(bound: &Bound, values: HashMap<usize, f64>) -> HashSet<usize> {
+    values
+        .into_iter()
+        .filter(|(_, p)| bound.holds(*p))
+        .map(|(s, _)| s)
+        .collect()
+}
+
+/// Check whether `formula` holds in the initial state of `model`.
+pub fn pctl_checking(
+    model: &mut Dtmc,
+    formula: &PCTLFormulae,
+    numerics: &Numerics,
+) -> Result<bool, String> {
+    let initial = model.kripke.initial_state;
+    Ok(satisfying_states(model, formula, numerics)?.contains(&initial))
+}
+
+/// The probability or the reward asked by a query, `P=? [...]`, `S=? [...]`
+/// or `R=? [...]`, in the initial state of `model`. `Pmax=?` and `Pmin=?` are
+/// the same as `P=?` on Markov chains, and likewise for rewards.
+///
+/// Fails if the formula is not a query, or as [`satisfying_states`] does.
+pub fn pctl_query(
+    model: &mut Dtmc,
+    formula: &PCTLFormulae,
+    numerics: &Numerics,
+) -> Result<f64, String> {
+    let initial = model.kripke.initial_state;
+    let values = match formula {
+        PCTLFormulae::Probability(bound, path) if bound.is_query() => {
+            path_probabilities(model, path, numerics)?
+        }
+        PCTLFormulae::SteadyState(bound, f) if bound.is_query() => {
+            steady_state_probabilities(model, f, numerics)?
+        }
+        PCTLFormulae::Reward(bound, rewards) if bound.is_query() => {
+            expected_rewards(model, rewards, numerics)?
+        }
+        _ => return Err(format!("{} is not a query", formula)),
+    };
+    Ok(values[&initial])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::pctl_parser::parse_pctl;
+
+    /// Knuth and Yao's simulation of a die by a fair coin: the states 7 to 12
+    /// are the faces 1 to 6.
+    const DIE: &str = r#"
+    {
+        "states": [
+            { "id": 0, "name": "s0", "labels": ["init"], "transit_to": [1, 2], "probabilities": [0.5, 0.5] },
+            { "id": 1, "name": "s1", "labels": [], "transit_to": [3, 4], "probabilities": [0.5, 0.5] },
+            { "id": 2, "name": "s2", "labels": [], "transit_to": [5, 6], "probabilities": [0.5, 0.5] },
+            { "id": 3, "name": "s3", "labels": [], "transit_to": [1, 7], "probabilities": [0.5, 0.5] },
+            { "id": 4, "name": "s4", "labels": [], "transit_to": [8, 9], "probabilities": [0.5, 0.5] },
+            { "id": 5, "name": "s5", "labels": [], "transit_to": [10, 11], "probabilities": [0.5, 0.5] },
+            { "id": 6, "name": "s6", "labels": [], "transit_to": [2, 12], "probabilities": [0.5, 0.5] },
+            { "id": 7, "name": "d1", "labels": ["one", "done", "odd"], "transit_to": [] },
+            { "id": 8, "name": "d2", "labels": ["two", "done"], "transit_to": [] },
+            { "id": 9, "name": "d3", "labels": ["three", "done", "odd"], "transit_to": [] },
+            { "id": 10, "name": "d4", "labels": ["four", "done"], "transit_to": [] },
+            { "id": 11, "name": "d5", "labels": ["five", "done", "odd"], "transit_to": [] },
+            { "id": 12, "name": "d6", "labels": ["six", "done"], "transit_to": [] }
+        ],
+        "initial_state": 0
+    }"#;
+
+    /// A channel which goes down with probability 0.1 and is repaired with
+    /// probability 0.5 at each step.
+    const CHANNEL: &str = r#"
+    {
+        "states": [
+            { "id": 0, "name": "up", "labels": ["up"], "transit_to": [0, 1], "probabilities": [0.9, 0.1] },
+            { "id": 1, "name": "down", "labels": ["down"], "transit_to": [0, 1], "probabilities": [0.5, 0.5] }
+        ],
+        "initial_state": 0
+    }"#;
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-6
+    }
+
+    #[test]
+    fn test_until() {
+        for method in [Method::GaussSeidel, Method::ValueIteration] {
+            let numerics = Numerics {
+                method,
+                ..Numerics::default()
+            };
+            let mut die = Dtmc::from_json(DIE).unwrap();
+            for face in ["one", "two", "three", "four", "five", "six"] {
+                let query = parse_pctl(&format!("P=? [F {}]", face)).unwrap();
+                assert!(close(
+                    pctl_query(&mut die, &query, &numerics).unwrap(),
+                    1.0 / 6.0
+                ));
+            }
+            let query = parse_pctl("P=? [!done U odd]").unwrap();
+            assert!(close(pctl_query(&mut die, &query, &numerics).unwrap(), 0.5));
+            let formula = parse_pctl("P>=1 [F done] && AG (done -> AX done)").unwrap();
+            assert!(pctl_checking(&mut die, &formula, &numerics).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_bounded_and_next() {
+        let numerics = Numerics::default();
+        let mut die = Dtmc::from_json(DIE).unwrap();
+        // only s3 and s6 may not throw the die in 3 steps
+        let query = parse_pctl("P=? [F<=3 done]").unwrap();
+        assert!(close(
+            pctl_query(&mut die, &query, &numerics).unwrap(),
+            0.75
+        ));
+        let query = parse_pctl("P=? [G<=3 !done]").unwrap();
+        assert!(close(
+            pctl_query(&mut die, &query, &numerics).unwrap(),
+            0.25
+        ));
+        // s3 goes to one, s1 to s3 and s0 to s1 with probability 1/2
+        let formula = parse_pctl("P>=0.5 [X P>0.4 [X P>0.4 [X one]]]").unwrap();
+        assert!(pctl_checking(&mut die, &formula, &numerics).unwrap());
+        let states =
+            satisfying_states(&mut die, &parse_pctl("P>0.4 [X one]").unwrap(), &numerics).unwrap();
+        assert_eq!(states, HashSet::from([3, 7]));
+    }
+
+    #[test]
+    fn test_steady_state() {
+        for method in [Method::GaussSeidel, Method::ValueIteration] {
+            let numerics = Numerics {
+                method,
+                ..Numerics::default()
+            };
+            let mut channel = Dtmc::from_json(CHANNEL).unwrap();
+            // pi(down) = 0.1 / (0.1 + 0.5)
+            let query = parse_pctl("S=? [down]").unwrap();
+            assert!(close(
+                pctl_query(&mut channel, &query, &numerics).unwrap(),
+                1.0 / 6.0
+            ));
+            let formula = parse_pctl("S>=0.8 [up] && P<0.2 [X down]").unwrap();
+            assert!(pctl_checking(&mut channel, &formula, &numerics).unwrap());
+
+            let mut die = Dtmc::from_json(DIE).unwrap();
+            let query = parse_pctl("S=? [odd]").unwrap();
+            assert!(close(pctl_query(&mut die, &query, &numerics).unwrap(), 0.5));
+        }
+    }
+
+    #[test]
+    fn test_rewards() {
+        // each failed send is retransmitted
+        let retransmission = r#"
+        {
+            "states": [
+                { "id": 0, "name": "send", "labels": ["sending"], "transit_to": [1, 0], "probabilities": [0.9, 0.1], "rewards": [0, 1] },
+                { "id": 1, "name": "delivered", "labels": ["delivered"], "transit_to": [] }
+            ],
+            "initial_state": 0
+        }"#;
+        let numerics = Numerics::default();
+        let mut chain = Dtmc::from_json(retransmission).unwrap();
+        let query = parse_pctl("R=? [F delivered]").unwrap();
+        assert!(close(
+            pctl_query(&mut chain, &query, &numerics).unwrap(),
+            1.0 / 9.0
+        ));
+        let query = parse_pctl("R=? [C<=2]").unwrap();
+        assert!(close(
+            pctl_query(&mut chain, &query, &numerics).unwrap(),
+            0.11
+        ));
+
+        // a coin is flipped in each state before the die is thrown
+        let data = DIE
+            .replace(
+                r#""labels": [], "transit_to""#,
+                r#""labels": [], "reward": 1, "transit_to""#,
+            )
+            .replace(
+                r#"["init"], "transit_to""#,
+                r#"["init"], "reward": 1, "transit_to""#,
+            );
+        let mut die = Dtmc::from_json(&data).unwrap();
+        let query = parse_pctl("R=? [F done]").unwrap();
+        assert!(close(
+            pctl_query(&mut die, &query, &numerics).unwrap(),
+            11.0 / 3.0
+        ));
+        let query = parse_pctl("R=? [F (one && two)]").unwrap();
+        assert_eq!(
+            pctl_query(&mut die, &query, &numerics).unwrap(),
+            f64::INFINITY
+        );
+        let formula = parse_pctl("R<4 [F done] && R<=3 [C<=3]").unwrap();
+        assert!(pctl_checking(&mut die, &formula, &numerics).unwrap());
+
+        let data = retransmission.replace("[0, 1]", "[0, -1]");
+        assert!(matches!(
+            Dtmc::from_json(&data),
+            Err(crate::model::dtmc::MarkovError::NegativeReward(0))
+        ));
+    }
+
+    #[test]
+    fn test_invalid_distribution() {
+        let data = CHANNEL.replace("[0.9, 0.1]", "[0.9, 0.2]");
+        assert!(matches!(
+            Dtmc::from_json(&data),
+            Err(crate::model::dtmc::MarkovError::Distribution(0, _))
+        ));
+    }
+}
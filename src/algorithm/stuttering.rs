@@ -0,0 +1,307 @@
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+
+use petgraph::graph::DiGraph;
+
+use crate::{
+    algorithm::{
+        bisimulation::{quotient, Dense, Quotient},
+        ctl_checking, ltl_checking,
+        on_the_fly::LtlError,
+    },
+    ctl::CTLFormulae,
+    ltl::LTLFormulae,
+    model::kripke::Kripke,
+};
+
+/// The coarsest divergence-sensitive stuttering bisimulation of `model` with
+/// respect to `labels`, all labels if `None`, and whether each block is
+/// divergent, that is its states can stay in it forever.
+fn stuttering_partition(model: &Kripke, labels: Option<&[&str]>) -> Vec<(Vec<usize>, bool)> {
+    let dense = Dense::new(model);
+    let n = dense.ids.len();
+    let classes = dense.label_classes(model, labels);
+
+    // states on a cycle of equally labelled states are equivalent and
+    // divergent, so these cycles are collapsed first
+    let mut inert = DiGraph::<usize, ()>::new();
+    let nodes = (0..n).map(|x| inert.add_node(x)).collect::<Vec<_>>();
+    for x in 0..n {
+        for y in &dense.post[x] {
+            if classes[x] == classes[*y] {
+                inert.add_edge(nodes[x], nodes[*y], ());
+            }
+        }
+    }
+    let sccs = petgraph::algo::tarjan_scc(&inert)
+        .into_iter()
+        .map(|scc| scc.into_iter().map(|i| inert[i]).collect::<Vec<usize>>())
+        .collect::<Vec<_>>();
+    let mut scc_of = vec![0; n];
+    for (c, scc) in sccs.iter().enumerate() {
+        for x in scc {
+            scc_of[*x] = c;
+        }
+    }
+
+    // divergence is made visible by a step to a fresh sink state with a label
+    // of its own
+    let sink = sccs.len();
+    let mut post = vec![BTreeSet::new(); sink + 1];
+    let mut pre = vec![BTreeSet::new(); sink + 1];
+    let mut divergent = vec![false; sink];
+    for x in 0..n {
+        for y in &dense.post[x] {
+            let (c, d) = (scc_of[x], scc_of[*y]);
+            if c == d {
+                divergent[c] = true;
+            } else {
+                post[c].insert(d);
+                pre[d].insert(c);
+            }
+        }
+    }
+    for (c, divergent) in divergent.iter().enumerate() {
+        if *divergent {
+            post[c].insert(sink);
+            pre[sink].insert(c);
+        }
+    }
+
+    let mut initial: BTreeMap<Option<usize>, Vec<usize>> = BTreeMap::new();
+    for (c, scc) in sccs.iter().enumerate() {
+        initial.entry(Some(classes[scc[0]])).or_default().push(c);
+    }
+    initial.insert(None, vec![sink]);
+    let mut blocks = initial.into_values().collect::<Vec<Vec<usize>>>();
+    let mut block_of = vec![0; sink + 1];
+    for (b, members) in blocks.iter().enumerate() {
+        for c in members {
+            block_of[*c] = b;
+        }
+    }
+
+    // Groote and Vaandrager: a block is split by another one into the states
+    // that can reach it by steps within the block and the others, until no
+    // block splits any more
+    let mut changed = true;
+    while changed {
+        changed = false;
+        let mut splitter = 0;
+        while splitter < blocks.len() {
+            let mut sources: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
+            for d in &blocks[splitter] {
+                for c in &pre[*d] {
+                    if block_of[*c] != splitter {
+                        sources.entry(block_of[*c]).or_default().push(*c);
+                    }
+                }
+            }
+
+            for (b, direct) in sources {
+                let mut reaching = direct.iter().cloned().collect::<HashSet<usize>>();
+                let mut stack = direct;
+                while let Some(d) = stack.pop() {
+                    for c in &pre[d] {
+                        if block_of[*c] == b && reaching.insert(*c) {
+                            stack.push(*c);
+                        }
+                    }
+                }
+
+                if reaching.len() < blocks[b].len() {
+                    blocks[b].retain(|c| !reaching.contains(c));
+                    let new = blocks.len();
+                    let mut part = reaching.into_iter().collect::<Vec<usize>>();
+                    part.sort();
+                    for c in &part {
+                        block_of[*c] = new;
+                    }
+                    blocks.push(part);
+                    changed = true;
+                }
+            }
+            splitter += 1;
+        }
+    }
+
+    let mut result = blocks
+        .into_iter()
+        .filter(|members| members[0] != sink)
+        .map(|members| {
+            let mut ids = members
+                .iter()
+                .flat_map(|c| sccs[*c].iter().map(|x| dense.ids[*x]))
+                .collect::<Vec<usize>>();
+            ids.sort();
+            (ids, members.iter().any(|c| divergent[*c]))
+        })
+        .collect::<Vec<_>>();
+    result.sort();
+    result
+}
+
+/// The coarsest partition of the states of `model` that is a
+/// divergence-sensitive stuttering bisimulation with respect to `labels`,
+/// all labels if `None`.
+pub fn stuttering_bisimulation(model: &Kripke, labels: Option<&[&str]>) -> Vec<Vec<usize>> {
+    stuttering_partition(model, labels)
+        .into_iter()
+        .map(|(block, _)| block)
+        .collect()
+}
+
+/// Minimize `model` by divergence-sensitive stuttering bisimulation over
+/// `labels`, all labels if `None`.
+///
+/// Steps within a block are dropped, except for a self-loop on divergent
+/// blocks. The quotient satisfies the same CTL* formulae without next over
+/// these labels.
+pub fn stuttering_quotient(model: &Kripke, labels: Option<&[&str]>) -> Quotient {
+    let partition = stuttering_partition(model, labels);
+    let (blocks, divergent): (Vec<Vec<usize>>, Vec<bool>) = partition.into_iter().unzip();
+    quotient(model, &blocks, labels, |b| divergent[b])
+}
+
+/// Check a CTL formula, on the stuttering quotient of `model` over its
+/// atomic propositions if it has no next operator.
+///
+/// Returns the quotient the formula was checked on, if any, and otherwise
+/// labels `model` like `ctl_checking`.
+pub fn ctl_checking_reduced(
+    model: &mut Kripke,
+    formula: CTLFormulae,
+) -> Result<(bool, Option<Quotient>), String> {
+    if !formula.is_next_free() {
+        return Ok((ctl_checking(model, formula)?, None));
+    }
+
+    let atomics = formula.atomics();
+    let labels = atomics.iter().map(|a| a.as_str()).collect::<Vec<&str>>();
+    let mut reduced = stuttering_quotient(model, Some(&labels));
+    let holds = ctl_checking(&mut reduced.model, formula)?;
+    Ok((holds, Some(reduced)))
+}
+
+/// Check an LTL formula, on the stuttering quotient of `model` over its
+/// atomic propositions if it has no next operator.
+///
+/// Returns the quotient the formula was checked on, if any, in which case a
+/// counterexample is a path of blocks of the quotient.
+pub fn ltl_checking_reduced(
+    model: &Kripke,
+    formula: &LTLFormulae,
+) -> (Result<(), LtlError<usize>>, Option<Quotient>) {
+    if !formula.is_next_free() {
+        return (ltl_checking(model, formula), None);
+    }
+
+    let atomics = formula.atomics();
+    let labels = atomics.iter().map(|a| a.as_str()).collect::<Vec<&str>>();
+    let reduced = stuttering_quotient(model, Some(&labels));
+    (ltl_checking(&reduced.model, formula), Some(reduced))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parser::ctl_parser::parse_ctl, parser::ltl_parser::parse_ltl};
+
+    /// Two ways of reaching `q` through `p` in a varying number of steps,
+    /// and a state staying in `p` forever.
+    fn model() -> Kripke {
+        let data = r#"
+        {
+            "states": [
+                { "id": 0, "name": "s0", "labels": [], "transit_to": [1, 3, 7] },
+                { "id": 1, "name": "s1", "labels": [], "transit_to": [2] },
+                { "id": 2, "name": "s2", "labels": ["p"], "transit_to": [6] },
+                { "id": 3, "name": "s3", "labels": [], "transit_to": [4] },
+                { "id": 4, "name": "s4", "labels": ["p"], "transit_to": [5] },
+                { "id": 5, "name": "s5", "labels": ["p", "r"], "transit_to": [6] },
+                { "id": 6, "name": "s6", "labels": ["q"], "transit_to": [6] },
+                { "id": 7, "name": "s7", "labels": ["p"], "transit_to": [7] }
+            ],
+            "initial_state": 0
+        }"#;
+        Kripke::from_json(data).unwrap()
+    }
+
+    #[test]
+    fn test_stuttering_bisimulation() {
+        let k = model();
+        assert_eq!(
+            stuttering_bisimulation(&k, Some(&["p", "q"])),
+            vec![vec![0], vec![1, 3], vec![2, 4, 5], vec![6], vec![7]]
+        );
+        // divergence keeps s7 apart from the other states satisfying p
+        assert_eq!(
+            stuttering_bisimulation(&k, Some(&["p"])),
+            vec![vec![0], vec![1, 3], vec![2, 4, 5], vec![6], vec![7]]
+        );
+        assert_eq!(
+            stuttering_bisimulation(&k, Some(&[] as &[&str])),
+            vec![vec![0, 1, 2, 3, 4, 5, 6, 7]]
+        );
+    }
+
+    #[test]
+    fn test_stuttering_quotient() {
+        let k = model();
+        let q = stuttering_quotient(&k, Some(&["p", "q"]));
+        assert_eq!(q.model.states[&q.blocks[&4]], "[s2, s4, s5]");
+        assert_eq!(
+            q.reduction.to_string(),
+            "states: 8 -> 5, transitions: 10 -> 6"
+        );
+        // only divergent blocks keep a self-loop
+        assert!(q.model.transitions[&q.blocks[&6]].contains(&q.blocks[&6]));
+        assert!(!q.model.transitions[&q.blocks[&4]].contains(&q.blocks[&4]));
+    }
+
+    #[test]
+    fn test_reduced_ctl_verdicts() {
+        for formula in [
+            "AF q",
+            "EG p",
+            "E (!q U p)",
+            "AG (p -> AF q)",
+            "EF EG p",
+            "AG (r -> AF q)",
+            "EF (p && EF q)",
+            "AX p",
+        ] {
+            let formula = parse_ctl(formula).unwrap();
+            let (reduced, quotient) = ctl_checking_reduced(&mut model(), formula.clone()).unwrap();
+            assert_eq!(quotient.is_some(), formula.is_next_free());
+            assert_eq!(
+                reduced,
+                ctl_checking(&mut model(), formula.clone()).unwrap(),
+                "{}",
+                formula
+            );
+        }
+    }
+
+    #[test]
+    fn test_reduced_ltl_verdicts() {
+        for formula in [
+            "F q",
+            "G (p -> F q)",
+            "F G p",
+            "!q U p",
+            "G F q",
+            "G !r",
+            "F (p && X q)",
+        ] {
+            let formula = parse_ltl(formula).unwrap();
+            let (reduced, quotient) = ltl_checking_reduced(&model(), &formula);
+            assert_eq!(quotient.is_some(), formula.is_next_free());
+            assert_eq!(
+                reduced.is_ok(),
+                ltl_checking(&model(), &formula).is_ok(),
+                "{}",
+                formula
+            );
+        }
+    }
+}
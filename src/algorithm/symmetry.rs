@@ -0,0 +1,395 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Display,
+};
+
+use crate::{
+    algorithm::on_the_fly::{check_ltl, Lasso, LtlError},
+    ctl::CTLFormulae,
+    expr::{BinaryOp, Expr},
+    ltl::LTLFormulae,
+    model::{
+        guarded::{GuardedProgram, Symmetry},
+        transition_system::TransitionSystem,
+    },
+};
+
+/// A guarded program explored up to the permutation of identical processes.
+///
+/// Every state is replaced with a canonical representative of its orbit, the
+/// least state obtained by permuting the instances of each template. The
+/// quotient satisfies the same symmetric formulae as the program.
+pub struct SymmetricProgram<'a> {
+    program: &'a GuardedProgram,
+    /// All permutations of the instances of each template, when the template
+    /// has scalar variables and its instances cannot simply be sorted.
+    permutations: Vec<Vec<Vec<usize>>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SymmetryError {
+    /// The formula is not invariant under the permutation of the instances.
+    Asymmetric(String),
+    /// The formula cannot be checked, with the reason.
+    Unsupported(String),
+    /// A path of canonical states violating the formula.
+    Counterexample(Lasso<Vec<i64>>),
+}
+
+impl Display for SymmetryError {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SymmetryError::Asymmetric(f) => write!(formatter, "{} is not symmetric", f),
+            SymmetryError::Unsupported(reason) => write!(formatter, "{}", reason),
+            SymmetryError::Counterexample(lasso) => write!(
+                formatter,
+                "counterexample {:?} ({:?})^ω",
+                lasso.prefix, lasso.cycle
+            ),
+        }
+    }
+}
+
+impl<'a> SymmetricProgram<'a> {
+    pub fn new(program: &'a GuardedProgram) -> SymmetricProgram<'a> {
+        let permutations = program
+            .symmetries
+            .iter()
+            .map(|symmetry| {
+                if symmetry.scalars.is_empty() {
+                    Vec::new()
+                } else {
+                    permutations(symmetry.locals.len())
+                }
+            })
+            .collect();
+        SymmetricProgram {
+            program,
+            permutations,
+        }
+    }
+
+    /// The representative of the orbit of `state`.
+    pub fn canonical(&self, state: &[i64]) -> Vec<i64> {
+        let mut state = state.to_vec();
+        for (symmetry, permutations) in self.program.symmetries.iter().zip(&self.permutations) {
+            if permutations.is_empty() {
+                // without scalars, sorting the instances gives the least state
+                let mut blocks = symmetry
+                    .locals
+                    .iter()
+                    .map(|vars| vars.iter().map(|v| state[*v]).collect::<Vec<i64>>())
+                    .collect::<Vec<Vec<i64>>>();
+                blocks.sort();
+                for (vars, block) in symmetry.locals.iter().zip(blocks) {
+                    for (v, value) in vars.iter().zip(block) {
+                        state[*v] = value;
+                    }
+                }
+            } else {
+                state = permutations
+                    .iter()
+                    .map(|permutation| permute(symmetry, &state, permutation))
+                    .min()
+                    .unwrap();
+            }
+        }
+        state
+    }
+
+    /// Whether the atomic propositions of a formula are global labels which
+    /// every permutation of the instances leaves unchanged.
+    ///
+    /// The labels of the instances are not, even in a formula which is
+    /// invariant under their renaming: a path of the quotient may permute
+    /// the instances at every step, so it does not follow the labels of one
+    /// instance.
+    fn symmetric_atomics(&self, atomics: &HashSet<String>) -> bool {
+        let program = self.program;
+        let locals = program
+            .symmetries
+            .iter()
+            .flat_map(|symmetry| symmetry.locals.iter().flatten())
+            .cloned()
+            .collect::<HashSet<usize>>();
+        let scalars = program
+            .symmetries
+            .iter()
+            .flat_map(|symmetry| {
+                let n = symmetry.locals.len() as i64;
+                symmetry.scalars.iter().map(move |v| (*v, n))
+            })
+            .collect::<HashMap<usize, i64>>();
+        atomics.iter().all(|atomic| {
+            program
+                .label(atomic)
+                .is_some_and(|expr| invariant(expr, &locals, &scalars))
+        })
+    }
+
+    /// Whether `formula` is invariant under the permutation of the instances,
+    /// which is the case when its atomic propositions are.
+    pub fn is_symmetric_ltl(&self, formula: &LTLFormulae) -> bool {
+        self.symmetric_atomics(&formula.atomics())
+    }
+
+    /// Same as [`SymmetricProgram::is_symmetric_ltl`] for CTL.
+    pub fn is_symmetric_ctl(&self, formula: &CTLFormulae) -> bool {
+        self.symmetric_atomics(&formula.atomics())
+    }
+}
+
+impl TransitionSystem for SymmetricProgram<'_> {
+    type State = Vec<i64>;
+
+    fn initial_states(&self) -> Vec<Vec<i64>> {
+        vec![self.canonical(&self.program.initial_state())]
+    }
+
+    fn successors(&self, state: &Vec<i64>) -> Vec<Vec<i64>> {
+        let mut next = self
+            .program
+            .successors(state)
+            .iter()
+            .map(|s| self.canonical(s))
+            .collect::<Vec<Vec<i64>>>();
+        next.sort();
+        next.dedup();
+        next
+    }
+
+    fn labels(&self, state: &Vec<i64>) -> HashSet<String> {
+        self.program.labels(state)
+    }
+
+    fn has_label(&self, state: &Vec<i64>, label: &str) -> bool {
+        self.program.has_label(state, label)
+    }
+
+    fn state_name(&self, state: &Vec<i64>) -> String {
+        self.program.state_name(state)
+    }
+}
+
+/// Check a symmetric LTL formula on the quotient of `program`.
+pub fn check_ltl_symmetric(
+    program: &GuardedProgram,
+    formula: &LTLFormulae,
+) -> Result<(), SymmetryError> {
+    let quotient = SymmetricProgram::new(program);
+    if !quotient.is_symmetric_ltl(formula) {
+        return Err(SymmetryError::Asymmetric(formula.to_string()));
+    }
+    check_ltl(&quotient, formula).map_err(|error| match error {
+        LtlError::Unsupported(reason) => SymmetryError::Unsupported(reason),
+        LtlError::Counterexample(lasso) => SymmetryError::Counterexample(lasso),
+    })
+}
+
+/// Whether `expr` does not depend on the numbering of the instances: it
+/// reads no local variable, and only compares scalars with values that are
+/// not instance numbers.
+fn invariant(expr: &Expr, locals: &HashSet<usize>, scalars: &HashMap<usize, i64>) -> bool {
+    match expr {
+        Expr::Int(_) => true,
+        Expr::Var(v) => !locals.contains(v) && !scalars.contains_key(v),
+        Expr::Not(e) | Expr::Neg(e) => invariant(e, locals, scalars),
+        Expr::Binary(op, lhs, rhs) => match (op, &**lhs, &**rhs) {
+            (BinaryOp::Eq | BinaryOp::Neq, Expr::Var(v), Expr::Int(c))
+            | (BinaryOp::Eq | BinaryOp::Neq, Expr::Int(c), Expr::Var(v))
+                if scalars.contains_key(v) =>
+            {
+                !(0..scalars[v]).contains(c)
+            }
+            _ => invariant(lhs, locals, scalars) && invariant(rhs, locals, scalars),
+        },
+    }
+}
+
+/// Move the instance `i` of `symmetry` to `permutation[i]`.
+fn permute(symmetry: &Symmetry, state: &[i64], permutation: &[usize]) -> Vec<i64> {
+    let n = symmetry.locals.len();
+    let mut image = state.to_vec();
+    for (i, vars) in symmetry.locals.iter().enumerate() {
+        for (v, w) in vars.iter().zip(&symmetry.locals[permutation[i]]) {
+            image[*w] = state[*v];
+        }
+    }
+    for v in &symmetry.scalars {
+        if (0..n as i64).contains(&state[*v]) {
+            image[*v] = permutation[state[*v] as usize] as i64;
+        }
+    }
+    image
+}
+
+fn permutations(n: usize) -> Vec<Vec<usize>> {
+    if n == 0 {
+        return vec![Vec::new()];
+    }
+    let mut result = Vec::new();
+    for permutation in permutations(n - 1) {
+        for i in 0..n {
+            let mut p = permutation.clone();
+            p.insert(i, n - 1);
+            result.push(p);
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        model::guarded::ProgramError,
+        parser::{ctl_parser::parse_ctl, ltl_parser::parse_ltl},
+    };
+
+    /// Three processes competing for a lock, which records its owner.
+    const MUTEX: &str = r#"
+    {
+        "variables": [
+            { "name": "owner", "min": -1, "max": 2, "init": -1 }
+        ],
+        "templates": [
+            {
+                "name": "p",
+                "count": 3,
+                "variables": [ { "name": "pc", "min": 0, "max": 2, "init": 0 } ],
+                "scalars": ["owner"],
+                "commands": [
+                    { "name": "try", "guard": "pc == 0", "updates": { "pc": "1" } },
+                    {
+                        "name": "enter",
+                        "guard": "pc == 1 && owner == -1",
+                        "updates": { "pc": "2", "owner": "self" }
+                    },
+                    {
+                        "name": "leave",
+                        "guard": "pc == 2 && owner == self",
+                        "updates": { "pc": "0", "owner": "-1" }
+                    }
+                ],
+                "labels": { "wait": "pc == 1", "crit": "pc == 2" }
+            }
+        ],
+        "labels": { "free": "owner == -1", "mine": "owner == 0" }
+    }"#;
+
+    const MUTUAL_EXCLUSION: &str =
+        "G (!(crit_0 && crit_1) && !(crit_1 && crit_2) && !(crit_0 && crit_2))";
+
+    #[test]
+    fn test_templates() {
+        let program = GuardedProgram::from_json(MUTEX).unwrap();
+        assert_eq!(program.processes, vec!["p_0", "p_1", "p_2"]);
+        assert_eq!(program.variables[2].name, "pc_1");
+        assert_eq!(program.commands[4].name, "p_1.enter");
+        assert_eq!(program.apply(4, &[-1, 0, 1, 0]).unwrap(), vec![1, 0, 2, 0]);
+        assert_eq!(
+            program.symmetries[0].labels[0],
+            vec!["crit_0", "crit_1", "crit_2"]
+        );
+
+        let data = MUTEX.replace(
+            "\"templates\"",
+            r#""processes": [
+                { "name": "spy", "commands": [ { "guard": "pc_0 == 2", "updates": {} } ] }
+            ],
+            "templates""#,
+        );
+        assert!(matches!(
+            GuardedProgram::from_json(&data),
+            Err(ProgramError::Asymmetric(_))
+        ));
+    }
+
+    #[test]
+    fn test_canonical() {
+        let program = GuardedProgram::from_json(MUTEX).unwrap();
+        let quotient = SymmetricProgram::new(&program);
+        // p_2 owns the lock while p_0 waits
+        let state = vec![2, 1, 0, 2];
+        let canonical = quotient.canonical(&state);
+        assert_eq!(canonical, vec![0, 2, 0, 1]);
+        assert_eq!(quotient.canonical(&[0, 2, 1, 0]), canonical);
+        assert_eq!(quotient.canonical(&[1, 0, 2, 1]), canonical);
+
+        let full = program.to_kripke().states.len();
+        let reduced = quotient.to_kripke().states.len();
+        assert_eq!(full, 20);
+        assert!(reduced < full);
+    }
+
+    #[test]
+    fn test_symmetric_formulae() {
+        let program = GuardedProgram::from_json(MUTEX).unwrap();
+        let quotient = SymmetricProgram::new(&program);
+
+        assert!(quotient.is_symmetric_ltl(&parse_ltl("G F free").unwrap()));
+        // invariant under the renaming of the instances, but over their labels
+        assert!(!quotient.is_symmetric_ltl(&parse_ltl(MUTUAL_EXCLUSION).unwrap()));
+        assert!(!quotient.is_symmetric_ltl(&parse_ltl("G !(crit_0 && crit_1)").unwrap()));
+        assert!(!quotient.is_symmetric_ltl(&parse_ltl("G F crit_0").unwrap()));
+        assert!(!quotient.is_symmetric_ltl(&parse_ltl("G F mine").unwrap()));
+
+        assert!(quotient.is_symmetric_ctl(&parse_ctl("AG EF free").unwrap()));
+        assert!(!quotient
+            .is_symmetric_ctl(&parse_ctl("AG (EF crit_0 && EF crit_1 && EF crit_2)").unwrap()));
+        assert!(!quotient.is_symmetric_ctl(&parse_ctl("AG EF crit_2").unwrap()));
+    }
+
+    #[test]
+    fn test_check_symmetric() {
+        let program = GuardedProgram::from_json(MUTEX).unwrap();
+
+        let formula = parse_ltl("G F free").unwrap();
+        assert!(check_ltl_symmetric(&program, &formula).is_ok());
+        assert!(check_ltl(&program, &formula).is_ok());
+
+        let formula = parse_ltl("F G free").unwrap();
+        assert!(matches!(
+            check_ltl_symmetric(&program, &formula),
+            Err(SymmetryError::Counterexample(_))
+        ));
+        assert!(check_ltl(&program, &formula).is_err());
+
+        for formula in [MUTUAL_EXCLUSION, "G F crit_0"] {
+            assert!(matches!(
+                check_ltl_symmetric(&program, &parse_ltl(formula).unwrap()),
+                Err(SymmetryError::Asymmetric(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_instance_labels() {
+        // the quotient may swap the instances at every step, so that a path
+        // of canonical states does not follow the labels of one instance
+        let data = r#"
+        {
+            "variables": [],
+            "templates": [
+                {
+                    "name": "p",
+                    "count": 2,
+                    "variables": [ { "name": "pc", "min": 0, "max": 2, "init": 0 } ],
+                    "commands": [
+                        { "name": "up", "guard": "pc < 2", "updates": { "pc": "pc + 1" } },
+                        { "name": "reset", "guard": "pc == 2", "updates": { "pc": "0" } }
+                    ],
+                    "labels": { "hi": "pc == 2", "zero": "pc == 0" }
+                }
+            ]
+        }"#;
+        let program = GuardedProgram::from_json(data).unwrap();
+        let formula =
+            parse_ltl("G (hi_0 -> (hi_0 W zero_0)) && G (hi_1 -> (hi_1 W zero_1))").unwrap();
+        assert!(check_ltl(&program, &formula).is_ok());
+        assert!(matches!(
+            check_ltl_symmetric(&program, &formula),
+            Err(SymmetryError::Asymmetric(_))
+        ));
+    }
+}
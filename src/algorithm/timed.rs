@@ -0,0 +1,331 @@
+use std::{collections::HashMap, fmt::Display};
+
+use crate::{
+    model::timed::{
+        dbm::{Bound, Dbm},
+        TimedAutomaton,
+    },
+    tctl::{TCTLFormulae, TimeBound},
+};
+
+/// A formula outside the subset of TCTL which can be checked.
+#[derive(Debug)]
+pub struct Unsupported(pub TCTLFormulae);
+
+impl Display for Unsupported {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(formatter, "{} is not in the checked subset of TCTL", self.0)
+    }
+}
+
+impl std::error::Error for Unsupported {}
+
+/// Whether the propositional `formula` holds in `location`.
+fn holds(model: &TimedAutomaton, location: usize, formula: &TCTLFormulae) -> bool {
+    match formula {
+        TCTLFormulae::True => true,
+        TCTLFormulae::Atomic(a) => model.locations[&location].labels.contains(a),
+        TCTLFormulae::Not(f) => !holds(model, location, f),
+        TCTLFormulae::And(f, g) => holds(model, location, f) && holds(model, location, g),
+        TCTLFormulae::Or(f, g) => holds(model, location, f) || holds(model, location, g),
+        f => panic!("{} is not propositional", f),
+    }
+}
+
+/// Whether some valuation of `zone` has the clock `z` within `bound`.
+fn within(zone: &Dbm, z: usize, bound: TimeBound) -> bool {
+    let mut zone = zone.clone();
+    match bound {
+        TimeBound::Less(c) => zone.constrain(z, 0, Bound::less(c)),
+        TimeBound::LessEq(c) => zone.constrain(z, 0, Bound::less_eq(c)),
+        TimeBound::Greater(c) => zone.constrain(0, z, Bound::less(-c)),
+        TimeBound::GreaterEq(c) => zone.constrain(0, z, Bound::less_eq(-c)),
+    }
+}
+
+/// The times beyond an upper `bound`.
+fn beyond(bound: TimeBound) -> TimeBound {
+    match bound {
+        TimeBound::Less(c) => TimeBound::GreaterEq(c),
+        TimeBound::LessEq(c) => TimeBound::Greater(c),
+        _ => unreachable!("{} is not an upper bound", bound),
+    }
+}
+
+/// A symbolic state, with the flag of an observer.
+struct Node {
+    location: usize,
+    flag: bool,
+    zone: Dbm,
+    parent: Option<usize>,
+}
+
+/// Search the zone graph of `model`, with one more clock `z` and an
+/// observer flag, for a node which is `bad`.
+///
+/// Entering a location, the `observer` gives the new flag from the old one,
+/// and whether `z` is reset. The flag is `initial` before the initial
+/// location. `ceiling` is the largest constant `z` is compared with.
+/// Returns the locations leading to a bad node.
+fn search(
+    model: &TimedAutomaton,
+    initial: bool,
+    ceiling: i64,
+    observer: impl Fn(usize, bool) -> (bool, bool),
+    bad: impl Fn(&Node) -> bool,
+) -> Option<Vec<usize>> {
+    let z = model.clocks.len();
+    let mut ceilings = model.ceilings();
+    ceilings.push(ceiling);
+    let zone = model.initial_zone(1)?;
+
+    let mut nodes = vec![Node {
+        location: model.initial_location,
+        flag: observer(model.initial_location, initial).0,
+        zone,
+        parent: None,
+    }];
+    let mut passed = HashMap::<(usize, bool), Vec<Dbm>>::new();
+    let mut waiting = vec![0];
+    while let Some(id) = waiting.pop() {
+        let node = &nodes[id];
+        let zones = passed.entry((node.location, node.flag)).or_default();
+        if zones.iter().any(|zone| zone.includes(&node.zone)) {
+            continue;
+        }
+        zones.push(node.zone.clone());
+        if bad(node) {
+            let mut path = Vec::new();
+            let mut current = Some(id);
+            while let Some(id) = current {
+                path.push(nodes[id].location);
+                current = nodes[id].parent;
+            }
+            path.reverse();
+            return Some(path);
+        }
+
+        let (location, flag) = (node.location, node.flag);
+        for edge in model.edges_from(location) {
+            let (next, reset) = observer(edge.to, flag);
+            let resets = if reset { vec![z] } else { Vec::new() };
+            if let Some(zone) = model.successor(&nodes[id].zone, edge, &resets, &ceilings) {
+                nodes.push(Node {
+                    location: edge.to,
+                    flag: next,
+                    zone,
+                    parent: Some(id),
+                });
+                waiting.push(nodes.len() - 1);
+            }
+        }
+    }
+    None
+}
+
+/// The locations leading to a location satisfying the propositional
+/// `target`, at a time within `bound`, if it can be reached.
+pub fn reachable(
+    model: &TimedAutomaton,
+    target: &TCTLFormulae,
+    bound: Option<TimeBound>,
+) -> Option<Vec<usize>> {
+    // z is the time since the start
+    let z = model.clocks.len() + 1;
+    let ceiling = bound.map_or(0, |b| b.constant());
+    search(
+        model,
+        false,
+        ceiling,
+        |_, _| (false, false),
+        |node| {
+            holds(model, node.location, target) && bound.is_none_or(|b| within(&node.zone, z, b))
+        },
+    )
+}
+
+/// Check that whenever `trigger` holds, `response` holds within `bound`, an
+/// upper bound: `AG (trigger -> AF⋈c response)`.
+///
+/// On failure, returns the locations leading to a location where the
+/// deadline can pass without `response`. The runs which stop, unable to
+/// take an edge or to let time pass, are not considered.
+pub fn bounded_response(
+    model: &TimedAutomaton,
+    trigger: &TCTLFormulae,
+    response: &TCTLFormulae,
+    bound: TimeBound,
+) -> Result<(), Vec<usize>> {
+    // the flag is set from the first time trigger holds without response,
+    // and z is the time since then, as the later triggers have later
+    // deadlines
+    let z = model.clocks.len() + 1;
+    let observer = |location: usize, pending: bool| {
+        if holds(model, location, response) {
+            (false, false)
+        } else if !pending && holds(model, location, trigger) {
+            (true, true)
+        } else {
+            (pending, false)
+        }
+    };
+    let late = |node: &Node| node.flag && within(&node.zone, z, beyond(bound));
+    match search(model, false, bound.constant(), observer, late) {
+        Some(path) => Err(path),
+        None => Ok(()),
+    }
+}
+
+/// Check that `response` holds within `bound`, an upper bound, from the
+/// start: `AF⋈c response`.
+///
+/// On failure, returns the locations leading to a location where the
+/// deadline can pass without `response`.
+pub fn deadline(
+    model: &TimedAutomaton,
+    response: &TCTLFormulae,
+    bound: TimeBound,
+) -> Result<(), Vec<usize>> {
+    // the flag is set until response holds, and z is never reset
+    let z = model.clocks.len() + 1;
+    let observer =
+        |location: usize, pending: bool| (pending && !holds(model, location, response), false);
+    let late = |node: &Node| node.flag && within(&node.zone, z, beyond(bound));
+    match search(model, true, bound.constant(), observer, late) {
+        Some(path) => Err(path),
+        None => Ok(()),
+    }
+}
+
+/// Check whether `formula` holds in the initial location of `model`.
+///
+/// The supported formulae are the boolean combinations of:
+/// * propositional formulae over the labels of the locations,
+/// * `EF⋈c f` and `AG⋈c f`, with the bound optional,
+/// * `AF<=c f` and `AF<c f`,
+/// * `AG (f -> AF<=c g)` and `AG (f -> AF<c g)`,
+///
+/// where `f` and `g` are propositional.
+pub fn tctl_checking(model: &TimedAutomaton, formula: &TCTLFormulae) -> Result<bool, Unsupported> {
+    let unsupported = || Err(Unsupported(formula.clone()));
+    if formula.is_propositional() {
+        return Ok(holds(model, model.initial_location, formula));
+    }
+    match formula {
+        TCTLFormulae::Not(f) => Ok(!tctl_checking(model, f)?),
+        TCTLFormulae::And(f, g) => Ok(tctl_checking(model, f)? && tctl_checking(model, g)?),
+        TCTLFormulae::Or(f, g) => Ok(tctl_checking(model, f)? || tctl_checking(model, g)?),
+        TCTLFormulae::Exist(path) => match &**path {
+            TCTLFormulae::Finally(f, bound) if f.is_propositional() => {
+                Ok(reachable(model, f, *bound).is_some())
+            }
+            _ => unsupported(),
+        },
+        TCTLFormulae::All(path) => match &**path {
+            TCTLFormulae::Globally(f, bound) if f.is_propositional() => {
+                let violation = TCTLFormulae::Not(f.clone());
+                Ok(reachable(model, &violation, *bound).is_none())
+            }
+            TCTLFormulae::Finally(f, Some(bound)) if f.is_propositional() && bound.is_upper() => {
+                Ok(deadline(model, f, *bound).is_ok())
+            }
+            TCTLFormulae::Globally(f, None) => {
+                let (trigger, response) = match &**f {
+                    TCTLFormulae::Or(not, response) => match &**not {
+                        TCTLFormulae::Not(trigger) => (&**trigger, &**response),
+                        _ => return unsupported(),
+                    },
+                    response => (&TCTLFormulae::True, response),
+                };
+                match response {
+                    TCTLFormulae::All(response) => match &**response {
+                        TCTLFormulae::Finally(g, Some(bound))
+                            if trigger.is_propositional()
+                                && g.is_propositional()
+                                && bound.is_upper() =>
+                        {
+                            Ok(bounded_response(model, trigger, g, *bound).is_ok())
+                        }
+                        _ => unsupported(),
+                    },
+                    _ => unsupported(),
+                }
+            }
+            _ => unsupported(),
+        },
+        _ => unsupported(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::tctl_parser::parse_tctl;
+
+    /// A request is acknowledged between 1 and 3 time units after it is
+    /// made, and the acknowledgement is immediately followed by the idle
+    /// location.
+    const PROTOCOL: &str = r#"
+    {
+        "clocks": ["x"],
+        "locations": [
+            { "id": 0, "name": "idle", "labels": ["idle"], "edges": [
+                { "to": 1, "resets": ["x"] }
+            ] },
+            { "id": 1, "name": "wait", "labels": ["req"], "invariant": "x <= 3", "edges": [
+                { "to": 2, "guard": "x >= 1", "resets": ["x"] }
+            ] },
+            { "id": 2, "name": "done", "labels": ["ack"], "invariant": "x <= 0", "edges": [
+                { "to": 0 }
+            ] }
+        ],
+        "initial_location": 0
+    }"#;
+
+    fn check(model: &TimedAutomaton, formula: &str) -> bool {
+        tctl_checking(model, &parse_tctl(formula).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_tctl_checking() {
+        let protocol = TimedAutomaton::from_json(PROTOCOL).unwrap();
+        assert!(check(&protocol, "AG (req -> AF<=3 ack)"));
+        assert!(!check(&protocol, "AG (req -> AF<3 ack)"));
+        assert!(check(&protocol, "EF<=1 ack && !EF<1 ack"));
+        assert!(check(&protocol, "AG !(req && ack) && idle"));
+        assert!(check(&protocol, "EF>100 req"));
+        // idle can last forever
+        assert!(!check(&protocol, "AF<=5 ack"));
+        assert!(check(&protocol, "AG<1 !ack"));
+
+        let formula = parse_tctl("EG idle").unwrap();
+        assert!(tctl_checking(&protocol, &formula).is_err());
+        let formula = parse_tctl("AG (req -> AF ack)").unwrap();
+        assert!(tctl_checking(&protocol, &formula).is_err());
+    }
+
+    #[test]
+    fn test_counterexamples() {
+        // the request may be retried forever
+        let data = PROTOCOL.replace(
+            r#"{ "to": 2, "guard": "x >= 1", "resets": ["x"] }"#,
+            r#"{ "to": 2, "guard": "x >= 1", "resets": ["x"] },
+               { "to": 0, "guard": "x >= 2" }"#,
+        );
+        let retrying = TimedAutomaton::from_json(&data).unwrap();
+        let (req, ack) = (
+            TCTLFormulae::Atomic("req".to_string()),
+            TCTLFormulae::Atomic("ack".to_string()),
+        );
+        let protocol = TimedAutomaton::from_json(PROTOCOL).unwrap();
+        assert!(bounded_response(&protocol, &req, &ack, TimeBound::LessEq(3)).is_ok());
+        // after a retry, idle may last forever
+        let path = bounded_response(&retrying, &req, &ack, TimeBound::LessEq(10)).unwrap_err();
+        assert_eq!(path, vec![0, 1, 0]);
+
+        let path = reachable(&retrying, &ack, Some(TimeBound::GreaterEq(7))).unwrap();
+        assert_eq!(path.last(), Some(&2));
+        let formula = TCTLFormulae::And(Box::new(req), Box::new(ack));
+        assert!(reachable(&retrying, &formula, None).is_none());
+    }
+}
@@ -0,0 +1,75 @@
+use crate::{
+    algorithm::{
+        ctl_checking,
+        ltlf::holds_finite,
+        on_the_fly::{check_ltl, LtlError},
+    },
+    ctl::CTLFormulae,
+    ltl::LTLFormulae,
+    model::trace::Trace,
+};
+
+/// Check whether `trace` satisfies `formula`, with the semantics of LTLf
+/// for a finite trace.
+///
+/// Fails when a past operator of `formula` has a future operator under it,
+/// which is only supported for a finite trace.
+pub fn check_trace_ltl(trace: &Trace, formula: &LTLFormulae) -> Result<bool, String> {
+    if trace.is_finite() {
+        return Ok(holds_finite(&trace.prefix, formula));
+    }
+    match check_ltl(&trace.to_kripke(), formula) {
+        Ok(()) => Ok(true),
+        Err(LtlError::Counterexample(_)) => Ok(false),
+        Err(LtlError::Unsupported(reason)) => Err(reason),
+    }
+}
+
+/// Check whether the first position of `trace` satisfies `formula`, in the
+/// linear Kripke structure of the trace, where the last event of a finite
+/// trace repeats forever. The atomic propositions which are not labels of
+/// the trace never hold.
+///
+/// Fails when `formula` is not a state formula.
+pub fn check_trace_ctl(trace: &Trace, formula: CTLFormulae) -> Result<bool, String> {
+    let mut model = trace.to_kripke();
+    for atomic in formula.atomics() {
+        model.get_label_id_or_add(&atomic);
+    }
+    ctl_checking(&mut model, formula)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::{ctl_parser::parse_ctl, ltl_parser::parse_ltl};
+
+    #[test]
+    fn test_trace_checking() {
+        // a request, granted after a wait, then idle and busy forever
+        let lasso = Trace::from_json(
+            r#"{ "prefix": [["req"], ["wait"], ["grant"]], "cycle": [["idle"], ["busy"]] }"#,
+        )
+        .unwrap();
+        let ltl = |formula: &str| check_trace_ltl(&lasso, &parse_ltl(formula).unwrap()).unwrap();
+        assert!(ltl("G (req -> F grant)"));
+        assert!(ltl("G F idle && G F busy"));
+        assert!(ltl("G (grant -> O req)"));
+        assert!(!ltl("F G idle"));
+        assert!(!ltl("X grant"));
+        assert!(check_trace_ltl(&lasso, &parse_ltl("F Y F grant").unwrap()).is_err());
+
+        let ctl = |formula: &str| check_trace_ctl(&lasso, parse_ctl(formula).unwrap()).unwrap();
+        assert!(ctl("AG (req -> AF grant)"));
+        assert!(ctl("EX EX grant && AG AF busy"));
+        assert!(!ctl("EF crash"));
+        assert!(!ctl("AX grant"));
+
+        // the semantics of LTLf, while CTL sees the last event repeat
+        let finite = Trace::from_json(r#"{ "prefix": [["req"], ["grant"]] }"#).unwrap();
+        assert!(finite.is_finite());
+        assert!(check_trace_ltl(&finite, &parse_ltl("F (grant && !X true)").unwrap()).unwrap());
+        assert!(!check_trace_ltl(&finite, &parse_ltl("G X true").unwrap()).unwrap());
+        assert!(check_trace_ctl(&finite, parse_ctl("AX AG grant").unwrap()).unwrap());
+    }
+}
@@ -0,0 +1,315 @@
+use crate::{
+    algorithm::{
+        mu_calculus::{game_checking, Evidence},
+        on_the_fly::{check_ltl, Lasso, LtlError},
+    },
+    ctl::CTLFormulae,
+    ltl::LTLFormulae,
+    model::{kripke::Kripke, transition_system::TransitionSystem},
+    mu_calculus::MuFormulae,
+};
+
+/// An occurrence of a subformula in a property which holds, with the
+/// property strengthened by replacing the occurrence with false, or with
+/// true under an odd number of negations.
+#[derive(Debug)]
+pub struct Occurrence<F, W> {
+    pub subformula: F,
+    pub mutant: F,
+    /// An interesting witness: the property holds on it, but the mutant
+    /// does not. None when the mutant holds as well, the occurrence not
+    /// mattering to the property, which holds vacuously.
+    pub witness: Option<W>,
+}
+
+impl<F, W> Occurrence<F, W> {
+    pub fn is_vacuous(&self) -> bool {
+        self.witness.is_none()
+    }
+}
+
+/// The occurrences of an LTL formula, with a path of `T` as witness.
+type LtlOccurrences<T> = Vec<Occurrence<LTLFormulae, Lasso<<T as TransitionSystem>::State>>>;
+
+fn ltl_constant(value: bool) -> LTLFormulae {
+    if value {
+        LTLFormulae::True
+    } else {
+        LTLFormulae::Not(Box::new(LTLFormulae::True))
+    }
+}
+
+/// The occurrences of subformulae in `f`, each with `f` where it is
+/// replaced. The constants are left out, the negations too, as replacing
+/// their operands is the same, and `f` itself when it is the `root` of the
+/// property.
+fn ltl_occurrences(f: &LTLFormulae, positive: bool, root: bool) -> Vec<(LTLFormulae, LTLFormulae)> {
+    let mut result = Vec::new();
+    if !root && !matches!(f, LTLFormulae::True | LTLFormulae::Not(_)) {
+        result.push((f.clone(), ltl_constant(!positive)));
+    }
+    let unary = |g: &LTLFormulae, wrap: &dyn Fn(Box<LTLFormulae>) -> LTLFormulae| {
+        ltl_occurrences(g, positive, false)
+            .into_iter()
+            .map(|(s, m)| (s, wrap(Box::new(m))))
+            .collect::<Vec<_>>()
+    };
+    let binary =
+        |g: &LTLFormulae,
+         h: &LTLFormulae,
+         wrap: &dyn Fn(Box<LTLFormulae>, Box<LTLFormulae>) -> LTLFormulae| {
+            let left = ltl_occurrences(g, positive, false).into_iter();
+            let right = ltl_occurrences(h, positive, false).into_iter();
+            left.map(|(s, m)| (s, wrap(Box::new(m), Box::new(h.clone()))))
+                .chain(right.map(|(s, m)| (s, wrap(Box::new(g.clone()), Box::new(m)))))
+                .collect::<Vec<_>>()
+        };
+    result.extend(match f {
+        LTLFormulae::True | LTLFormulae::Atomic(_) => Vec::new(),
+        LTLFormulae::Not(g) => ltl_occurrences(g, !positive, root)
+            .into_iter()
+            .map(|(s, m)| (s, LTLFormulae::Not(Box::new(m))))
+            .collect(),
+        LTLFormulae::Next(g) => unary(g, &LTLFormulae::Next),
+        LTLFormulae::Yesterday(g) => unary(g, &LTLFormulae::Yesterday),
+        LTLFormulae::And(g, h) => binary(g, h, &LTLFormulae::And),
+        LTLFormulae::Until(g, h) => binary(g, h, &LTLFormulae::Until),
+        LTLFormulae::Since(g, h) => binary(g, h, &LTLFormulae::Since),
+        LTLFormulae::BoundedUntil(a, b, g, h) => {
+            binary(g, h, &|g, h| LTLFormulae::BoundedUntil(*a, *b, g, h))
+        }
+    });
+    result
+}
+
+/// The vacuity analysis of `formula` on `system`: every occurrence of a
+/// subformula, with a path of the system violating the property once the
+/// occurrence is replaced, if there is one.
+///
+/// Returns `None` when `formula` does not hold, and fails when it cannot be
+/// checked.
+pub fn ltl_vacuity<T: TransitionSystem>(
+    system: &T,
+    formula: &LTLFormulae,
+) -> Result<Option<LtlOccurrences<T>>, String> {
+    match check_ltl(system, formula) {
+        Ok(()) => {}
+        Err(LtlError::Unsupported(reason)) => return Err(reason),
+        Err(LtlError::Counterexample(_)) => return Ok(None),
+    }
+    // replacing an occurrence under a past operator keeps it propositional,
+    // so the mutants can be checked as well
+    let occurrences = ltl_occurrences(formula, true, true)
+        .into_iter()
+        .map(|(subformula, mutant)| Occurrence {
+            witness: check_ltl(system, &mutant)
+                .err()
+                .and_then(LtlError::counterexample),
+            subformula,
+            mutant,
+        })
+        .collect();
+    Ok(Some(occurrences))
+}
+
+fn ctl_constant(value: bool) -> CTLFormulae {
+    if value {
+        CTLFormulae::True
+    } else {
+        CTLFormulae::Not(Box::new(CTLFormulae::True))
+    }
+}
+
+/// The same as [`ltl_occurrences`] in a CTL formula, where the path
+/// formulae under the quantifiers are not occurrences either.
+fn ctl_occurrences(f: &CTLFormulae, positive: bool, root: bool) -> Vec<(CTLFormulae, CTLFormulae)> {
+    let mut result = Vec::new();
+    let state = !matches!(
+        f,
+        CTLFormulae::True
+            | CTLFormulae::Not(_)
+            | CTLFormulae::Next(_)
+            | CTLFormulae::Finally(_)
+            | CTLFormulae::Globally(_)
+            | CTLFormulae::Until(_, _)
+            | CTLFormulae::Release(_, _)
+            | CTLFormulae::BoundedFinally(_, _, _)
+            | CTLFormulae::BoundedGlobally(_, _, _)
+            | CTLFormulae::BoundedUntil(_, _, _, _)
+    );
+    if !root && state {
+        result.push((f.clone(), ctl_constant(!positive)));
+    }
+    let unary = |g: &CTLFormulae, wrap: &dyn Fn(Box<CTLFormulae>) -> CTLFormulae| {
+        ctl_occurrences(g, positive, false)
+            .into_iter()
+            .map(|(s, m)| (s, wrap(Box::new(m))))
+            .collect::<Vec<_>>()
+    };
+    let binary =
+        |g: &CTLFormulae,
+         h: &CTLFormulae,
+         wrap: &dyn Fn(Box<CTLFormulae>, Box<CTLFormulae>) -> CTLFormulae| {
+            let left = ctl_occurrences(g, positive, false).into_iter();
+            let right = ctl_occurrences(h, positive, false).into_iter();
+            left.map(|(s, m)| (s, wrap(Box::new(m), Box::new(h.clone()))))
+                .chain(right.map(|(s, m)| (s, wrap(Box::new(g.clone()), Box::new(m)))))
+                .collect::<Vec<_>>()
+        };
+    result.extend(match f {
+        CTLFormulae::True | CTLFormulae::Atomic(_) => Vec::new(),
+        CTLFormulae::Not(g) => ctl_occurrences(g, !positive, root)
+            .into_iter()
+            .map(|(s, m)| (s, CTLFormulae::Not(Box::new(m))))
+            .collect(),
+        CTLFormulae::All(g) => unary(g, &CTLFormulae::All),
+        CTLFormulae::Exist(g) => unary(g, &CTLFormulae::Exist),
+        CTLFormulae::Next(g) => unary(g, &CTLFormulae::Next),
+        CTLFormulae::Finally(g) => unary(g, &CTLFormulae::Finally),
+        CTLFormulae::Globally(g) => unary(g, &CTLFormulae::Globally),
+        CTLFormulae::BoundedFinally(a, b, g) => {
+            unary(g, &|g| CTLFormulae::BoundedFinally(*a, *b, g))
+        }
+        CTLFormulae::BoundedGlobally(a, b, g) => {
+            unary(g, &|g| CTLFormulae::BoundedGlobally(*a, *b, g))
+        }
+        CTLFormulae::And(g, h) => binary(g, h, &CTLFormulae::And),
+        CTLFormulae::Or(g, h) => binary(g, h, &CTLFormulae::Or),
+        CTLFormulae::Until(g, h) => binary(g, h, &CTLFormulae::Until),
+        CTLFormulae::Release(g, h) => binary(g, h, &CTLFormulae::Release),
+        CTLFormulae::BoundedUntil(a, b, g, h) => {
+            binary(g, h, &|g, h| CTLFormulae::BoundedUntil(*a, *b, g, h))
+        }
+    });
+    result
+}
+
+/// The vacuity analysis of `formula` in the initial state of `model`: every
+/// occurrence of a subformula, with the strategy refuting the property
+/// once the occurrence is replaced, if there is one.
+///
+/// The formulae are checked by solving their model checking games, see
+/// [`MuFormulae::from_ctl`] for the states without successors. Returns
+/// `None` when `formula` does not hold, and fails when it is not a state
+/// formula.
+pub fn ctl_vacuity(
+    model: &Kripke,
+    formula: &CTLFormulae,
+) -> Result<Option<Vec<Occurrence<CTLFormulae, Evidence>>>, String> {
+    let check = |f: &CTLFormulae| {
+        let mu = MuFormulae::from_ctl(f)?;
+        game_checking(model, &mu).map_err(|e| e.to_string())
+    };
+    if !check(formula)?.holds {
+        return Ok(None);
+    }
+    let occurrences = ctl_occurrences(formula, true, true)
+        .into_iter()
+        .map(|(subformula, mutant)| {
+            let evidence = check(&mutant)?;
+            Ok(Occurrence {
+                witness: (!evidence.holds).then_some(evidence),
+                subformula,
+                mutant,
+            })
+        })
+        .collect::<Result<_, String>>()?;
+    Ok(Some(occurrences))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::{ctl_parser::parse_ctl, ltl_parser::parse_ltl};
+
+    /// Requests, when `requests` is true, which are acknowledged, and an idle
+    /// loop after an acknowledgement.
+    fn model(requests: bool) -> Kripke {
+        let data = format!(
+            r#"
+        {{
+            "states": [
+                {{ "id": 0, "name": "s0", "labels": [{}], "transit_to": [1] }},
+                {{ "id": 1, "name": "s1", "labels": ["ack"], "transit_to": [0, 2] }},
+                {{ "id": 2, "name": "s2", "labels": [], "transit_to": [2] }}
+            ],
+            "initial_state": 0
+        }}"#,
+            if requests { r#""req""# } else { "" }
+        );
+        Kripke::from_json(&data).unwrap()
+    }
+
+    /// The occurrences which did not matter.
+    fn vacuous<F: ToString, W>(occurrences: &[Occurrence<F, W>]) -> Vec<String> {
+        occurrences
+            .iter()
+            .filter(|o| o.is_vacuous())
+            .map(|o| o.subformula.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_vacuity() {
+        let ctl = parse_ctl("AG (req -> AF ack)").unwrap();
+        let occurrences = ctl_vacuity(&model(false), &ctl).unwrap().unwrap();
+        // req matters, as the idle loop never acknowledges
+        assert_eq!(vacuous(&occurrences), vec!["AFack", "ack"]);
+        assert_eq!(occurrences.len(), 4);
+        let occurrences = ctl_vacuity(&model(true), &ctl).unwrap().unwrap();
+        assert!(vacuous(&occurrences).is_empty());
+        let witness = occurrences[1].witness.as_ref().unwrap();
+        assert!(!witness.holds);
+        assert!(ctl_vacuity(&model(true), &parse_ctl("AG !req").unwrap())
+            .unwrap()
+            .is_none());
+        assert!(ctl_vacuity(&model(true), &parse_ctl("G req").unwrap()).is_err());
+
+        let ltl = parse_ltl("G (req -> F ack)").unwrap();
+        let occurrences = ltl_vacuity(&model(false), &ltl).unwrap().unwrap();
+        assert_eq!(vacuous(&occurrences), vec!["(true U ack)", "ack"]);
+        let occurrences = ltl_vacuity(&model(true), &ltl).unwrap().unwrap();
+        assert!(vacuous(&occurrences).is_empty());
+        // replacing req with true, the idle loop violates it
+        let witness = occurrences
+            .iter()
+            .find(|o| o.subformula.to_string() == "req")
+            .and_then(|o| o.witness.as_ref())
+            .unwrap();
+        assert_eq!(witness.cycle, vec![2]);
+    }
+
+    #[test]
+    fn test_vacuity_shapes() {
+        // req never holds, and under a negation it is replaced with true
+        let ltl = parse_ltl("G !(req && X ack)").unwrap();
+        let occurrences = ltl_vacuity(&model(false), &ltl).unwrap().unwrap();
+        assert_eq!(vacuous(&occurrences), vec!["Xack", "ack"]);
+        assert_eq!(
+            occurrences[1].mutant.to_string(),
+            "¬(true U ¬¬(true ∧ Xack))"
+        );
+        assert!(!occurrences[1].is_vacuous());
+        let ctl = parse_ctl("AG !(req && AX ack)").unwrap();
+        let occurrences = ctl_vacuity(&model(false), &ctl).unwrap().unwrap();
+        assert_eq!(vacuous(&occurrences), vec!["AXack", "ack"]);
+        assert_eq!(occurrences[1].mutant.to_string(), "AG¬(true ∧ AXack)");
+        assert!(!occurrences[1].is_vacuous());
+
+        // the operand of a bounded operator is an occurrence
+        let ltl = parse_ltl("G (req -> F[1,2] ack)").unwrap();
+        let occurrences = ltl_vacuity(&model(false), &ltl).unwrap().unwrap();
+        assert_eq!(vacuous(&occurrences), vec!["(true U[1,2] ack)", "ack"]);
+        let occurrences = ltl_vacuity(&model(true), &ltl).unwrap().unwrap();
+        assert!(vacuous(&occurrences).is_empty());
+        assert_eq!(
+            occurrences[3].mutant.to_string(),
+            "¬(true U ¬¬(req ∧ ¬(true U[1,2] ¬true)))"
+        );
+        let ctl = parse_ctl("AG (req -> AF[1,2] ack)").unwrap();
+        let occurrences = ctl_vacuity(&model(false), &ctl).unwrap().unwrap();
+        assert_eq!(vacuous(&occurrences), vec!["AF[1,2]ack", "ack"]);
+        let occurrences = ctl_vacuity(&model(true), &ctl).unwrap().unwrap();
+        assert!(vacuous(&occurrences).is_empty());
+    }
+}
@@ -0,0 +1,80 @@
+use std::collections::BTreeSet;
+use std::fmt::Display;
+
+/// Alternating-time temporal logic formulae, whose strategic operators
+/// quantify over the strategies of a coalition of agents.
+#[derive(Debug, Clone)]
+pub enum ATLFormulae {
+    True,
+    Atomic(String),
+
+    Not(Box<ATLFormulae>),
+    And(Box<ATLFormulae>, Box<ATLFormulae>),
+    Or(Box<ATLFormulae>, Box<ATLFormulae>),
+
+    /// `<<A>> X f`, the coalition can force `f` in the next state.
+    Next(Vec<String>, Box<ATLFormulae>),
+    /// `<<A>> G f`, the coalition can keep `f` forever.
+    Globally(Vec<String>, Box<ATLFormulae>),
+    /// `<<A>> (f U g)`, the coalition can force `g`, keeping `f` until then.
+    Until(Vec<String>, Box<ATLFormulae>, Box<ATLFormulae>),
+}
+
+impl Display for ATLFormulae {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ATLFormulae::True => write!(formatter, "true"),
+            ATLFormulae::Atomic(s) => write!(formatter, "{}", s),
+            ATLFormulae::Not(f) => write!(formatter, "¬{}", f),
+            ATLFormulae::And(f, g) => write!(formatter, "({} ∧ {})", f, g),
+            ATLFormulae::Or(f, g) => write!(formatter, "({} ∨ {})", f, g),
+            ATLFormulae::Next(a, f) => write!(formatter, "⟨⟨{}⟩⟩X{}", a.join(","), f),
+            ATLFormulae::Globally(a, f) => write!(formatter, "⟨⟨{}⟩⟩G{}", a.join(","), f),
+            ATLFormulae::Until(a, f, g) => {
+                write!(formatter, "⟨⟨{}⟩⟩({} U {})", a.join(","), f, g)
+            }
+        }
+    }
+}
+
+impl ATLFormulae {
+    /// The name of the label of the formula, as in CTL.
+    pub fn get_str(&self) -> String {
+        match self {
+            ATLFormulae::True => "true".to_string(),
+            ATLFormulae::Atomic(s) => s.clone(),
+            ATLFormulae::Not(f) => format!("!{}", f.get_str()),
+            ATLFormulae::And(f, g) => format!("({}&&{})", f.get_str(), g.get_str()),
+            ATLFormulae::Or(f, g) => format!("({}||{})", f.get_str(), g.get_str()),
+            ATLFormulae::Next(a, f) => format!("<<{}>>X{}", a.join(","), f.get_str()),
+            ATLFormulae::Globally(a, f) => format!("<<{}>>G{}", a.join(","), f.get_str()),
+            ATLFormulae::Until(a, f, g) => {
+                format!("<<{}>>({}U{})", a.join(","), f.get_str(), g.get_str())
+            }
+        }
+    }
+
+    /// The agents of the coalitions in the formula.
+    pub fn agents(&self) -> BTreeSet<String> {
+        match self {
+            ATLFormulae::True | ATLFormulae::Atomic(_) => BTreeSet::new(),
+            ATLFormulae::Not(f) => f.agents(),
+            ATLFormulae::And(f, g) | ATLFormulae::Or(f, g) => {
+                let mut agents = f.agents();
+                agents.extend(g.agents());
+                agents
+            }
+            ATLFormulae::Next(a, f) | ATLFormulae::Globally(a, f) => {
+                let mut agents = f.agents();
+                agents.extend(a.iter().cloned());
+                agents
+            }
+            ATLFormulae::Until(a, f, g) => {
+                let mut agents = f.agents();
+                agents.extend(g.agents());
+                agents.extend(a.iter().cloned());
+                agents
+            }
+        }
+    }
+}
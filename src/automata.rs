@@ -0,0 +1,3 @@
+pub mod buchi;
+pub mod dfa;
+pub mod monitor;
@@ -0,0 +1,464 @@
+use std::collections::{BTreeSet, HashSet};
+
+use crate::{ltl::LTLFormulae, model::transition_system::TransitionSystem};
+
+/// LTL formulae in negation normal form, the input of the tableau.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+enum Nnf {
+    True,
+    False,
+    Atomic(String),
+    NotAtomic(String),
+    And(Box<Nnf>, Box<Nnf>),
+    Or(Box<Nnf>, Box<Nnf>),
+    Next(Box<Nnf>),
+    Until(Box<Nnf>, Box<Nnf>),
+    Release(Box<Nnf>, Box<Nnf>),
+}
+
+/// Push the negations of `f` down to the atomic propositions.
+fn to_nnf(f: &LTLFormulae, negated: bool) -> Nnf {
+    match f {
+        LTLFormulae::True if negated => Nnf::False,
+        LTLFormulae::True => Nnf::True,
+        LTLFormulae::Atomic(a) if negated => Nnf::NotAtomic(a.clone()),
+        LTLFormulae::Atomic(a) => Nnf::Atomic(a.clone()),
+        LTLFormulae::Not(f) => to_nnf(f, !negated),
+        LTLFormulae::And(f, g) if negated => {
+            Nnf::Or(Box::new(to_nnf(f, true)), Box::new(to_nnf(g, true)))
+        }
+        LTLFormulae::And(f, g) => Nnf::And(Box::new(to_nnf(f, false)), Box::new(to_nnf(g, false))),
+        LTLFormulae::Next(f) => Nnf::Next(Box::new(to_nnf(f, negated))),
+        LTLFormulae::Until(f, g) if negated => {
+            Nnf::Release(Box::new(to_nnf(f, true)), Box::new(to_nnf(g, true)))
+        }
+        LTLFormulae::Until(f, g) => {
+            Nnf::Until(Box::new(to_nnf(f, false)), Box::new(to_nnf(g, false)))
+        }
+        LTLFormulae::BoundedUntil(_, _, _, _) => to_nnf(&f.unroll_bounded(), negated),
+        LTLFormulae::Yesterday(_) | LTLFormulae::Since(_, _) => {
+            panic!("Past operator in {}, eliminate it first", f)
+        }
+    }
+}
+
+/// The propositions a state of the automaton requires to hold and not to hold.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BuchiState {
+    pub positive: Vec<String>,
+    pub negative: Vec<String>,
+}
+
+/// Generalized Büchi automaton with labels on states.
+///
+/// A run `q0 q1 ...` reads a word `σ0 σ1 ...` if every `σi` satisfies the
+/// label of `qi`, and it is accepting if it visits every acceptance set
+/// infinitely often.
+#[derive(Debug, Clone)]
+pub struct Buchi {
+    pub states: Vec<BuchiState>,
+    pub initial: Vec<usize>,
+    pub transitions: Vec<Vec<usize>>,
+    /// The acceptance sets, there is always at least one.
+    pub acceptance: Vec<HashSet<usize>>,
+}
+
+/// A node of the tableau of Gerth, Peled, Vardi and Wolper.
+#[derive(Debug, Clone)]
+struct Node {
+    incoming: HashSet<usize>,
+    new: BTreeSet<Nnf>,
+    old: BTreeSet<Nnf>,
+    next: BTreeSet<Nnf>,
+}
+
+/// The pseudo node every initial node comes from.
+const INIT: usize = usize::MAX;
+
+impl Buchi {
+    /// Translate `formula` into an automaton accepting exactly its models.
+    /// Past operators must have been eliminated, see
+    /// [`LTLFormulae::eliminate_past`].
+    pub fn from_ltl(formula: &LTLFormulae) -> Buchi {
+        let nnf = to_nnf(formula, false);
+        let mut nodes = Vec::new();
+        expand(
+            Node {
+                incoming: HashSet::from([INIT]),
+                new: BTreeSet::from([nnf.clone()]),
+                old: BTreeSet::new(),
+                next: BTreeSet::new(),
+            },
+            &mut nodes,
+        );
+
+        let states = nodes
+            .iter()
+            .map(|node| {
+                let mut state = BuchiState::default();
+                for f in &node.old {
+                    match f {
+                        Nnf::Atomic(a) => state.positive.push(a.clone()),
+                        Nnf::NotAtomic(a) => state.negative.push(a.clone()),
+                        _ => {}
+                    }
+                }
+                state
+            })
+            .collect();
+
+        let mut transitions = vec![Vec::new(); nodes.len()];
+        let mut initial = Vec::new();
+        for (to, node) in nodes.iter().enumerate() {
+            for from in &node.incoming {
+                if *from == INIT {
+                    initial.push(to);
+                } else {
+                    transitions[*from].push(to);
+                }
+            }
+        }
+        initial.sort();
+        for next in &mut transitions {
+            next.sort();
+        }
+
+        let mut untils = BTreeSet::new();
+        collect_untils(&nnf, &mut untils);
+        let mut acceptance = untils
+            .iter()
+            .map(|until| {
+                let Nnf::Until(_, g) = until else {
+                    unreachable!()
+                };
+                (0..nodes.len())
+                    .filter(|q| !nodes[*q].old.contains(until) || nodes[*q].old.contains(g))
+                    .collect::<HashSet<usize>>()
+            })
+            .collect::<Vec<HashSet<usize>>>();
+        if acceptance.is_empty() {
+            acceptance.push((0..nodes.len()).collect());
+        }
+
+        Buchi {
+            states,
+            initial,
+            transitions,
+            acceptance,
+        }
+    }
+
+    /// Convert to an equivalent automaton with a single acceptance set.
+    ///
+    /// State `(q, i)` waits for acceptance set `i`, and is numbered
+    /// `q * k + i` where `k` is the number of acceptance sets.
+    pub fn degeneralize(&self) -> Buchi {
+        let k = self.acceptance.len();
+        if k == 1 {
+            return self.clone();
+        }
+
+        let n = self.states.len();
+        let mut states = Vec::with_capacity(n * k);
+        let mut transitions = Vec::with_capacity(n * k);
+        for q in 0..n {
+            for i in 0..k {
+                states.push(self.states[q].clone());
+                let j = if self.acceptance[i].contains(&q) {
+                    (i + 1) % k
+                } else {
+                    i
+                };
+                transitions.push(self.transitions[q].iter().map(|r| r * k + j).collect());
+            }
+        }
+
+        Buchi {
+            states,
+            initial: self.initial.iter().map(|q| q * k).collect(),
+            transitions,
+            acceptance: vec![self.acceptance[0].iter().map(|q| q * k).collect()],
+        }
+    }
+
+    /// Whether state `q` of the automaton can read a letter with the labels of
+    /// `state` in `system`.
+    pub fn matches<T: TransitionSystem>(&self, q: usize, system: &T, state: &T::State) -> bool {
+        let label = &self.states[q];
+        label.positive.iter().all(|p| system.has_label(state, p))
+            && label.negative.iter().all(|p| !system.has_label(state, p))
+    }
+}
+
+fn expand(mut node: Node, nodes: &mut Vec<Node>) {
+    let Some(f) = node.new.pop_first() else {
+        if let Some(existing) = nodes
+            .iter_mut()
+            .find(|n| n.old == node.old && n.next == node.next)
+        {
+            existing.incoming.extend(node.incoming);
+        } else {
+            let id = nodes.len();
+            let next = node.next.clone();
+            nodes.push(node);
+            expand(
+                Node {
+                    incoming: HashSet::from([id]),
+                    new: next,
+                    old: BTreeSet::new(),
+                    next: BTreeSet::new(),
+                },
+                nodes,
+            );
+        }
+        return;
+    };
+
+    if node.old.contains(&f) {
+        expand(node, nodes);
+        return;
+    }
+
+    match &f {
+        Nnf::False => {}
+        Nnf::True => {
+            node.old.insert(f);
+            expand(node, nodes);
+        }
+        Nnf::Atomic(a) => {
+            if !node.old.contains(&Nnf::NotAtomic(a.clone())) {
+                node.old.insert(f);
+                expand(node, nodes);
+            }
+        }
+        Nnf::NotAtomic(a) => {
+            if !node.old.contains(&Nnf::Atomic(a.clone())) {
+                node.old.insert(f);
+                expand(node, nodes);
+            }
+        }
+        Nnf::And(g, h) => {
+            node.new.insert(*g.clone());
+            node.new.insert(*h.clone());
+            node.old.insert(f);
+            expand(node, nodes);
+        }
+        Nnf::Next(g) => {
+            node.next.insert(*g.clone());
+            node.old.insert(f);
+            expand(node, nodes);
+        }
+        Nnf::Or(g, h) => {
+            let mut other = node.clone();
+            node.new.insert(*g.clone());
+            other.new.insert(*h.clone());
+            node.old.insert(f.clone());
+            other.old.insert(f);
+            expand(node, nodes);
+            expand(other, nodes);
+        }
+        Nnf::Until(g, h) => {
+            let mut other = node.clone();
+            node.new.insert(*g.clone());
+            node.next.insert(f.clone());
+            other.new.insert(*h.clone());
+            node.old.insert(f.clone());
+            other.old.insert(f);
+            expand(node, nodes);
+            expand(other, nodes);
+        }
+        Nnf::Release(g, h) => {
+            let mut other = node.clone();
+            node.new.insert(*h.clone());
+            node.next.insert(f.clone());
+            other.new.insert(*g.clone());
+            other.new.insert(*h.clone());
+            node.old.insert(f.clone());
+            other.old.insert(f);
+            expand(node, nodes);
+            expand(other, nodes);
+        }
+    }
+}
+
+fn collect_untils(f: &Nnf, untils: &mut BTreeSet<Nnf>) {
+    match f {
+        Nnf::True | Nnf::False | Nnf::Atomic(_) | Nnf::NotAtomic(_) => {}
+        Nnf::Next(g) => collect_untils(g, untils),
+        Nnf::And(g, h) | Nnf::Or(g, h) | Nnf::Release(g, h) => {
+            collect_untils(g, untils);
+            collect_untils(h, untils);
+        }
+        Nnf::Until(g, h) => {
+            untils.insert(f.clone());
+            collect_untils(g, untils);
+            collect_untils(h, untils);
+        }
+    }
+}
+
+/// The synchronous product of a transition system with a Büchi automaton.
+///
+/// States are pairs of a system state and an automaton state, where the
+/// automaton state matches the labels of the system state.
+#[derive(Debug, Clone)]
+pub struct BuchiProduct<'a, T> {
+    pub system: &'a T,
+    pub automaton: &'a Buchi,
+}
+
+impl<'a, T: TransitionSystem> BuchiProduct<'a, T> {
+    pub fn new(system: &'a T, automaton: &'a Buchi) -> BuchiProduct<'a, T> {
+        BuchiProduct { system, automaton }
+    }
+
+    /// Whether the automaton component of `state` is in acceptance set `set`.
+    pub fn is_accepting(&self, state: &(T::State, usize), set: usize) -> bool {
+        self.automaton.acceptance[set].contains(&state.1)
+    }
+}
+
+impl<T: TransitionSystem> TransitionSystem for BuchiProduct<'_, T> {
+    type State = (T::State, usize);
+
+    fn initial_states(&self) -> Vec<Self::State> {
+        let mut initial = Vec::new();
+        for s in self.system.initial_states() {
+            for q in &self.automaton.initial {
+                if self.automaton.matches(*q, self.system, &s) {
+                    initial.push((s.clone(), *q));
+                }
+            }
+        }
+        initial
+    }
+
+    fn successors(&self, (s, q): &Self::State) -> Vec<Self::State> {
+        let mut next = Vec::new();
+        for t in self.system.successors(s) {
+            for r in &self.automaton.transitions[*q] {
+                if self.automaton.matches(*r, self.system, &t) {
+                    next.push((t.clone(), *r));
+                }
+            }
+        }
+        next
+    }
+
+    fn labels(&self, (s, _): &Self::State) -> HashSet<String> {
+        self.system.labels(s)
+    }
+
+    fn has_label(&self, (s, _): &Self::State, label: &str) -> bool {
+        self.system.has_label(s, label)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::ltl_parser::parse_ltl;
+
+    /// Whether the automaton accepts the word `prefix · cycle^ω`, where each
+    /// letter is the set of propositions holding.
+    fn accepts(automaton: &Buchi, prefix: &[&[&str]], cycle: &[&[&str]]) -> bool {
+        let automaton = automaton.degeneralize();
+        let letter = |i: usize| -> &[&str] {
+            if i < prefix.len() {
+                prefix[i]
+            } else {
+                cycle[(i - prefix.len()) % cycle.len()]
+            }
+        };
+        let matches = |q: usize, i: usize| {
+            let label = &automaton.states[q];
+            label
+                .positive
+                .iter()
+                .all(|p| letter(i).contains(&p.as_str()))
+                && label
+                    .negative
+                    .iter()
+                    .all(|p| !letter(i).contains(&p.as_str()))
+        };
+
+        // positions beyond the prefix are identified modulo the cycle length
+        let position = |i: usize| {
+            if i < prefix.len() {
+                i
+            } else {
+                prefix.len() + (i - prefix.len()) % cycle.len()
+            }
+        };
+        let n = prefix.len() + cycle.len();
+        let mut edges = vec![Vec::new(); automaton.states.len() * n];
+        for q in 0..automaton.states.len() {
+            for i in 0..n {
+                if !matches(q, i) {
+                    continue;
+                }
+                for r in &automaton.transitions[q] {
+                    let j = position(i + 1);
+                    if matches(*r, j) {
+                        edges[q * n + i].push(r * n + j);
+                    }
+                }
+            }
+        }
+        let reach = |from: usize| {
+            let mut seen = HashSet::new();
+            let mut stack = edges[from].clone();
+            while let Some(v) = stack.pop() {
+                if seen.insert(v) {
+                    stack.extend(edges[v].iter().cloned());
+                }
+            }
+            seen
+        };
+        let initial = automaton
+            .initial
+            .iter()
+            .filter(|q| matches(**q, 0))
+            .map(|q| q * n)
+            .collect::<Vec<usize>>();
+        initial.iter().any(|init| {
+            let mut reachable = reach(*init);
+            reachable.insert(*init);
+            reachable
+                .iter()
+                .any(|v| automaton.acceptance[0].contains(&(v / n)) && reach(*v).contains(v))
+        })
+    }
+
+    #[test]
+    fn test_buchi_until() {
+        let a = Buchi::from_ltl(&parse_ltl("a U b").unwrap());
+        assert!(accepts(&a, &[&["a"], &["a"], &["b"]], &[&[]]));
+        assert!(!accepts(&a, &[&["a"], &[]], &[&["b"]]));
+        assert!(!accepts(&a, &[], &[&["a"]]));
+    }
+
+    #[test]
+    fn test_buchi_fairness() {
+        let a = Buchi::from_ltl(&parse_ltl("G F a && G F b").unwrap());
+        assert_eq!(a.acceptance.len(), 2);
+        assert!(accepts(&a, &[], &[&["a"], &["b"]]));
+        assert!(!accepts(&a, &[&["b"]], &[&["a"]]));
+
+        let a = Buchi::from_ltl(&parse_ltl("F G a").unwrap());
+        assert!(accepts(&a, &[&[], &["b"]], &[&["a"]]));
+        assert!(!accepts(&a, &[], &[&["a"], &[]]));
+    }
+
+    #[test]
+    fn test_buchi_next_and_release() {
+        let a = Buchi::from_ltl(&parse_ltl("X X a").unwrap());
+        assert!(accepts(&a, &[&[], &[], &["a"]], &[&[]]));
+        assert!(!accepts(&a, &[&[], &["a"]], &[&[]]));
+
+        let a = Buchi::from_ltl(&parse_ltl("a R b").unwrap());
+        assert!(accepts(&a, &[], &[&["b"]]));
+        assert!(accepts(&a, &[&["b"], &["a", "b"]], &[&[]]));
+        assert!(!accepts(&a, &[&["b"], &["a"]], &[&[]]));
+    }
+}
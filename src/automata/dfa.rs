@@ -0,0 +1,313 @@
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+use crate::ltl::LTLFormulae;
+
+/// Deterministic finite automaton over the valuations of some atomic
+/// propositions, bit `i` of a letter being the value of the `i`th one.
+#[derive(Debug, Clone)]
+pub struct Dfa {
+    pub atomics: Vec<String>,
+    pub initial: usize,
+    /// The successor of each state for each letter.
+    pub transitions: Vec<Vec<usize>>,
+    pub accepting: HashSet<usize>,
+}
+
+/// A subformula in negation normal form, on finite traces, where the
+/// negation of the next operator is the weak next, holding in the last
+/// position.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum Node {
+    True,
+    False,
+    Literal(usize, bool),
+    And(usize, usize),
+    Or(usize, usize),
+    Next(usize),
+    WeakNext(usize),
+    Until(usize, usize),
+    Release(usize, usize),
+}
+
+/// The subformulae at the next position which the rest of the trace must
+/// satisfy, in disjunctive normal form. An obligation is a subformula with
+/// whether it is strong, requiring a next position, or weak.
+type Obligations = BTreeSet<BTreeSet<(usize, bool)>>;
+
+struct Builder {
+    atomics: Vec<String>,
+    nodes: Vec<Node>,
+    ids: HashMap<Node, usize>,
+}
+
+impl Builder {
+    fn node(&mut self, node: Node) -> usize {
+        if let Some(id) = self.ids.get(&node) {
+            return *id;
+        }
+        self.nodes.push(node.clone());
+        self.ids.insert(node, self.nodes.len() - 1);
+        self.nodes.len() - 1
+    }
+
+    /// Add `f`, or its negation when `negated`, in negation normal form,
+    /// failing on the past operators.
+    fn add(&mut self, f: &LTLFormulae, negated: bool) -> Result<usize, String> {
+        let node = match f {
+            LTLFormulae::True if negated => Node::False,
+            LTLFormulae::True => Node::True,
+            LTLFormulae::Atomic(a) => {
+                let index = self.atomics.iter().position(|b| b == a).unwrap();
+                Node::Literal(index, !negated)
+            }
+            LTLFormulae::Not(f) => return self.add(f, !negated),
+            LTLFormulae::And(f, g) => {
+                let (f, g) = (self.add(f, negated)?, self.add(g, negated)?);
+                if negated {
+                    Node::Or(f, g)
+                } else {
+                    Node::And(f, g)
+                }
+            }
+            LTLFormulae::Next(f) if negated => Node::WeakNext(self.add(f, true)?),
+            LTLFormulae::Next(f) => Node::Next(self.add(f, false)?),
+            LTLFormulae::Until(f, g) => {
+                let (f, g) = (self.add(f, negated)?, self.add(g, negated)?);
+                if negated {
+                    Node::Release(f, g)
+                } else {
+                    Node::Until(f, g)
+                }
+            }
+            LTLFormulae::BoundedUntil(_, _, _, _) => return self.add(&f.unroll_bounded(), negated),
+            LTLFormulae::Yesterday(_) | LTLFormulae::Since(_, _) => {
+                return Err(format!(
+                    "Past operator in {}, which has no finite automaton here",
+                    f
+                ))
+            }
+        };
+        Ok(self.node(node))
+    }
+
+    /// The obligations for the rest of the trace, for `node` to hold at a
+    /// position labelled with `letter`.
+    fn progress(&self, node: usize, letter: usize) -> Obligations {
+        let unit = |obligation| BTreeSet::from([BTreeSet::from([obligation])]);
+        match self.nodes[node] {
+            Node::True => BTreeSet::from([BTreeSet::new()]),
+            Node::False => BTreeSet::new(),
+            Node::Literal(a, positive) => {
+                if (letter >> a & 1 == 1) == positive {
+                    BTreeSet::from([BTreeSet::new()])
+                } else {
+                    BTreeSet::new()
+                }
+            }
+            Node::And(f, g) => and(&self.progress(f, letter), &self.progress(g, letter)),
+            Node::Or(f, g) => or(self.progress(f, letter), self.progress(g, letter)),
+            Node::Next(f) => unit((f, true)),
+            Node::WeakNext(f) => unit((f, false)),
+            // g || (f && X(f U g))
+            Node::Until(f, g) => or(
+                self.progress(g, letter),
+                and(&self.progress(f, letter), &unit((node, true))),
+            ),
+            // g && (f || WX(f R g))
+            Node::Release(f, g) => and(
+                &self.progress(g, letter),
+                &or(self.progress(f, letter), unit((node, false))),
+            ),
+        }
+    }
+}
+
+fn or(mut f: Obligations, g: Obligations) -> Obligations {
+    f.extend(g);
+    simplify(f)
+}
+
+fn and(f: &Obligations, g: &Obligations) -> Obligations {
+    let mut result = BTreeSet::new();
+    for a in f {
+        for b in g {
+            result.insert(a.union(b).cloned().collect());
+        }
+    }
+    simplify(result)
+}
+
+/// Drop the weak obligations implied by strong ones, and the conjunctions
+/// implied by others, so that equivalent states are more often equal.
+fn simplify(f: Obligations) -> Obligations {
+    let f = f
+        .into_iter()
+        .map(|clause| {
+            clause
+                .iter()
+                .filter(|(g, strong)| *strong || !clause.contains(&(*g, true)))
+                .cloned()
+                .collect::<BTreeSet<(usize, bool)>>()
+        })
+        .collect::<Vec<_>>();
+    f.iter()
+        .filter(|clause| {
+            !f.iter()
+                .any(|other| other.len() < clause.len() && other.is_subset(clause))
+        })
+        .cloned()
+        .collect()
+}
+
+impl Dfa {
+    /// Translate the future formula `formula` into an automaton accepting
+    /// exactly its models among the nonempty finite traces, by progression:
+    /// a state is what the rest of the trace must satisfy. Fails on the past
+    /// operators, and when there are too many atomic propositions for the
+    /// letters.
+    pub fn from_ltlf(formula: &LTLFormulae) -> Result<Dfa, String> {
+        let mut atomics = formula.atomics().into_iter().collect::<Vec<String>>();
+        atomics.sort();
+        let mut builder = Builder {
+            atomics,
+            nodes: Vec::new(),
+            ids: HashMap::new(),
+        };
+        let letters = u32::try_from(builder.atomics.len())
+            .ok()
+            .and_then(|n| 1usize.checked_shl(n))
+            .ok_or_else(|| {
+                format!(
+                    "{} atomic propositions are too many for the letters",
+                    builder.atomics.len()
+                )
+            })?;
+        let root = builder.add(formula, false)?;
+
+        let initial = BTreeSet::from([BTreeSet::from([(root, true)])]);
+        let mut ids = HashMap::from([(initial.clone(), 0)]);
+        let mut states = vec![initial];
+        let mut transitions = Vec::new();
+        let mut i = 0;
+        while i < states.len() {
+            let mut next = Vec::new();
+            for letter in 0..letters {
+                let mut successor = BTreeSet::new();
+                for clause in &states[i] {
+                    let mut conjunction = BTreeSet::from([BTreeSet::new()]);
+                    for (f, _) in clause {
+                        conjunction = and(&conjunction, &builder.progress(*f, letter));
+                    }
+                    successor = or(successor, conjunction);
+                }
+                let id = *ids.entry(successor.clone()).or_insert_with(|| {
+                    states.push(successor);
+                    states.len() - 1
+                });
+                next.push(id);
+            }
+            transitions.push(next);
+            i += 1;
+        }
+        // the trace may end when no obligation requires a next position
+        let accepting = (0..states.len())
+            .filter(|q| {
+                states[*q]
+                    .iter()
+                    .any(|clause| clause.iter().all(|(_, strong)| !strong))
+            })
+            .collect();
+
+        Ok(Dfa {
+            atomics: builder.atomics,
+            initial: 0,
+            transitions,
+            accepting,
+        })
+    }
+
+    /// The letter of a position with the atomic propositions `labels`.
+    pub fn letter(&self, labels: &HashSet<String>) -> usize {
+        self.atomics
+            .iter()
+            .enumerate()
+            .filter(|(_, a)| labels.contains(*a))
+            .map(|(i, _)| 1 << i)
+            .sum()
+    }
+
+    /// Whether the automaton accepts the trace of labels `trace`.
+    pub fn accepts(&self, trace: &[HashSet<String>]) -> bool {
+        let last = trace.iter().fold(self.initial, |q, labels| {
+            self.transitions[q][self.letter(labels)]
+        });
+        self.accepting.contains(&last)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{algorithm::ltlf::holds_finite, parser::ltl_parser::parse_ltl};
+
+    #[test]
+    fn test_ltlf_dfa() {
+        // every trace over a and b up to length 4
+        let mut traces = vec![Vec::new()];
+        for length in 0..4 {
+            for i in 0..traces.len() {
+                if traces[i].len() == length {
+                    for letter in 0..4 {
+                        let mut trace = traces[i].clone();
+                        let labels = [(1, "a"), (2, "b")]
+                            .iter()
+                            .filter(|(bit, _)| letter & bit != 0)
+                            .map(|(_, a)| a.to_string())
+                            .collect::<HashSet<String>>();
+                        trace.push(labels);
+                        traces.push(trace);
+                    }
+                }
+            }
+        }
+        for formula in [
+            "a U b",
+            "X a",
+            "!X true",
+            "G (a -> X b)",
+            "F (a && !X true)",
+            "G F a",
+            "a R (b || X !b)",
+            "(a U X b) W !a",
+            "F[1,2] a",
+            "G[0,1] (a || b)",
+            "a U[1,3] b",
+        ] {
+            let f = parse_ltl(formula).unwrap();
+            let dfa = Dfa::from_ltlf(&f).unwrap();
+            for trace in &traces {
+                assert_eq!(
+                    dfa.accepts(trace),
+                    holds_finite(trace, &f),
+                    "{} {:?}",
+                    formula,
+                    trace
+                );
+            }
+        }
+
+        // the last position has no next one
+        let dfa = Dfa::from_ltlf(&parse_ltl("F !X true").unwrap()).unwrap();
+        assert_eq!(dfa.transitions.len(), 2);
+        let dfa = Dfa::from_ltlf(&parse_ltl("G a").unwrap()).unwrap();
+        assert!(dfa.accepts(&[HashSet::from(["a".to_string()])]));
+        assert!(!dfa.accepts(&[]));
+
+        assert!(Dfa::from_ltlf(&parse_ltl("G (a -> Y b)").unwrap()).is_err());
+        let many = (0..64)
+            .map(|i| format!("a{}", i))
+            .collect::<Vec<String>>()
+            .join(" && ");
+        assert!(Dfa::from_ltlf(&parse_ltl(&many).unwrap()).is_err());
+    }
+}
@@ -0,0 +1,236 @@
+use std::{
+    collections::{BTreeSet, HashSet},
+    fmt::Display,
+};
+
+use petgraph::graph::DiGraph;
+
+use crate::{automata::buchi::Buchi, ltl::LTLFormulae, model::history::add_past_labels};
+
+/// The verdict of a monitor on the events read so far.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verdict {
+    /// Every infinite continuation of the events satisfies the formula.
+    True,
+    /// No infinite continuation of the events satisfies the formula.
+    False,
+    Inconclusive,
+}
+
+impl Display for Verdict {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Verdict::True => write!(formatter, "true"),
+            Verdict::False => write!(formatter, "false"),
+            Verdict::Inconclusive => write!(formatter, "inconclusive"),
+        }
+    }
+}
+
+/// An automaton with the states it can be in to read the next event, among
+/// those from which an accepting run starts.
+#[derive(Debug, Clone)]
+struct Tracker {
+    automaton: Buchi,
+    live: Vec<bool>,
+    current: BTreeSet<usize>,
+}
+
+impl Tracker {
+    fn new(formula: &LTLFormulae) -> Tracker {
+        let automaton = Buchi::from_ltl(formula);
+        let live = live_states(&automaton);
+        let current = initial_states(&automaton, &live);
+        Tracker {
+            automaton,
+            live,
+            current,
+        }
+    }
+
+    fn step(&mut self, event: &HashSet<String>) {
+        let automaton = &self.automaton;
+        self.current = self
+            .current
+            .iter()
+            .filter(|q| {
+                let label = &automaton.states[**q];
+                label.positive.iter().all(|p| event.contains(p))
+                    && label.negative.iter().all(|p| !event.contains(p))
+            })
+            .flat_map(|q| automaton.transitions[*q].iter())
+            .filter(|r| self.live[**r])
+            .cloned()
+            .collect();
+    }
+}
+
+fn initial_states(automaton: &Buchi, live: &[bool]) -> BTreeSet<usize> {
+    automaton
+        .initial
+        .iter()
+        .filter(|q| live[**q])
+        .cloned()
+        .collect()
+}
+
+/// Whether an accepting run starts in each state of `automaton`, reaching a
+/// cycle through every acceptance set.
+fn live_states(automaton: &Buchi) -> Vec<bool> {
+    let n = automaton.states.len();
+    let mut graph = DiGraph::<(), ()>::new();
+    let nodes = (0..n).map(|_| graph.add_node(())).collect::<Vec<_>>();
+    let mut predecessors = vec![Vec::new(); n];
+    for (q, next) in automaton.transitions.iter().enumerate() {
+        for r in next {
+            graph.add_edge(nodes[q], nodes[*r], ());
+            predecessors[*r].push(q);
+        }
+    }
+
+    let mut live = vec![false; n];
+    let mut queue = Vec::new();
+    for scc in petgraph::algo::tarjan_scc(&graph) {
+        let members = scc.iter().map(|n| n.index()).collect::<HashSet<usize>>();
+        let q = scc[0].index();
+        let cyclic = members.len() > 1 || automaton.transitions[q].contains(&q);
+        let accepting = automaton
+            .acceptance
+            .iter()
+            .all(|set| members.iter().any(|q| set.contains(q)));
+        if cyclic && accepting {
+            for q in members {
+                live[q] = true;
+                queue.push(q);
+            }
+        }
+    }
+    while let Some(q) = queue.pop() {
+        for p in &predecessors[q] {
+            if !live[*p] {
+                live[*p] = true;
+                queue.push(*p);
+            }
+        }
+    }
+    live
+}
+
+/// A monitor of an LTL formula with the three-valued semantics of LTL3,
+/// reading the events of a trace one at a time.
+///
+/// It follows the automata of the formula and of its negation, without
+/// their states from which no run is accepting: the formula is violated as
+/// soon as the first has no state left, and satisfied as soon as the second
+/// has none, which is as early as possible. The past subformulae are kept
+/// as labels of the events, with their history.
+#[derive(Debug, Clone)]
+pub struct Monitor {
+    positive: Tracker,
+    negative: Tracker,
+    past: Vec<(String, LTLFormulae)>,
+    /// The labels of the last event, with the past subformulae holding.
+    previous: Option<HashSet<String>>,
+}
+
+impl Monitor {
+    /// Fails when a past operator of `formula` has a future operator under
+    /// it.
+    pub fn new(formula: &LTLFormulae) -> Result<Monitor, String> {
+        let (formula, past) = formula.eliminate_past()?;
+        Ok(Monitor {
+            positive: Tracker::new(&formula),
+            negative: Tracker::new(&LTLFormulae::Not(Box::new(formula))),
+            past,
+            previous: None,
+        })
+    }
+
+    /// Read the next event, the atomic propositions holding in it, and
+    /// return the verdict. A verdict other than inconclusive never changes.
+    pub fn step(&mut self, event: &HashSet<String>) -> Verdict {
+        let mut labels = event.clone();
+        add_past_labels(&self.past, self.previous.as_ref(), &mut labels);
+        self.positive.step(&labels);
+        self.negative.step(&labels);
+        self.previous = Some(labels);
+        self.verdict()
+    }
+
+    /// The verdict on the events read so far.
+    pub fn verdict(&self) -> Verdict {
+        if self.positive.current.is_empty() {
+            Verdict::False
+        } else if self.negative.current.is_empty() {
+            Verdict::True
+        } else {
+            Verdict::Inconclusive
+        }
+    }
+
+    /// Forget the events read, to monitor another trace.
+    pub fn reset(&mut self) {
+        for tracker in [&mut self.positive, &mut self.negative] {
+            tracker.current = initial_states(&tracker.automaton, &tracker.live);
+        }
+        self.previous = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::ltl_parser::parse_ltl;
+
+    /// The verdicts of `formula` after each event of `trace`.
+    fn verdicts(formula: &str, trace: &[&[&str]]) -> Vec<Verdict> {
+        let mut monitor = Monitor::new(&parse_ltl(formula).unwrap()).unwrap();
+        trace
+            .iter()
+            .map(|event| monitor.step(&event.iter().map(|a| a.to_string()).collect()))
+            .collect()
+    }
+
+    #[test]
+    fn test_monitor() {
+        use Verdict::*;
+        assert_eq!(
+            verdicts("p U q", &[&["p"], &["p"], &["q"], &[]]),
+            vec![Inconclusive, Inconclusive, True, True]
+        );
+        assert_eq!(
+            verdicts("G ok", &[&["ok"], &[], &["ok"]]),
+            vec![Inconclusive, False, False]
+        );
+        assert_eq!(
+            verdicts("G (grant -> O request)", &[&["request"], &["grant"]]),
+            vec![Inconclusive, Inconclusive]
+        );
+        assert_eq!(
+            verdicts("G (grant -> O request)", &[&[], &["grant"]]),
+            vec![Inconclusive, False]
+        );
+        // never conclusive, and known before the last event
+        assert_eq!(
+            verdicts("G F p", &[&["p"], &[]]),
+            vec![Inconclusive, Inconclusive]
+        );
+        assert_eq!(
+            verdicts("X (p && !p) || F q", &[&[], &["q"]]),
+            vec![Inconclusive, True]
+        );
+        assert_eq!(verdicts("X X (p && !p)", &[&[]]), vec![False]);
+
+        let mut monitor = Monitor::new(&parse_ltl("F done").unwrap()).unwrap();
+        assert_eq!(monitor.step(&HashSet::from(["done".to_string()])), True);
+        monitor.reset();
+        assert_eq!(monitor.verdict(), Inconclusive);
+        let monitor = Monitor::new(&parse_ltl("p || !p").unwrap()).unwrap();
+        assert_eq!(monitor.verdict(), True);
+
+        assert_eq!(
+            Monitor::new(&parse_ltl("Y F grant").unwrap()).unwrap_err(),
+            "Y(true U grant) has a future operator under a past operator"
+        );
+    }
+}
@@ -1,5 +1,4 @@
-use std::fmt::Display;
-use std::hash::{Hash, Hasher};
+use std::{collections::HashSet, fmt::Display};
 
 #[derive(Debug, Clone)]
 pub enum CTLFormulae {
@@ -18,6 +17,12 @@ pub enum CTLFormulae {
     Globally(Box<CTLFormulae>),
     Until(Box<CTLFormulae>, Box<CTLFormulae>),
     Release(Box<CTLFormulae>, Box<CTLFormulae>),
+
+    /// The operators restricted to the steps `a` to `b` of the path, both
+    /// included, as in `F[a,b] f`.
+    BoundedFinally(usize, usize, Box<CTLFormulae>),
+    BoundedGlobally(usize, usize, Box<CTLFormulae>),
+    BoundedUntil(usize, usize, Box<CTLFormulae>, Box<CTLFormulae>),
 }
 
 impl Display for CTLFormulae {
@@ -35,6 +40,11 @@ impl Display for CTLFormulae {
             CTLFormulae::Globally(f) => write!(formatter, "G{}", f),
             CTLFormulae::Until(f, g) => write!(formatter, "({} U {})", f, g),
             CTLFormulae::Release(f, g) => write!(formatter, "({} R {})", f, g),
+            CTLFormulae::BoundedFinally(a, b, f) => write!(formatter, "F[{},{}]{}", a, b, f),
+            CTLFormulae::BoundedGlobally(a, b, f) => write!(formatter, "G[{},{}]{}", a, b, f),
+            CTLFormulae::BoundedUntil(a, b, f, g) => {
+                write!(formatter, "({} U[{},{}] {})", f, a, b, g)
+            }
         }
     }
 }
@@ -54,6 +64,90 @@ impl CTLFormulae {
             CTLFormulae::Globally(f) => format!("G{}", f.get_str()),
             CTLFormulae::Until(f, g) => format!("({}U{})", f.get_str(), g.get_str()),
             CTLFormulae::Release(f, g) => format!("({}R{})", f.get_str(), g.get_str()),
+            CTLFormulae::BoundedFinally(a, b, f) => format!("F[{},{}]{}", a, b, f.get_str()),
+            CTLFormulae::BoundedGlobally(a, b, f) => format!("G[{},{}]{}", a, b, f.get_str()),
+            CTLFormulae::BoundedUntil(a, b, f, g) => {
+                format!("({}U[{},{}]{})", f.get_str(), a, b, g.get_str())
+            }
+        }
+    }
+
+    /// Whether the formula does not use the next operator, so that it cannot
+    /// distinguish states that only differ by stuttering.
+    pub fn is_next_free(&self) -> bool {
+        match self {
+            CTLFormulae::True | CTLFormulae::Atomic(_) => true,
+            // the steps are counted
+            CTLFormulae::Next(_)
+            | CTLFormulae::BoundedFinally(_, _, _)
+            | CTLFormulae::BoundedGlobally(_, _, _)
+            | CTLFormulae::BoundedUntil(_, _, _, _) => false,
+            CTLFormulae::Not(f)
+            | CTLFormulae::All(f)
+            | CTLFormulae::Exist(f)
+            | CTLFormulae::Finally(f)
+            | CTLFormulae::Globally(f) => f.is_next_free(),
+            CTLFormulae::And(f, g)
+            | CTLFormulae::Or(f, g)
+            | CTLFormulae::Until(f, g)
+            | CTLFormulae::Release(f, g) => f.is_next_free() && g.is_next_free(),
+        }
+    }
+
+    /// Check that the formula is a state formula, every temporal operator
+    /// being right under a path quantifier and every path quantifier right
+    /// above one.
+    ///
+    /// Fails with the first subformula which is not.
+    pub fn check_state_form(&self) -> Result<(), String> {
+        let error = || Err(format!("{} is not a CTL state formula", self));
+        match self {
+            CTLFormulae::True | CTLFormulae::Atomic(_) => Ok(()),
+            CTLFormulae::Not(f) => f.check_state_form(),
+            CTLFormulae::And(f, g) | CTLFormulae::Or(f, g) => {
+                f.check_state_form()?;
+                g.check_state_form()
+            }
+            CTLFormulae::All(path) | CTLFormulae::Exist(path) => match &**path {
+                CTLFormulae::Next(f)
+                | CTLFormulae::Finally(f)
+                | CTLFormulae::Globally(f)
+                | CTLFormulae::BoundedFinally(_, _, f)
+                | CTLFormulae::BoundedGlobally(_, _, f) => f.check_state_form(),
+                CTLFormulae::Until(f, g)
+                | CTLFormulae::Release(f, g)
+                | CTLFormulae::BoundedUntil(_, _, f, g) => {
+                    f.check_state_form()?;
+                    g.check_state_form()
+                }
+                _ => error(),
+            },
+            _ => error(),
+        }
+    }
+
+    /// The atomic propositions occurring in the formula.
+    pub fn atomics(&self) -> HashSet<String> {
+        match self {
+            CTLFormulae::True => HashSet::new(),
+            CTLFormulae::Atomic(a) => HashSet::from([a.clone()]),
+            CTLFormulae::Not(f)
+            | CTLFormulae::All(f)
+            | CTLFormulae::Exist(f)
+            | CTLFormulae::Next(f)
+            | CTLFormulae::Finally(f)
+            | CTLFormulae::Globally(f)
+            | CTLFormulae::BoundedFinally(_, _, f)
+            | CTLFormulae::BoundedGlobally(_, _, f) => f.atomics(),
+            CTLFormulae::And(f, g)
+            | CTLFormulae::Or(f, g)
+            | CTLFormulae::Until(f, g)
+            | CTLFormulae::Release(f, g)
+            | CTLFormulae::BoundedUntil(_, _, f, g) => {
+                let mut atomics = f.atomics();
+                atomics.extend(g.atomics());
+                atomics
+            }
         }
     }
 }
@@ -108,6 +202,27 @@ pub fn to_normal_form_rec(formulae: CTLFormulae) -> CTLFormulae {
                     Box::new(CTLFormulae::Not(g)),
                 )))))
             }
+            CTLFormulae::BoundedFinally(a, b, f) => CTLFormulae::Not(Box::new(CTLFormulae::Exist(
+                Box::new(CTLFormulae::BoundedGlobally(
+                    a,
+                    b,
+                    Box::new(CTLFormulae::Not(Box::new(to_normal_form_rec(*f)))),
+                )),
+            ))),
+            CTLFormulae::BoundedGlobally(a, b, f) => CTLFormulae::Not(Box::new(
+                CTLFormulae::Exist(Box::new(CTLFormulae::BoundedUntil(
+                    a,
+                    b,
+                    Box::new(CTLFormulae::True),
+                    Box::new(CTLFormulae::Not(Box::new(to_normal_form_rec(*f)))),
+                ))),
+            )),
+            // kept universal, its dual being a bounded release
+            CTLFormulae::BoundedUntil(a, b, f, g) => {
+                let f = Box::new(to_normal_form_rec(*f));
+                let g = Box::new(to_normal_form_rec(*g));
+                CTLFormulae::All(Box::new(CTLFormulae::BoundedUntil(a, b, f, g)))
+            }
             _ => to_normal_form_rec(*all),
         },
         CTLFormulae::Exist(e) => match *e {
@@ -115,8 +230,48 @@ pub fn to_normal_form_rec(formulae: CTLFormulae) -> CTLFormulae {
                 Box::new(CTLFormulae::True),
                 Box::new(to_normal_form_rec(*f)),
             ))),
-            CTLFormulae::Release(_, _) => todo!(),
-            _ => CTLFormulae::Exist(Box::new(*e)),
+            CTLFormulae::Next(f) => CTLFormulae::Exist(Box::new(CTLFormulae::Next(Box::new(
+                to_normal_form_rec(*f),
+            )))),
+            CTLFormulae::Globally(f) => CTLFormulae::Exist(Box::new(CTLFormulae::Globally(
+                Box::new(to_normal_form_rec(*f)),
+            ))),
+            CTLFormulae::Until(f, g) => {
+                let f = Box::new(to_normal_form_rec(*f));
+                let g = Box::new(to_normal_form_rec(*g));
+                CTLFormulae::Exist(Box::new(CTLFormulae::Until(f, g)))
+            }
+            // E (f R g) = EG g || E (g U (f && g))
+            CTLFormulae::Release(f, g) => {
+                let f = Box::new(to_normal_form_rec(*f));
+                let g = Box::new(to_normal_form_rec(*g));
+                CTLFormulae::Or(
+                    Box::new(CTLFormulae::Exist(Box::new(CTLFormulae::Globally(
+                        g.clone(),
+                    )))),
+                    Box::new(CTLFormulae::Exist(Box::new(CTLFormulae::Until(
+                        g.clone(),
+                        Box::new(CTLFormulae::And(f, g)),
+                    )))),
+                )
+            }
+            CTLFormulae::BoundedFinally(a, b, f) => {
+                CTLFormulae::Exist(Box::new(CTLFormulae::BoundedUntil(
+                    a,
+                    b,
+                    Box::new(CTLFormulae::True),
+                    Box::new(to_normal_form_rec(*f)),
+                )))
+            }
+            CTLFormulae::BoundedGlobally(a, b, f) => CTLFormulae::Exist(Box::new(
+                CTLFormulae::BoundedGlobally(a, b, Box::new(to_normal_form_rec(*f))),
+            )),
+            CTLFormulae::BoundedUntil(a, b, f, g) => {
+                let f = Box::new(to_normal_form_rec(*f));
+                let g = Box::new(to_normal_form_rec(*g));
+                CTLFormulae::Exist(Box::new(CTLFormulae::BoundedUntil(a, b, f, g)))
+            }
+            _ => CTLFormulae::Exist(Box::new(to_normal_form_rec(*e))),
         },
         CTLFormulae::Next(f) => {
             let f = Box::new(to_normal_form_rec(*f));
@@ -140,6 +295,17 @@ pub fn to_normal_form_rec(formulae: CTLFormulae) -> CTLFormulae {
             let g = Box::new(to_normal_form_rec(*g));
             CTLFormulae::Release(f, g)
         }
+        CTLFormulae::BoundedFinally(a, b, f) => {
+            CTLFormulae::BoundedFinally(a, b, Box::new(to_normal_form_rec(*f)))
+        }
+        CTLFormulae::BoundedGlobally(a, b, f) => {
+            CTLFormulae::BoundedGlobally(a, b, Box::new(to_normal_form_rec(*f)))
+        }
+        CTLFormulae::BoundedUntil(a, b, f, g) => {
+            let f = Box::new(to_normal_form_rec(*f));
+            let g = Box::new(to_normal_form_rec(*g));
+            CTLFormulae::BoundedUntil(a, b, f, g)
+        }
         CTLFormulae::Not(f) => {
             if let CTLFormulae::Not(f) = *f {
                 to_normal_form_rec(*f)
@@ -153,6 +319,32 @@ pub fn to_normal_form_rec(formulae: CTLFormulae) -> CTLFormulae {
 
 pub fn to_normal_form(formulae: CTLFormulae) -> CTLFormulae {
     let pass1 = to_normal_form_rec(formulae);
-    let pass2 = to_normal_form_rec(pass1);
-    pass2
+    to_normal_form_rec(pass1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{mu_calculus::MuFormulae, parser::ctl_parser::parse_ctl};
+
+    #[test]
+    fn test_exist_normal_form() {
+        let normal = |formula: &str| to_normal_form(parse_ctl(formula).unwrap()).get_str();
+        // the operands are in normal form too
+        assert_eq!(normal("EX !!p"), "EXp");
+        assert_eq!(normal("EG AX p"), "EG!EX!p");
+        assert_eq!(normal("E (p U AX q)"), "E(pU!EX!q)");
+        assert_eq!(normal("E (p R q)"), "(EGq||E(qU(p&&q)))");
+    }
+
+    #[test]
+    fn test_state_form() {
+        let check = |formula: &str| parse_ctl(formula).unwrap().check_state_form();
+        assert!(check("AG (p -> E (q U[1,2] r)) && !EX p").is_ok());
+        assert!(check("X p").is_err());
+        let quantified = CTLFormulae::Exist(Box::new(CTLFormulae::Atomic("p".to_string())));
+        assert!(quantified.check_state_form().is_err());
+        assert!(check("A (F p && G q)").is_err());
+        assert!(MuFormulae::from_ctl(&parse_ctl("EF p U q").unwrap()).is_err());
+    }
 }
@@ -0,0 +1,135 @@
+use std::fmt::Display;
+
+use crate::{
+    ctl::CTLFormulae,
+    ltl::{unroll_until, LTLFormulae},
+};
+
+/// CTL* formulae, where path quantifiers and temporal operators can be
+/// nested freely, as in `E(GF p ∧ FG q)`.
+#[derive(Debug, Clone)]
+pub enum CTLStarFormulae {
+    True,
+    Atomic(String),
+
+    Not(Box<CTLStarFormulae>),
+    And(Box<CTLStarFormulae>, Box<CTLStarFormulae>),
+    Or(Box<CTLStarFormulae>, Box<CTLStarFormulae>),
+
+    All(Box<CTLStarFormulae>),
+    Exist(Box<CTLStarFormulae>),
+
+    Next(Box<CTLStarFormulae>),
+    Finally(Box<CTLStarFormulae>),
+    Globally(Box<CTLStarFormulae>),
+    Until(Box<CTLStarFormulae>, Box<CTLStarFormulae>),
+    Release(Box<CTLStarFormulae>, Box<CTLStarFormulae>),
+}
+
+impl Display for CTLStarFormulae {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CTLStarFormulae::True => write!(formatter, "true"),
+            CTLStarFormulae::Atomic(s) => write!(formatter, "{}", s),
+            CTLStarFormulae::Not(f) => write!(formatter, "¬{}", f),
+            CTLStarFormulae::And(f, g) => write!(formatter, "({} ∧ {})", f, g),
+            CTLStarFormulae::Or(f, g) => write!(formatter, "({} ∨ {})", f, g),
+            CTLStarFormulae::All(f) => write!(formatter, "A{}", f),
+            CTLStarFormulae::Exist(f) => write!(formatter, "E{}", f),
+            CTLStarFormulae::Next(f) => write!(formatter, "X{}", f),
+            CTLStarFormulae::Finally(f) => write!(formatter, "F{}", f),
+            CTLStarFormulae::Globally(f) => write!(formatter, "G{}", f),
+            CTLStarFormulae::Until(f, g) => write!(formatter, "({} U {})", f, g),
+            CTLStarFormulae::Release(f, g) => write!(formatter, "({} R {})", f, g),
+        }
+    }
+}
+
+impl CTLStarFormulae {
+    /// Whether the formula only depends on the current state, its temporal
+    /// operators all being under a path quantifier.
+    pub fn is_state_formula(&self) -> bool {
+        match self {
+            CTLStarFormulae::True
+            | CTLStarFormulae::Atomic(_)
+            | CTLStarFormulae::All(_)
+            | CTLStarFormulae::Exist(_) => true,
+            CTLStarFormulae::Not(f) => f.is_state_formula(),
+            CTLStarFormulae::And(f, g) | CTLStarFormulae::Or(f, g) => {
+                f.is_state_formula() && g.is_state_formula()
+            }
+            _ => false,
+        }
+    }
+}
+
+impl From<CTLFormulae> for CTLStarFormulae {
+    fn from(formula: CTLFormulae) -> Self {
+        let unary = |f: Box<CTLFormulae>| Box::new(CTLStarFormulae::from(*f));
+        match formula {
+            CTLFormulae::True => CTLStarFormulae::True,
+            CTLFormulae::Atomic(a) => CTLStarFormulae::Atomic(a),
+            CTLFormulae::Not(f) => CTLStarFormulae::Not(unary(f)),
+            CTLFormulae::And(f, g) => CTLStarFormulae::And(unary(f), unary(g)),
+            CTLFormulae::Or(f, g) => CTLStarFormulae::Or(unary(f), unary(g)),
+            CTLFormulae::All(f) => CTLStarFormulae::All(unary(f)),
+            CTLFormulae::Exist(f) => CTLStarFormulae::Exist(unary(f)),
+            CTLFormulae::Next(f) => CTLStarFormulae::Next(unary(f)),
+            CTLFormulae::Finally(f) => CTLStarFormulae::Finally(unary(f)),
+            CTLFormulae::Globally(f) => CTLStarFormulae::Globally(unary(f)),
+            CTLFormulae::Until(f, g) => CTLStarFormulae::Until(unary(f), unary(g)),
+            CTLFormulae::Release(f, g) => CTLStarFormulae::Release(unary(f), unary(g)),
+            CTLFormulae::BoundedFinally(a, b, f) => {
+                bounded_until(a, b, CTLStarFormulae::True, CTLStarFormulae::from(*f))
+            }
+            // G[a,b] f = !(true U[a,b] !f)
+            CTLFormulae::BoundedGlobally(a, b, f) => {
+                let f = CTLStarFormulae::Not(unary(f));
+                CTLStarFormulae::Not(Box::new(bounded_until(a, b, CTLStarFormulae::True, f)))
+            }
+            CTLFormulae::BoundedUntil(a, b, f, g) => {
+                bounded_until(a, b, CTLStarFormulae::from(*f), CTLStarFormulae::from(*g))
+            }
+        }
+    }
+}
+
+/// The path formula `f U[a,b] g`, with nested next operators.
+pub(crate) fn bounded_until(
+    a: usize,
+    b: usize,
+    f: CTLStarFormulae,
+    g: CTLStarFormulae,
+) -> CTLStarFormulae {
+    unroll_until(
+        a,
+        b,
+        f,
+        g,
+        |f| CTLStarFormulae::Next(Box::new(f)),
+        |f, g| CTLStarFormulae::And(Box::new(f), Box::new(g)),
+        |f, g| CTLStarFormulae::Or(Box::new(f), Box::new(g)),
+    )
+}
+
+/// An LTL formula without past operators is a CTL* path formula, holding in
+/// a state when all the paths from it satisfy the formula.
+impl TryFrom<LTLFormulae> for CTLStarFormulae {
+    type Error = String;
+
+    fn try_from(formula: LTLFormulae) -> Result<Self, Self::Error> {
+        let unary = |f: Box<LTLFormulae>| CTLStarFormulae::try_from(*f).map(Box::new);
+        Ok(match formula {
+            LTLFormulae::True => CTLStarFormulae::True,
+            LTLFormulae::Atomic(a) => CTLStarFormulae::Atomic(a),
+            LTLFormulae::Not(f) => CTLStarFormulae::Not(unary(f)?),
+            LTLFormulae::And(f, g) => CTLStarFormulae::And(unary(f)?, unary(g)?),
+            LTLFormulae::Next(f) => CTLStarFormulae::Next(unary(f)?),
+            LTLFormulae::Until(f, g) => CTLStarFormulae::Until(unary(f)?, unary(g)?),
+            f @ LTLFormulae::BoundedUntil(_, _, _, _) => {
+                CTLStarFormulae::try_from(f.unroll_bounded())?
+            }
+            f => return Err(format!("{} has past operators, which CTL* has not", f)),
+        })
+    }
+}
@@ -0,0 +1,158 @@
+use std::{collections::BTreeSet, fmt::Display};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BinaryOp {
+    Or,
+    And,
+    Eq,
+    Neq,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Mod,
+}
+
+/// Integer expressions over the variables of a program.
+///
+/// Booleans are integers, 0 being false and anything else true.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Expr {
+    Int(i64),
+    /// A variable, by index in the program.
+    Var(usize),
+    Not(Box<Expr>),
+    Neg(Box<Expr>),
+    Binary(BinaryOp, Box<Expr>, Box<Expr>),
+}
+
+/// An arithmetic error in the evaluation of an expression.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EvalError {
+    DivisionByZero,
+    Overflow,
+}
+
+impl Display for EvalError {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EvalError::DivisionByZero => write!(formatter, "division by zero"),
+            EvalError::Overflow => write!(formatter, "arithmetic overflow"),
+        }
+    }
+}
+
+impl std::error::Error for EvalError {}
+
+impl BinaryOp {
+    pub fn apply(&self, lhs: i64, rhs: i64) -> Result<i64, EvalError> {
+        let checked = |result: Option<i64>| result.ok_or(EvalError::Overflow);
+        match self {
+            BinaryOp::Or => Ok((lhs != 0 || rhs != 0) as i64),
+            BinaryOp::And => Ok((lhs != 0 && rhs != 0) as i64),
+            BinaryOp::Eq => Ok((lhs == rhs) as i64),
+            BinaryOp::Neq => Ok((lhs != rhs) as i64),
+            BinaryOp::Lt => Ok((lhs < rhs) as i64),
+            BinaryOp::Le => Ok((lhs <= rhs) as i64),
+            BinaryOp::Gt => Ok((lhs > rhs) as i64),
+            BinaryOp::Ge => Ok((lhs >= rhs) as i64),
+            BinaryOp::Add => checked(lhs.checked_add(rhs)),
+            BinaryOp::Sub => checked(lhs.checked_sub(rhs)),
+            BinaryOp::Mul => checked(lhs.checked_mul(rhs)),
+            BinaryOp::Div | BinaryOp::Mod if rhs == 0 => Err(EvalError::DivisionByZero),
+            BinaryOp::Div => checked(lhs.checked_div(rhs)),
+            BinaryOp::Mod => checked(lhs.checked_rem(rhs)),
+        }
+    }
+
+    fn symbol(&self) -> &'static str {
+        match self {
+            BinaryOp::Or => "||",
+            BinaryOp::And => "&&",
+            BinaryOp::Eq => "==",
+            BinaryOp::Neq => "!=",
+            BinaryOp::Lt => "<",
+            BinaryOp::Le => "<=",
+            BinaryOp::Gt => ">",
+            BinaryOp::Ge => ">=",
+            BinaryOp::Add => "+",
+            BinaryOp::Sub => "-",
+            BinaryOp::Mul => "*",
+            BinaryOp::Div => "/",
+            BinaryOp::Mod => "%",
+        }
+    }
+}
+
+impl Expr {
+    /// The value of the expression in `state`.
+    ///
+    /// The conjunctions and disjunctions are short-circuiting, so that a
+    /// guard such as `y != 0 && x / y > 1` never divides by zero.
+    pub fn eval(&self, state: &[i64]) -> Result<i64, EvalError> {
+        Ok(match self {
+            Expr::Int(i) => *i,
+            Expr::Var(v) => state[*v],
+            Expr::Not(e) => (e.eval(state)? == 0) as i64,
+            Expr::Neg(e) => e.eval(state)?.checked_neg().ok_or(EvalError::Overflow)?,
+            Expr::Binary(BinaryOp::And, lhs, rhs) => {
+                (lhs.holds(state)? && rhs.holds(state)?) as i64
+            }
+            Expr::Binary(BinaryOp::Or, lhs, rhs) => (lhs.holds(state)? || rhs.holds(state)?) as i64,
+            Expr::Binary(op, lhs, rhs) => op.apply(lhs.eval(state)?, rhs.eval(state)?)?,
+        })
+    }
+
+    pub fn holds(&self, state: &[i64]) -> Result<bool, EvalError> {
+        Ok(self.eval(state)? != 0)
+    }
+
+    /// The variables read by the expression.
+    pub fn variables(&self) -> BTreeSet<usize> {
+        let mut vars = BTreeSet::new();
+        self.collect_variables(&mut vars);
+        vars
+    }
+
+    fn collect_variables(&self, vars: &mut BTreeSet<usize>) {
+        match self {
+            Expr::Int(_) => {}
+            Expr::Var(v) => {
+                vars.insert(*v);
+            }
+            Expr::Not(e) | Expr::Neg(e) => e.collect_variables(vars),
+            Expr::Binary(_, lhs, rhs) => {
+                lhs.collect_variables(vars);
+                rhs.collect_variables(vars);
+            }
+        }
+    }
+
+    /// The operands of the top-level conjunctions.
+    pub fn conjuncts(&self) -> Vec<&Expr> {
+        match self {
+            Expr::Binary(BinaryOp::And, lhs, rhs) => {
+                let mut conjuncts = lhs.conjuncts();
+                conjuncts.extend(rhs.conjuncts());
+                conjuncts
+            }
+            _ => vec![self],
+        }
+    }
+}
+
+impl Display for Expr {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Expr::Int(i) => write!(formatter, "{}", i),
+            Expr::Var(v) => write!(formatter, "${}", v),
+            Expr::Not(e) => write!(formatter, "!{}", e),
+            Expr::Neg(e) => write!(formatter, "-{}", e),
+            Expr::Binary(op, lhs, rhs) => write!(formatter, "({} {} {})", lhs, op.symbol(), rhs),
+        }
+    }
+}
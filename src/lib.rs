@@ -1,7 +1,13 @@
-#[macro_use]
-
 pub mod algorithm;
+pub mod atl;
+pub mod automata;
 pub mod ctl;
+pub mod ctl_star;
+pub mod expr;
 pub mod ltl;
 pub mod model;
+pub mod mu_calculus;
 pub mod parser;
+pub mod pctl;
+pub mod sat;
+pub mod tctl;
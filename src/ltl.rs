@@ -1,4 +1,4 @@
-use std::fmt::Display;
+use std::{collections::HashSet, fmt::Display};
 
 #[derive(Debug, Clone)]
 pub enum LTLFormulae {
@@ -8,6 +8,34 @@ pub enum LTLFormulae {
     Not(Box<LTLFormulae>),
     Next(Box<LTLFormulae>),
     Until(Box<LTLFormulae>, Box<LTLFormulae>),
+
+    /// `Y f`, `f` held in the previous state, which the first state has not.
+    Yesterday(Box<LTLFormulae>),
+    /// `f S g`, `g` held in some state up to now, and `f` ever since.
+    Since(Box<LTLFormulae>, Box<LTLFormulae>),
+
+    /// `f U[a,b] g`, `g` holding in one of the steps `a` to `b`, both
+    /// included, and `f` before it.
+    BoundedUntil(usize, usize, Box<LTLFormulae>, Box<LTLFormulae>),
+}
+
+/// `f U[a,b] g` with nested next operators, built by `next`, `and` and `or`,
+/// from the step `b` backwards.
+pub(crate) fn unroll_until<T: Clone>(
+    a: usize,
+    b: usize,
+    f: T,
+    g: T,
+    next: impl Fn(T) -> T,
+    and: impl Fn(T, T) -> T,
+    or: impl Fn(T, T) -> T,
+) -> T {
+    let mut result = g.clone();
+    for k in (0..b).rev() {
+        let later = and(f.clone(), next(result));
+        result = if k >= a { or(g.clone(), later) } else { later };
+    }
+    result
 }
 
 impl Display for LTLFormulae {
@@ -19,6 +47,155 @@ impl Display for LTLFormulae {
             LTLFormulae::And(f, g) => write!(formatter, "({} ∧ {})", f, g),
             LTLFormulae::Next(f) => write!(formatter, "X{}", f),
             LTLFormulae::Until(f, g) => write!(formatter, "({} U {})", f, g),
+            LTLFormulae::Yesterday(f) => write!(formatter, "Y{}", f),
+            LTLFormulae::Since(f, g) => write!(formatter, "({} S {})", f, g),
+            LTLFormulae::BoundedUntil(a, b, f, g) => {
+                write!(formatter, "({} U[{},{}] {})", f, a, b, g)
+            }
+        }
+    }
+}
+
+impl LTLFormulae {
+    /// Whether the formula does not use the next operator, so that it cannot
+    /// distinguish paths that only differ by stuttering.
+    pub fn is_next_free(&self) -> bool {
+        match self {
+            LTLFormulae::True | LTLFormulae::Atomic(_) => true,
+            LTLFormulae::Next(_)
+            | LTLFormulae::Yesterday(_)
+            | LTLFormulae::BoundedUntil(_, _, _, _) => false,
+            LTLFormulae::Not(f) => f.is_next_free(),
+            LTLFormulae::And(f, g) | LTLFormulae::Until(f, g) | LTLFormulae::Since(f, g) => {
+                f.is_next_free() && g.is_next_free()
+            }
+        }
+    }
+
+    /// The atomic propositions occurring in the formula.
+    pub fn atomics(&self) -> HashSet<String> {
+        match self {
+            LTLFormulae::True => HashSet::new(),
+            LTLFormulae::Atomic(a) => HashSet::from([a.clone()]),
+            LTLFormulae::Not(f) | LTLFormulae::Next(f) | LTLFormulae::Yesterday(f) => f.atomics(),
+            LTLFormulae::And(f, g)
+            | LTLFormulae::Until(f, g)
+            | LTLFormulae::Since(f, g)
+            | LTLFormulae::BoundedUntil(_, _, f, g) => {
+                let mut atomics = f.atomics();
+                atomics.extend(g.atomics());
+                atomics
+            }
+        }
+    }
+
+    /// Whether the formula has no past operator.
+    pub fn is_future(&self) -> bool {
+        match self {
+            LTLFormulae::True | LTLFormulae::Atomic(_) => true,
+            LTLFormulae::Yesterday(_) | LTLFormulae::Since(_, _) => false,
+            LTLFormulae::Not(f) | LTLFormulae::Next(f) => f.is_future(),
+            LTLFormulae::And(f, g)
+            | LTLFormulae::Until(f, g)
+            | LTLFormulae::BoundedUntil(_, _, f, g) => f.is_future() && g.is_future(),
+        }
+    }
+
+    /// The formula with its bounded until operators unrolled into next
+    /// operators.
+    pub fn unroll_bounded(&self) -> LTLFormulae {
+        let unary = |f: &LTLFormulae| Box::new(f.unroll_bounded());
+        match self {
+            LTLFormulae::True | LTLFormulae::Atomic(_) => self.clone(),
+            LTLFormulae::Not(f) => LTLFormulae::Not(unary(f)),
+            LTLFormulae::Next(f) => LTLFormulae::Next(unary(f)),
+            LTLFormulae::Yesterday(f) => LTLFormulae::Yesterday(unary(f)),
+            LTLFormulae::And(f, g) => LTLFormulae::And(unary(f), unary(g)),
+            LTLFormulae::Until(f, g) => LTLFormulae::Until(unary(f), unary(g)),
+            LTLFormulae::Since(f, g) => LTLFormulae::Since(unary(f), unary(g)),
+            LTLFormulae::BoundedUntil(a, b, f, g) => unroll_until(
+                *a,
+                *b,
+                f.unroll_bounded(),
+                g.unroll_bounded(),
+                |f| LTLFormulae::Next(Box::new(f)),
+                |f, g| LTLFormulae::And(Box::new(f), Box::new(g)),
+                |f, g| {
+                    LTLFormulae::Not(Box::new(LTLFormulae::And(
+                        Box::new(LTLFormulae::Not(Box::new(f))),
+                        Box::new(LTLFormulae::Not(Box::new(g))),
+                    )))
+                },
+            ),
+        }
+    }
+
+    /// Whether the formula has no temporal operator.
+    pub fn is_propositional(&self) -> bool {
+        match self {
+            LTLFormulae::True | LTLFormulae::Atomic(_) => true,
+            LTLFormulae::Not(f) => f.is_propositional(),
+            LTLFormulae::And(f, g) => f.is_propositional() && g.is_propositional(),
+            _ => false,
+        }
+    }
+
+    /// Replace every past subformula by an atomic proposition, its name in
+    /// brackets, whose value can be kept along the paths with a
+    /// [`History`](crate::model::history::History).
+    ///
+    /// Returns the formula with the names and the past subformulae, each
+    /// after those it refers to. Fails if a past operator has a future
+    /// operator under it.
+    pub fn eliminate_past(&self) -> Result<(LTLFormulae, Vec<(String, LTLFormulae)>), String> {
+        let mut past = Vec::new();
+        let formula = self.eliminate_past_in(&mut past)?;
+        Ok((formula, past))
+    }
+
+    fn eliminate_past_in(
+        &self,
+        past: &mut Vec<(String, LTLFormulae)>,
+    ) -> Result<LTLFormulae, String> {
+        let mut unary = |f: &LTLFormulae| f.eliminate_past_in(past).map(Box::new);
+        let eliminated = match self {
+            LTLFormulae::True | LTLFormulae::Atomic(_) => return Ok(self.clone()),
+            LTLFormulae::Not(f) => return Ok(LTLFormulae::Not(unary(f)?)),
+            LTLFormulae::Next(f) => return Ok(LTLFormulae::Next(unary(f)?)),
+            LTLFormulae::And(f, g) => {
+                let f = unary(f)?;
+                return Ok(LTLFormulae::And(f, unary(g)?));
+            }
+            LTLFormulae::Until(f, g) => {
+                let f = unary(f)?;
+                return Ok(LTLFormulae::Until(f, unary(g)?));
+            }
+            LTLFormulae::BoundedUntil(a, b, f, g) => {
+                let f = unary(f)?;
+                return Ok(LTLFormulae::BoundedUntil(*a, *b, f, unary(g)?));
+            }
+            LTLFormulae::Yesterday(f) => LTLFormulae::Yesterday(unary(f)?),
+            LTLFormulae::Since(f, g) => {
+                let f = unary(f)?;
+                LTLFormulae::Since(f, unary(g)?)
+            }
+        };
+        let operands = match &eliminated {
+            LTLFormulae::Yesterday(f) => f.is_propositional(),
+            LTLFormulae::Since(f, g) => f.is_propositional() && g.is_propositional(),
+            _ => unreachable!(),
+        };
+        if !operands {
+            return Err(format!(
+                "{} has a future operator under a past operator",
+                self
+            ));
+        }
+        // brackets cannot occur in the labels of the model
+        let name = format!("[{}]", self);
+        if !past.iter().any(|(n, _)| *n == name) {
+            past.push((name.clone(), eliminated));
         }
+        Ok(LTLFormulae::Atomic(name))
     }
 }
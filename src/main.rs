@@ -1,3 +1,105 @@
-fn main() {
-    println!("Hello, world!");
+use std::{collections::HashSet, env, fs, process::ExitCode};
+
+use model_checker::{
+    algorithm::equivalence::{check_equivalence, Relation},
+    automata::monitor::{Monitor, Verdict},
+    model::kripke::Kripke,
+    parser::ltl_parser::parse_ltl,
+};
+
+const USAGE: &str = "usage: model-checker equiv [--relation bisimulation|stuttering|simulation] \
+                     [--labels a,b,...] <left.json> <right.json>
+       model-checker monitor <ltl formula> <trace.jsonl>...";
+
+fn main() -> ExitCode {
+    let args = env::args().skip(1).collect::<Vec<String>>();
+    let result = match args.first().map(|arg| arg.as_str()) {
+        Some("equiv") => equiv(&args[1..]),
+        Some("monitor") => monitor(&args[1..]),
+        _ => Err(USAGE.to_string()),
+    };
+    match result {
+        Ok(code) => code,
+        Err(message) => {
+            eprintln!("{}", message);
+            ExitCode::from(2)
+        }
+    }
+}
+
+fn read_kripke(path: &str) -> Result<Kripke, String> {
+    let data = fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+    Kripke::from_json(&data).map_err(|e| format!("{}: {}", path, e))
+}
+
+/// Compare two Kripke structures, failing when they are not related.
+fn equiv(args: &[String]) -> Result<ExitCode, String> {
+    let mut relation = Relation::default();
+    let mut labels = None;
+    let mut files = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--relation" => relation = args.next().ok_or(USAGE)?.parse()?,
+            "--labels" => {
+                let list = args.next().ok_or(USAGE)?;
+                labels = Some(list.split(',').collect::<Vec<&str>>());
+            }
+            _ => files.push(arg.as_str()),
+        }
+    }
+    let [left, right] = files[..] else {
+        return Err(USAGE.to_string());
+    };
+
+    let (left_model, right_model) = (read_kripke(left)?, read_kripke(right)?);
+    match check_equivalence(&left_model, &right_model, relation, labels.as_deref())? {
+        None => {
+            println!("{} and {} are related by {}", left, right, relation);
+            Ok(ExitCode::SUCCESS)
+        }
+        Some(formula) => {
+            println!("{} and {} are not related by {}", left, right, relation);
+            println!("{} holds in {} and not in {}", formula, left, right);
+            Ok(ExitCode::FAILURE)
+        }
+    }
+}
+
+/// Monitor an LTL formula on traces with one event per line, the JSON array
+/// of the atomic propositions holding in it, failing when one violates it.
+fn monitor(args: &[String]) -> Result<ExitCode, String> {
+    let [formula, files @ ..] = args else {
+        return Err(USAGE.to_string());
+    };
+    if files.is_empty() {
+        return Err(USAGE.to_string());
+    }
+    let formula = parse_ltl(formula).map_err(|e| e.to_string())?;
+    let mut monitor = Monitor::new(&formula)?;
+
+    let mut code = ExitCode::SUCCESS;
+    for path in files {
+        let data = fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+        monitor.reset();
+        let mut events = 0;
+        for (number, line) in data.lines().enumerate() {
+            if monitor.verdict() != Verdict::Inconclusive {
+                break;
+            }
+            if line.trim().is_empty() {
+                continue;
+            }
+            let event = serde_json::from_str::<HashSet<String>>(line)
+                .map_err(|e| format!("{}:{}: {}", path, number + 1, e))?;
+            monitor.step(&event);
+            events += 1;
+        }
+        let verdict = monitor.verdict();
+        println!("{}: {} after {} events", path, verdict, events);
+        if verdict == Verdict::False {
+            code = ExitCode::FAILURE;
+        }
+    }
+    Ok(code)
 }
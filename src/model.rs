@@ -1 +1,12 @@
+pub mod concurrent_game;
+pub mod dtmc;
+pub mod guarded;
+pub mod history;
 pub mod kripke;
+pub mod mdp;
+pub mod parity_game;
+pub mod product;
+pub mod symbolic;
+pub mod timed;
+pub mod trace;
+pub mod transition_system;
@@ -0,0 +1,151 @@
+use std::{collections::HashMap, fmt::Display};
+
+use serde::{Deserialize, Serialize};
+
+use crate::model::kripke::Kripke;
+
+/// Concurrent game structure.
+///
+/// In each state, every agent chooses one of its moves at the same time,
+/// and the joint move decides the successor. The Kripke structure has the
+/// transitions of every joint move.
+#[derive(Debug)]
+pub struct ConcurrentGame {
+    pub kripke: Kripke,
+    pub agents: Vec<String>,
+
+    /// The moves of each agent in each state.
+    ///
+    /// key: state, value: the names of the moves of each agent
+    pub moves: HashMap<usize, Vec<Vec<String>>>,
+
+    /// The successor of each joint move, given by the index of the move of
+    /// each agent.
+    ///
+    /// key: state, value: (key: joint move, value: to)
+    pub outcomes: HashMap<usize, HashMap<Vec<usize>, usize>>,
+}
+
+#[derive(Debug)]
+pub enum GameError {
+    Json(serde_json::Error),
+    UnknownState(usize),
+    /// A state has not as many lists of moves, or a transition as many
+    /// moves, as there are agents.
+    Mismatch(usize),
+    /// A transition of a state uses a move its agent does not have.
+    UnknownMove(usize, String),
+    /// A joint move of a state has no successor.
+    MissingOutcome(usize),
+}
+
+impl Display for GameError {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GameError::Json(e) => write!(formatter, "{}", e),
+            GameError::UnknownState(s) => write!(formatter, "unknown state {}", s),
+            GameError::Mismatch(s) => {
+                write!(formatter, "state {} has not one move per agent", s)
+            }
+            GameError::UnknownMove(s, m) => write!(formatter, "unknown move {} in state {}", m, s),
+            GameError::MissingOutcome(s) => {
+                write!(formatter, "a joint move of state {} has no successor", s)
+            }
+        }
+    }
+}
+
+impl std::error::Error for GameError {}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct TransitionInfo {
+    moves: Vec<String>,
+    to: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct StateInfo {
+    id: usize,
+    name: String,
+    labels: Vec<String>,
+    #[serde(default)]
+    moves: Vec<Vec<String>>,
+    #[serde(default)]
+    transitions: Vec<TransitionInfo>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct GameBuilder {
+    agents: Vec<String>,
+    states: Vec<StateInfo>,
+    initial_state: usize,
+}
+
+impl ConcurrentGame {
+    /// Read a game in the JSON format of Kripke structures, with the names
+    /// of the `agents`, and each state having the `moves` of each agent and
+    /// `transitions` from a move of each agent `to` a state. Every joint
+    /// move must have a transition. A state without transitions is
+    /// absorbing, each agent having a single `idle` move.
+    pub fn from_json(data: &str) -> Result<ConcurrentGame, GameError> {
+        let builder = serde_json::from_str::<GameBuilder>(data).map_err(GameError::Json)?;
+        let agents = builder.agents.len();
+        let mut kripke = Kripke::new(builder.initial_state);
+        let mut moves = HashMap::new();
+        let mut outcomes = HashMap::new();
+
+        for state in &builder.states {
+            kripke.add_state(state.id, &state.name, &state.labels);
+        }
+        for state in builder.states {
+            let mut joint = HashMap::new();
+            if state.transitions.is_empty() {
+                kripke.add_transition(state.id, state.id);
+                joint.insert(vec![0; agents], state.id);
+                moves.insert(state.id, vec![vec!["idle".to_string()]; agents]);
+                outcomes.insert(state.id, joint);
+                continue;
+            }
+            if state.moves.len() != agents {
+                return Err(GameError::Mismatch(state.id));
+            }
+            for transition in state.transitions {
+                if transition.moves.len() != agents {
+                    return Err(GameError::Mismatch(state.id));
+                }
+                if !kripke.states.contains_key(&transition.to) {
+                    return Err(GameError::UnknownState(transition.to));
+                }
+                let indices = transition
+                    .moves
+                    .iter()
+                    .zip(&state.moves)
+                    .map(|(name, choices)| {
+                        choices
+                            .iter()
+                            .position(|choice| choice == name)
+                            .ok_or_else(|| GameError::UnknownMove(state.id, name.clone()))
+                    })
+                    .collect::<Result<Vec<usize>, GameError>>()?;
+                kripke.add_transition(state.id, transition.to);
+                joint.insert(indices, transition.to);
+            }
+            let combinations = state.moves.iter().map(|m| m.len()).product::<usize>();
+            if joint.len() != combinations {
+                return Err(GameError::MissingOutcome(state.id));
+            }
+            moves.insert(state.id, state.moves);
+            outcomes.insert(state.id, joint);
+        }
+        if !kripke.states.contains_key(&kripke.initial_state) {
+            return Err(GameError::UnknownState(kripke.initial_state));
+        }
+
+        Ok(ConcurrentGame {
+            kripke,
+            agents: builder.agents,
+            moves,
+            outcomes,
+        })
+    }
+}
@@ -0,0 +1,214 @@
+use std::{collections::HashMap, fmt::Display};
+
+use serde::{Deserialize, Serialize};
+
+use crate::model::kripke::Kripke;
+
+/// Discrete-time Markov chain.
+///
+/// A Kripke structure whose transitions have probabilities, those leaving a
+/// state summing to 1.
+#[derive(Debug)]
+pub struct Dtmc {
+    pub kripke: Kripke,
+
+    /// The probabilities of the transitions.
+    ///
+    /// key: from, value: (key: to, value: probability)
+    pub probabilities: HashMap<usize, HashMap<usize, f64>>,
+
+    /// The reward earned at each step in a state, 0 when missing.
+    pub state_rewards: HashMap<usize, f64>,
+
+    /// The reward earned taking a transition, 0 when missing.
+    ///
+    /// key: from, value: (key: to, value: reward)
+    pub transition_rewards: HashMap<usize, HashMap<usize, f64>>,
+}
+
+/// An error reading a Markov chain or decision process.
+#[derive(Debug)]
+pub enum MarkovError {
+    Json(serde_json::Error),
+    /// A state has not as many probabilities as transitions.
+    Mismatch(usize),
+    UnknownState(usize),
+    /// The probabilities leaving a state do not sum to 1.
+    Distribution(usize, f64),
+    /// A state has a negative reward, or a transition leaving it.
+    NegativeReward(usize),
+    /// A scheduler chooses no action in the state.
+    Unscheduled(usize),
+    /// A scheduler chooses an action the state does not have.
+    UnknownAction(usize, String),
+}
+
+impl Display for MarkovError {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MarkovError::Json(e) => write!(formatter, "{}", e),
+            MarkovError::Mismatch(s) => {
+                write!(
+                    formatter,
+                    "state {} has not one probability per transition",
+                    s
+                )
+            }
+            MarkovError::UnknownState(s) => write!(formatter, "unknown state {}", s),
+            MarkovError::Distribution(s, sum) => {
+                write!(formatter, "the probabilities of state {} sum to {}", s, sum)
+            }
+            MarkovError::NegativeReward(s) => {
+                write!(formatter, "state {} has a negative reward", s)
+            }
+            MarkovError::Unscheduled(s) => write!(formatter, "no action is chosen in state {}", s),
+            MarkovError::UnknownAction(s, action) => {
+                write!(formatter, "state {} has no action {}", s, action)
+            }
+        }
+    }
+}
+
+impl std::error::Error for MarkovError {}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct StateInfo {
+    id: usize,
+    name: String,
+    labels: Vec<String>,
+    transit_to: Vec<usize>,
+    #[serde(default)]
+    probabilities: Vec<f64>,
+    #[serde(default)]
+    reward: f64,
+    #[serde(default)]
+    rewards: Vec<f64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct DtmcBuilder {
+    states: Vec<StateInfo>,
+    initial_state: usize,
+}
+
+/// The probabilities and the rewards of the transitions leaving a state, by
+/// target.
+pub(crate) type Transitions = (HashMap<usize, f64>, HashMap<usize, f64>);
+
+/// Read the distribution of the transitions from `from` to `transit_to`,
+/// adding them to `kripke`, with their `rewards` if any.
+///
+/// Returns the distribution and the nonzero rewards of the transitions.
+pub(crate) fn read_distribution(
+    kripke: &mut Kripke,
+    from: usize,
+    transit_to: Vec<usize>,
+    probabilities: Vec<f64>,
+    rewards: Vec<f64>,
+) -> Result<Transitions, MarkovError> {
+    if transit_to.len() != probabilities.len()
+        || !(rewards.is_empty() || rewards.len() == transit_to.len())
+    {
+        return Err(MarkovError::Mismatch(from));
+    }
+    if rewards.iter().any(|r| *r < 0.0) {
+        return Err(MarkovError::NegativeReward(from));
+    }
+    let mut distribution = HashMap::<usize, f64>::new();
+    // the expected reward of each target, which may appear several times
+    let mut earned = HashMap::<usize, f64>::new();
+    for (i, (to, probability)) in transit_to.into_iter().zip(probabilities).enumerate() {
+        if !kripke.states.contains_key(&to) {
+            return Err(MarkovError::UnknownState(to));
+        }
+        if probability != 0.0 {
+            kripke.add_transition(from, to);
+            *distribution.entry(to).or_default() += probability;
+            if let Some(reward) = rewards.get(i).filter(|r| **r != 0.0) {
+                *earned.entry(to).or_default() += probability * reward;
+            }
+        }
+    }
+    let sum = distribution.values().sum::<f64>();
+    if (sum - 1.0).abs() > 1e-9 || distribution.values().any(|p| *p <= 0.0) {
+        return Err(MarkovError::Distribution(from, sum));
+    }
+    for (to, reward) in earned.iter_mut() {
+        *reward /= distribution[to];
+    }
+    Ok((distribution, earned))
+}
+
+impl Dtmc {
+    /// Read a chain in the JSON format of Kripke structures, each state
+    /// having the `probabilities` of its transitions in the same order as
+    /// `transit_to`. A state without transitions is absorbing.
+    ///
+    /// A state may have a `reward`, earned at each step spent in it, and
+    /// `rewards` for its transitions, in the same order as `transit_to`.
+    /// Rewards cannot be negative.
+    pub fn from_json(data: &str) -> Result<Dtmc, MarkovError> {
+        let builder = serde_json::from_str::<DtmcBuilder>(data).map_err(MarkovError::Json)?;
+        let mut kripke = Kripke::new(builder.initial_state);
+        let mut probabilities = HashMap::new();
+        let mut state_rewards = HashMap::new();
+        let mut transition_rewards = HashMap::new();
+
+        for state in &builder.states {
+            kripke.add_state(state.id, &state.name, &state.labels);
+        }
+        for state in builder.states {
+            if state.reward < 0.0 {
+                return Err(MarkovError::NegativeReward(state.id));
+            }
+            if state.reward != 0.0 {
+                state_rewards.insert(state.id, state.reward);
+            }
+            if state.transit_to.is_empty() {
+                kripke.add_transition(state.id, state.id);
+                probabilities.insert(state.id, HashMap::from([(state.id, 1.0)]));
+                continue;
+            }
+            let (distribution, rewards) = read_distribution(
+                &mut kripke,
+                state.id,
+                state.transit_to,
+                state.probabilities,
+                state.rewards,
+            )?;
+            probabilities.insert(state.id, distribution);
+            if !rewards.is_empty() {
+                transition_rewards.insert(state.id, rewards);
+            }
+        }
+        if !kripke.states.contains_key(&kripke.initial_state) {
+            return Err(MarkovError::UnknownState(kripke.initial_state));
+        }
+
+        Ok(Dtmc {
+            kripke,
+            probabilities,
+            state_rewards,
+            transition_rewards,
+        })
+    }
+
+    pub fn probability(&self, from: usize, to: usize) -> f64 {
+        self.probabilities
+            .get(&from)
+            .and_then(|distribution| distribution.get(&to))
+            .cloned()
+            .unwrap_or(0.0)
+    }
+
+    /// The expected reward of a step from `state`.
+    pub fn reward(&self, state: usize) -> f64 {
+        let transitions = self.transition_rewards.get(&state).map_or(0.0, |rewards| {
+            rewards
+                .iter()
+                .map(|(to, reward)| self.probability(state, *to) * reward)
+                .sum()
+        });
+        self.state_rewards.get(&state).cloned().unwrap_or(0.0) + transitions
+    }
+}
@@ -0,0 +1,613 @@
+use std::{
+    collections::{BTreeMap, BTreeSet, HashSet},
+    fmt::Display,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    expr::{EvalError, Expr},
+    model::transition_system::TransitionSystem,
+    parser::expr_parser::{parse_expr, Rule},
+};
+
+/// An integer variable ranging over `min..=max`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Variable {
+    pub name: String,
+    pub min: i64,
+    pub max: i64,
+    pub init: i64,
+}
+
+/// A guarded command `guard -> v1 := e1, ..., vn := en` of a process.
+///
+/// The updates are simultaneous, all right-hand sides are evaluated in the
+/// state before the command.
+#[derive(Debug, Clone)]
+pub struct Command {
+    pub name: String,
+    /// The index of the process the command belongs to.
+    pub process: usize,
+    pub guard: Expr,
+    pub updates: Vec<(usize, Expr)>,
+}
+
+/// A program made of processes of guarded commands over shared variables.
+///
+/// At each step one enabled command of one process is executed, so the
+/// program is the asynchronous product of its processes. A state where no
+/// command is enabled loops on itself.
+#[derive(Debug, Clone)]
+pub struct GuardedProgram {
+    pub variables: Vec<Variable>,
+    /// The names of the processes.
+    pub processes: Vec<String>,
+    pub commands: Vec<Command>,
+    /// The atomic propositions, defined as expressions over the variables.
+    pub labels: Vec<(String, Expr)>,
+    /// The groups of identical processes, from the templates.
+    pub symmetries: Vec<Symmetry>,
+}
+
+/// The instances of a process template, which are interchangeable.
+///
+/// Instance `i` of template `t` is the process `t_i`, and its copy of local
+/// variable or label `x` is named `x_i`. Scalar variables hold instance
+/// numbers, so they are permuted along with the instances.
+#[derive(Debug, Clone)]
+pub struct Symmetry {
+    pub template: String,
+    /// The local variables of each instance.
+    pub locals: Vec<Vec<usize>>,
+    /// The global variables whose values in `0..count` are instance numbers.
+    pub scalars: Vec<usize>,
+    /// The names of the labels of each instance, by template label.
+    pub labels: Vec<Vec<String>>,
+}
+
+#[derive(Debug)]
+pub enum ProgramError {
+    Json(serde_json::Error),
+    Expr(String, Box<pest::error::Error<Rule>>),
+    DuplicateVariable(String),
+    UnknownVariable(String),
+    InvalidDomain(String),
+    /// A process outside of a template accesses the local or scalar variable.
+    Asymmetric(String),
+    /// Executing the command failed in the state, both given by name.
+    Execution {
+        command: String,
+        state: String,
+        reason: String,
+    },
+    /// Evaluating the label failed in the state.
+    Label {
+        label: String,
+        state: String,
+        error: EvalError,
+    },
+}
+
+impl Display for ProgramError {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ProgramError::Json(e) => write!(formatter, "{}", e),
+            ProgramError::Expr(input, e) => write!(formatter, "in `{}`: {}", input, e),
+            ProgramError::DuplicateVariable(v) => write!(formatter, "duplicate variable {}", v),
+            ProgramError::UnknownVariable(v) => write!(formatter, "unknown variable {}", v),
+            ProgramError::InvalidDomain(v) => {
+                write!(formatter, "invalid domain or initial value of {}", v)
+            }
+            ProgramError::Asymmetric(v) => {
+                write!(formatter, "{} is accessed outside of its template", v)
+            }
+            ProgramError::Execution {
+                command,
+                state,
+                reason,
+            } => write!(
+                formatter,
+                "command {} in state {}: {}",
+                command, state, reason
+            ),
+            ProgramError::Label {
+                label,
+                state,
+                error,
+            } => write!(formatter, "label {} in state {}: {}", label, state, error),
+        }
+    }
+}
+
+impl std::error::Error for ProgramError {}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct VariableInfo {
+    name: String,
+    min: i64,
+    max: i64,
+    init: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CommandInfo {
+    #[serde(default)]
+    name: String,
+    guard: String,
+    #[serde(default)]
+    updates: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ProcessInfo {
+    name: String,
+    commands: Vec<CommandInfo>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct TemplateInfo {
+    name: String,
+    count: usize,
+    #[serde(default)]
+    variables: Vec<VariableInfo>,
+    #[serde(default)]
+    scalars: Vec<String>,
+    commands: Vec<CommandInfo>,
+    #[serde(default)]
+    labels: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ProgramBuilder {
+    variables: Vec<VariableInfo>,
+    #[serde(default)]
+    processes: Vec<ProcessInfo>,
+    #[serde(default)]
+    templates: Vec<TemplateInfo>,
+    #[serde(default)]
+    labels: BTreeMap<String, String>,
+}
+
+impl GuardedProgram {
+    pub fn from_json(data: &str) -> Result<GuardedProgram, ProgramError> {
+        let builder = serde_json::from_str::<ProgramBuilder>(data).map_err(ProgramError::Json)?;
+
+        let mut program = GuardedProgram {
+            variables: Vec::new(),
+            processes: Vec::new(),
+            commands: Vec::new(),
+            labels: Vec::new(),
+            symmetries: Vec::new(),
+        };
+
+        for v in builder.variables {
+            program.add_variable(v)?;
+        }
+        for template in &builder.templates {
+            let mut locals = vec![Vec::new(); template.count];
+            for (i, instance) in locals.iter_mut().enumerate() {
+                for v in &template.variables {
+                    instance.push(program.variables.len());
+                    program.add_variable(VariableInfo {
+                        name: format!("{}_{}", v.name, i),
+                        ..v.clone()
+                    })?;
+                }
+            }
+            let mut scalars = Vec::new();
+            for scalar in &template.scalars {
+                scalars.push(
+                    program
+                        .variable(scalar)
+                        .ok_or_else(|| ProgramError::UnknownVariable(scalar.clone()))?,
+                );
+            }
+            program.symmetries.push(Symmetry {
+                template: template.name.clone(),
+                locals,
+                scalars,
+                labels: Vec::new(),
+            });
+        }
+
+        for info in builder.processes {
+            let process = program.processes.len();
+            program.processes.push(info.name);
+            for (i, command) in info.commands.into_iter().enumerate() {
+                program.add_command(process, i, command)?;
+            }
+        }
+        // processes outside of templates must not break the symmetry
+        for symmetry in &program.symmetries {
+            let private = symmetry.locals.iter().flatten().chain(&symmetry.scalars);
+            for var in private {
+                if (0..program.commands.len())
+                    .any(|c| program.reads(c).contains(var) || program.writes(c).contains(var))
+                {
+                    return Err(ProgramError::Asymmetric(
+                        program.variables[*var].name.clone(),
+                    ));
+                }
+            }
+        }
+
+        for (t, template) in builder.templates.into_iter().enumerate() {
+            let locals = template
+                .variables
+                .iter()
+                .map(|v| v.name.clone())
+                .collect::<Vec<String>>();
+            let mut labels = vec![Vec::new(); template.labels.len()];
+            for i in 0..template.count {
+                let process = program.processes.len();
+                program.processes.push(format!("{}_{}", template.name, i));
+                for (j, command) in template.commands.iter().enumerate() {
+                    let command = CommandInfo {
+                        name: if command.name.is_empty() {
+                            String::new()
+                        } else {
+                            format!("{}_{}.{}", template.name, i, command.name)
+                        },
+                        guard: instantiate(&command.guard, &locals, i),
+                        updates: command
+                            .updates
+                            .iter()
+                            .map(|(var, value)| {
+                                (instantiate(var, &locals, i), instantiate(value, &locals, i))
+                            })
+                            .collect(),
+                    };
+                    program.add_command(process, j, command)?;
+                }
+                for (l, (label, expr)) in template.labels.iter().enumerate() {
+                    let name = format!("{}_{}", label, i);
+                    let expr = program.parse(&instantiate(expr, &locals, i))?;
+                    program.labels.push((name.clone(), expr));
+                    labels[l].push(name);
+                }
+            }
+            program.symmetries[t].labels = labels;
+        }
+
+        for (name, expr) in builder.labels {
+            let expr = program.parse(&expr)?;
+            program.labels.push((name, expr));
+        }
+
+        Ok(program)
+    }
+
+    fn add_variable(&mut self, v: VariableInfo) -> Result<(), ProgramError> {
+        if self.variable(&v.name).is_some() {
+            return Err(ProgramError::DuplicateVariable(v.name));
+        }
+        if v.min > v.max || v.init < v.min || v.init > v.max {
+            return Err(ProgramError::InvalidDomain(v.name));
+        }
+        self.variables.push(Variable {
+            name: v.name,
+            min: v.min,
+            max: v.max,
+            init: v.init,
+        });
+        Ok(())
+    }
+
+    fn add_command(
+        &mut self,
+        process: usize,
+        index: usize,
+        command: CommandInfo,
+    ) -> Result<(), ProgramError> {
+        let guard = self.parse(&command.guard)?;
+        let mut updates = Vec::new();
+        for (var, value) in &command.updates {
+            let var = self
+                .variable(var)
+                .ok_or_else(|| ProgramError::UnknownVariable(var.clone()))?;
+            updates.push((var, self.parse(value)?));
+        }
+        let name = if command.name.is_empty() {
+            format!("{}.{}", self.processes[process], index)
+        } else {
+            command.name
+        };
+        self.commands.push(Command {
+            name,
+            process,
+            guard,
+            updates,
+        });
+        Ok(())
+    }
+
+    /// Parse an expression over the variables of the program.
+    pub fn parse(&self, input: &str) -> Result<Expr, ProgramError> {
+        parse_expr(input, |name| self.variable(name))
+            .map_err(|e| ProgramError::Expr(input.to_string(), e))
+    }
+
+    pub fn variable(&self, name: &str) -> Option<usize> {
+        self.variables.iter().position(|v| v.name == name)
+    }
+
+    pub fn label(&self, name: &str) -> Option<&Expr> {
+        self.labels
+            .iter()
+            .find_map(|(label, expr)| if label == name { Some(expr) } else { None })
+    }
+
+    pub fn initial_state(&self) -> Vec<i64> {
+        self.variables.iter().map(|v| v.init).collect()
+    }
+
+    /// The indices of the commands enabled in `state`.
+    pub fn enabled(&self, state: &[i64]) -> Result<Vec<usize>, ProgramError> {
+        let mut enabled = Vec::new();
+        for (c, command) in self.commands.iter().enumerate() {
+            let holds = command
+                .guard
+                .holds(state)
+                .map_err(|e| self.execution_error(c, state, e))?;
+            if holds {
+                enabled.push(c);
+            }
+        }
+        Ok(enabled)
+    }
+
+    /// Execute `command` from `state`.
+    ///
+    /// Fails if an update cannot be evaluated or assigns a value outside of
+    /// the domain of its variable.
+    pub fn apply(&self, command: usize, state: &[i64]) -> Result<Vec<i64>, ProgramError> {
+        let mut next = state.to_vec();
+        for (var, value) in &self.commands[command].updates {
+            let value = value
+                .eval(state)
+                .map_err(|e| self.execution_error(command, state, e))?;
+            let v = &self.variables[*var];
+            if value < v.min || value > v.max {
+                let reason = format!(
+                    "assigns {} to {}, outside of {}..={}",
+                    value, v.name, v.min, v.max
+                );
+                return Err(self.execution_error(command, state, reason));
+            }
+            next[*var] = value;
+        }
+        Ok(next)
+    }
+
+    fn execution_error(&self, command: usize, state: &[i64], reason: impl Display) -> ProgramError {
+        ProgramError::Execution {
+            command: self.commands[command].name.clone(),
+            state: self.state_name(&state.to_vec()),
+            reason: reason.to_string(),
+        }
+    }
+
+    /// The successors of `state`, which loops on itself when no command is
+    /// enabled.
+    pub fn next_states(&self, state: &[i64]) -> Result<Vec<Vec<i64>>, ProgramError> {
+        let mut next = self
+            .enabled(state)?
+            .into_iter()
+            .map(|c| self.apply(c, state))
+            .collect::<Result<Vec<Vec<i64>>, ProgramError>>()?;
+        if next.is_empty() {
+            next.push(state.to_vec());
+        }
+        next.sort();
+        next.dedup();
+        Ok(next)
+    }
+
+    /// The names of the labels holding in `state`.
+    pub fn label_names(&self, state: &[i64]) -> Result<HashSet<String>, ProgramError> {
+        let mut names = HashSet::new();
+        for (name, expr) in &self.labels {
+            if self.label_holds(name, expr, state)? {
+                names.insert(name.clone());
+            }
+        }
+        Ok(names)
+    }
+
+    fn label_holds(&self, name: &str, expr: &Expr, state: &[i64]) -> Result<bool, ProgramError> {
+        expr.holds(state).map_err(|error| ProgramError::Label {
+            label: name.to_string(),
+            state: self.state_name(&state.to_vec()),
+            error,
+        })
+    }
+
+    /// Explore the reachable states, failing on the first command or label
+    /// which cannot be evaluated, on which the [`TransitionSystem`]
+    /// implementation would panic.
+    pub fn check_execution(&self) -> Result<(), ProgramError> {
+        let mut visited = HashSet::from([self.initial_state()]);
+        let mut stack = vec![self.initial_state()];
+        while let Some(state) = stack.pop() {
+            self.label_names(&state)?;
+            for next in self.next_states(&state)? {
+                if visited.insert(next.clone()) {
+                    stack.push(next);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// The variables read by the guard and the updates of `command`.
+    pub fn reads(&self, command: usize) -> BTreeSet<usize> {
+        let command = &self.commands[command];
+        let mut reads = command.guard.variables();
+        for (_, value) in &command.updates {
+            reads.extend(value.variables());
+        }
+        reads
+    }
+
+    /// The variables assigned by `command`.
+    pub fn writes(&self, command: usize) -> BTreeSet<usize> {
+        self.commands[command]
+            .updates
+            .iter()
+            .map(|(var, _)| *var)
+            .collect()
+    }
+}
+
+/// Rename the local variables of a template in `input` to those of instance
+/// `i`, and replace `self` with `i`.
+fn instantiate(input: &str, locals: &[String], i: usize) -> String {
+    let mut result = String::new();
+    let mut word = String::new();
+    let rename = |word: &str| {
+        if word == "self" {
+            i.to_string()
+        } else if locals.iter().any(|l| l == word) {
+            format!("{}_{}", word, i)
+        } else {
+            word.to_string()
+        }
+    };
+    for c in input.chars() {
+        if c.is_ascii_alphanumeric() || c == '_' || c == '.' {
+            word.push(c);
+        } else {
+            result.push_str(&rename(&word));
+            word.clear();
+            result.push(c);
+        }
+    }
+    result.push_str(&rename(&word));
+    result
+}
+
+impl TransitionSystem for GuardedProgram {
+    type State = Vec<i64>;
+
+    fn initial_states(&self) -> Vec<Vec<i64>> {
+        vec![self.initial_state()]
+    }
+
+    /// Panics with the error of [`GuardedProgram::next_states`], see
+    /// [`GuardedProgram::check_execution`] to detect it beforehand.
+    fn successors(&self, state: &Vec<i64>) -> Vec<Vec<i64>> {
+        self.next_states(state).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Panics with the error of [`GuardedProgram::label_names`].
+    fn labels(&self, state: &Vec<i64>) -> HashSet<String> {
+        self.label_names(state).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    fn has_label(&self, state: &Vec<i64>, label: &str) -> bool {
+        self.label(label).is_some_and(|expr| {
+            self.label_holds(label, expr, state)
+                .unwrap_or_else(|e| panic!("{}", e))
+        })
+    }
+
+    fn state_name(&self, state: &Vec<i64>) -> String {
+        self.variables
+            .iter()
+            .zip(state)
+            .map(|(v, value)| format!("{}={}", v.name, value))
+            .collect::<Vec<String>>()
+            .join(", ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Two processes incrementing a shared counter under a lock.
+    const COUNTER: &str = r#"
+    {
+        "variables": [
+            { "name": "lock", "min": 0, "max": 2, "init": 0 },
+            { "name": "n", "min": 0, "max": 2, "init": 0 }
+        ],
+        "processes": [
+            {
+                "name": "p",
+                "commands": [
+                    { "name": "p.take", "guard": "lock == 0", "updates": { "lock": "1" } },
+                    { "name": "p.inc", "guard": "lock == 1", "updates": { "n": "(n + 1) % 3", "lock": "0" } }
+                ]
+            },
+            {
+                "name": "q",
+                "commands": [
+                    { "guard": "lock == 0", "updates": { "lock": "2" } },
+                    { "guard": "lock == 2", "updates": { "n": "(n + 1) % 3", "lock": "0" } }
+                ]
+            }
+        ],
+        "labels": { "zero": "n == 0", "busy": "lock != 0" }
+    }"#;
+
+    #[test]
+    fn test_program() {
+        let program = GuardedProgram::from_json(COUNTER).unwrap();
+        assert_eq!(program.processes, vec!["p", "q"]);
+        assert_eq!(program.commands[2].name, "q.0");
+        assert_eq!(program.enabled(&[0, 0]).unwrap(), vec![0, 2]);
+        assert_eq!(program.apply(1, &[1, 2]).unwrap(), vec![0, 0]);
+        assert_eq!(program.reads(1), BTreeSet::from([0, 1]));
+        assert_eq!(program.writes(1), BTreeSet::from([0, 1]));
+        assert!(program.has_label(&vec![0, 0], "zero"));
+        assert!(!program.has_label(&vec![0, 0], "busy"));
+
+        let k = program.to_kripke();
+        assert_eq!(k.states.len(), 9);
+        assert_eq!(k.states[&0], "lock=0, n=0");
+    }
+
+    #[test]
+    fn test_program_errors() {
+        let data = COUNTER.replace("\"n == 0\"", "\"m == 0\"");
+        assert!(matches!(
+            GuardedProgram::from_json(&data),
+            Err(ProgramError::Expr(_, _))
+        ));
+        let data = COUNTER.replace("\"init\": 0 }\n        ],", "\"init\": 3 }\n        ],");
+        assert!(matches!(
+            GuardedProgram::from_json(&data),
+            Err(ProgramError::InvalidDomain(_))
+        ));
+    }
+
+    #[test]
+    fn test_execution_errors() {
+        let program = GuardedProgram::from_json(COUNTER).unwrap();
+        assert!(program.check_execution().is_ok());
+        let e = program.parse("n * 9223372036854775807 > 0").unwrap();
+        assert_eq!(e.holds(&[0, 2]), Err(EvalError::Overflow));
+        let e = program.parse("n != 0 && 1 / n == 1").unwrap();
+        assert_eq!(e.holds(&[0, 0]), Ok(false));
+
+        let data = COUNTER.replacen("(n + 1) % 3", "n + 1", 1);
+        let program = GuardedProgram::from_json(&data).unwrap();
+        assert_eq!(
+            program.apply(1, &[1, 2]).unwrap_err().to_string(),
+            "command p.inc in state lock=1, n=2: assigns 3 to n, outside of 0..=2"
+        );
+        assert!(matches!(
+            program.check_execution(),
+            Err(ProgramError::Execution { .. })
+        ));
+
+        let data = COUNTER.replace("\"n == 0\"", "\"1 / n == 1\"");
+        let program = GuardedProgram::from_json(&data).unwrap();
+        assert_eq!(
+            program.label_names(&[0, 0]).unwrap_err().to_string(),
+            "label zero in state lock=0, n=0: division by zero"
+        );
+    }
+}
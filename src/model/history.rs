@@ -0,0 +1,99 @@
+use std::collections::HashSet;
+
+use crate::{ltl::LTLFormulae, model::transition_system::TransitionSystem};
+
+/// A transition system keeping the values of past formulae along the path
+/// leading to each state, as extra labels.
+///
+/// The past formulae are those of [`LTLFormulae::eliminate_past`], each
+/// depending on the current labels and on the labels of the previous state.
+pub struct History<'a, T> {
+    pub system: &'a T,
+    /// The names of the past formulae, with the formulae.
+    pub past: Vec<(String, LTLFormulae)>,
+}
+
+/// Whether the propositional formula `f` holds with `labels`.
+fn holds(f: &LTLFormulae, labels: &HashSet<String>) -> bool {
+    match f {
+        LTLFormulae::True => true,
+        LTLFormulae::Atomic(a) => labels.contains(a),
+        LTLFormulae::Not(f) => !holds(f, labels),
+        LTLFormulae::And(f, g) => holds(f, labels) && holds(g, labels),
+        f => unreachable!("{} is not propositional", f),
+    }
+}
+
+/// Add to `labels` the names of the `past` formulae holding in a position
+/// with these labels, after a position with the labels `previous`, which
+/// include those of the past formulae. Returns the value of each formula.
+pub(crate) fn add_past_labels(
+    past: &[(String, LTLFormulae)],
+    previous: Option<&HashSet<String>>,
+    labels: &mut HashSet<String>,
+) -> Vec<bool> {
+    let mut values = Vec::new();
+    for (name, f) in past {
+        let value = match f {
+            LTLFormulae::Yesterday(f) => previous.is_some_and(|p| holds(f, p)),
+            LTLFormulae::Since(f, g) => {
+                holds(g, labels) || (holds(f, labels) && previous.is_some_and(|p| p.contains(name)))
+            }
+            f => unreachable!("{} is not a past formula", f),
+        };
+        if value {
+            labels.insert(name.clone());
+        }
+        values.push(value);
+    }
+    values
+}
+
+impl<T: TransitionSystem> History<'_, T> {
+    /// The values of the past formulae in `state`, after a state with the
+    /// labels `previous`.
+    fn values(&self, previous: Option<&HashSet<String>>, state: &T::State) -> Vec<bool> {
+        add_past_labels(&self.past, previous, &mut self.system.labels(state))
+    }
+}
+
+impl<T: TransitionSystem> TransitionSystem for History<'_, T> {
+    type State = (T::State, Vec<bool>);
+
+    fn initial_states(&self) -> Vec<Self::State> {
+        self.system
+            .initial_states()
+            .into_iter()
+            .map(|s| {
+                let values = self.values(None, &s);
+                (s, values)
+            })
+            .collect()
+    }
+
+    fn successors(&self, state: &Self::State) -> Vec<Self::State> {
+        let labels = self.labels(state);
+        self.system
+            .successors(&state.0)
+            .into_iter()
+            .map(|t| {
+                let values = self.values(Some(&labels), &t);
+                (t, values)
+            })
+            .collect()
+    }
+
+    fn labels(&self, state: &Self::State) -> HashSet<String> {
+        let mut labels = self.system.labels(&state.0);
+        for ((name, _), value) in self.past.iter().zip(&state.1) {
+            if *value {
+                labels.insert(name.clone());
+            }
+        }
+        labels
+    }
+
+    fn state_name(&self, state: &Self::State) -> String {
+        self.system.state_name(&state.0)
+    }
+}
@@ -3,6 +3,8 @@ use std::collections::{HashMap, HashSet};
 use petgraph::graph::{DiGraph, NodeIndex};
 use serde::{Deserialize, Serialize};
 
+use crate::model::transition_system::TransitionSystem;
+
 /// Kripke structure.
 #[derive(Debug, Serialize)]
 pub struct Kripke {
@@ -50,59 +52,63 @@ struct KripkeBuilder {
 }
 
 impl Kripke {
+    /// Create an empty Kripke structure with the given initial state.
+    pub fn new(initial_state: usize) -> Kripke {
+        Kripke {
+            states: HashMap::new(),
+            transitions: HashMap::new(),
+            labels: HashMap::new(),
+            label_to_states: HashMap::new(),
+            state_to_labels: HashMap::new(),
+            initial_state,
+        }
+    }
+
     pub fn from_json(data: &str) -> Result<Kripke, serde_json::Error> {
-        let builder = serde_json::from_str::<KripkeBuilder>(data);
-        if let Ok(k) = builder {
-            let init = k.initial_state;
-            let mut states = HashMap::new();
-            let mut transitions = HashMap::new();
-            let mut labels = HashMap::new();
-            let mut label_to_states: HashMap<usize, HashSet<usize>> = HashMap::new();
-            let mut state_to_labels: HashMap<usize, HashSet<usize>> = HashMap::new();
-            let mut temp_labels: HashMap<String, usize> = HashMap::new();
-
-            for state in k.states {
-                states.insert(state.id, state.name);
-                state_to_labels.insert(state.id, HashSet::new());
-                transitions.insert(state.id, state.transit_to.into_iter().collect());
-                for label in state.labels {
-                    if let Some(id) = temp_labels.get(&label) {
-                        label_to_states.get_mut(id).unwrap().insert(state.id);
-                        state_to_labels.get_mut(&state.id).unwrap().insert(*id);
-                    } else {
-                        let id = labels.len();
-                        temp_labels.insert(label.clone(), id);
-                        labels.insert(id, label);
-                        label_to_states.insert(id, {
-                            let mut set = HashSet::new();
-                            set.insert(state.id);
-                            set
-                        });
-                        state_to_labels.insert(state.id, {
-                            let mut set = HashSet::new();
-                            set.insert(id);
-                            set
-                        });
-                    }
-                }
+        let k = serde_json::from_str::<KripkeBuilder>(data)?;
+        let mut kripke = Kripke::new(k.initial_state);
+
+        for state in k.states {
+            kripke.add_state(state.id, &state.name, &state.labels);
+            for to in state.transit_to {
+                kripke.add_transition(state.id, to);
             }
+        }
 
-            // TODO: check if there is non-exist state in transitions
-            // TODO: check if there is orphan state in transitions
+        // TODO: check if there is non-exist state in transitions
+        // TODO: check if there is orphan state in transitions
 
-            Ok(Kripke {
-                states,
-                transitions,
-                labels,
-                label_to_states,
-                state_to_labels,
-                initial_state: init,
-            })
-        } else {
-            Err(builder.unwrap_err())
+        Ok(kripke)
+    }
+
+    /// Add a state with its labels, replacing any state with the same id.
+    pub fn add_state<S: AsRef<str>>(&mut self, id: usize, name: &str, labels: &[S]) {
+        self.states.insert(id, name.to_string());
+        self.transitions.entry(id).or_default();
+        self.state_to_labels.entry(id).or_default();
+        for label in labels {
+            let label = self.get_label_id_or_add(label.as_ref());
+            self.add_state_for_label(label, id);
         }
     }
 
+    pub fn add_transition(&mut self, from: usize, to: usize) {
+        self.transitions.entry(from).or_default().insert(to);
+    }
+
+    /// The names of the labels of `state`.
+    pub fn label_names_of(&self, state: usize) -> HashSet<&str> {
+        self.state_to_labels
+            .get(&state)
+            .map(|labels| {
+                labels
+                    .iter()
+                    .map(|label| self.labels[label].as_str())
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
     pub fn contains_label(&self, label: &str) -> Option<usize> {
         self.labels
             .iter()
@@ -122,7 +128,7 @@ impl Kripke {
     pub fn get_state_with_label(&self, label_index: usize) -> Vec<usize> {
         if label_index == usize::MAX {
             // true
-            return self.states.keys().cloned().collect();
+            self.states.keys().cloned().collect()
         } else {
             self.label_to_states
                 .get(&label_index)
@@ -136,7 +142,7 @@ impl Kripke {
     pub fn get_state_with_label_as_set(&self, label_index: usize) -> HashSet<usize> {
         if label_index == usize::MAX {
             // true
-            return self.states.keys().cloned().collect();
+            self.states.keys().cloned().collect()
         } else {
             self.label_to_states
                 .get(&label_index)
@@ -208,17 +214,16 @@ impl Kripke {
     }
 
     pub fn non_trivial_scc_of(&self, states: &[usize]) -> Vec<Vec<usize>> {
-        // retain transitions that are in states
-        let transitions = self
+        // retain transitions within states
+        let transitions_vec = self
             .transitions
             .iter()
-            .filter(|(from, to)| states.contains(from) && to.iter().all(|t| states.contains(t)))
-            .map(|(from, to)| (*from, to.clone()))
-            .collect::<HashMap<usize, HashSet<usize>>>();
-
-        let transitions_vec = transitions
-            .iter()
-            .flat_map(|(from, to)| to.iter().map(move |t| (*from, *t)))
+            .filter(|(from, _)| states.contains(from))
+            .flat_map(|(from, to)| {
+                to.iter()
+                    .filter(|t| states.contains(t))
+                    .map(move |t| (*from, *t))
+            })
             .collect::<Vec<(usize, usize)>>();
 
         let g = construct_graph(states, &transitions_vec);
@@ -226,7 +231,7 @@ impl Kripke {
         let sccs = petgraph::algo::tarjan_scc(&g);
 
         sccs.into_iter()
-            .filter(|scc| scc.len() > 1)
+            .filter(|scc| scc.len() > 1 || g.contains_edge(scc[0], scc[0]))
             .map(|scc| {
                 scc.into_iter()
                     .map(|i| *g.node_weight(i).unwrap())
@@ -236,6 +241,42 @@ impl Kripke {
     }
 }
 
+impl TransitionSystem for Kripke {
+    type State = usize;
+
+    fn initial_states(&self) -> Vec<usize> {
+        vec![self.initial_state]
+    }
+
+    fn successors(&self, state: &usize) -> Vec<usize> {
+        let mut next = self
+            .transitions
+            .get(state)
+            .map(|to| to.iter().cloned().collect::<Vec<usize>>())
+            .unwrap_or_default();
+        next.sort();
+        next
+    }
+
+    fn labels(&self, state: &usize) -> HashSet<String> {
+        self.label_names_of(*state)
+            .into_iter()
+            .map(|label| label.to_string())
+            .collect()
+    }
+
+    fn has_label(&self, state: &usize, label: &str) -> bool {
+        self.label_names_of(*state).contains(label)
+    }
+
+    fn state_name(&self, state: &usize) -> String {
+        self.states
+            .get(state)
+            .cloned()
+            .unwrap_or_else(|| state.to_string())
+    }
+}
+
 fn construct_graph(states: &[usize], transitions: &[(usize, usize)]) -> DiGraph<usize, ()> {
     let mut g = DiGraph::<usize, ()>::new();
     let mut nodes = HashMap::new();
@@ -254,7 +295,9 @@ fn construct_graph(states: &[usize], transitions: &[(usize, usize)]) -> DiGraph<
 #[cfg(test)]
 mod tests {
 
-    use crate::{algorithm::explicit_state::explicit_state_checking, parser::ctl_parser::parse_ctl};
+    use crate::{
+        algorithm::explicit_state::explicit_state_checking, parser::ctl_parser::parse_ctl,
+    };
 
     use super::*;
 
@@ -350,16 +393,16 @@ mod tests {
         if let Ok(mut k) = res {
             let formula = parse_ctl("AG(s -> AF h)");
             if let Ok(formula) = formula {
-                explicit_state_checking(&mut k, formula.clone());
+                explicit_state_checking(&mut k, formula.clone()).unwrap();
 
                 let labels = k.labels;
                 let mut labels: Vec<(usize, String)> = labels.into_iter().collect();
-                labels.sort_by(|a, b| a.0.cmp(&b.0));
+                labels.sort_by_key(|a| a.0);
                 println!("{:?}", labels);
 
                 let states = k.label_to_states;
                 let mut states: Vec<(usize, HashSet<usize>)> = states.into_iter().collect();
-                states.sort_by(|a, b| a.0.cmp(&b.0));
+                states.sort_by_key(|a| a.0);
                 println!("{:?}", states);
             } else {
                 println!("Error: {}", formula.err().unwrap());
@@ -368,4 +411,23 @@ mod tests {
             println!("Error: {}", res.err().unwrap());
         }
     }
+
+    #[test]
+    fn test_non_trivial_scc() {
+        let mut k = Kripke::new(0);
+        for id in 0..4 {
+            k.add_state(id, &format!("s{}", id), &[] as &[&str]);
+        }
+        // a self-loop, and a cycle with a transition leaving the states
+        k.add_transition(0, 0);
+        k.add_transition(1, 2);
+        k.add_transition(1, 3);
+        k.add_transition(2, 1);
+
+        assert_eq!(k.non_trivial_scc_of(&[0]), vec![vec![0]]);
+        let mut sccs = k.non_trivial_scc_of(&[1, 2]);
+        sccs[0].sort();
+        assert_eq!(sccs, vec![vec![1, 2]]);
+        assert!(k.non_trivial_scc_of(&[1, 3]).is_empty());
+    }
 }
@@ -0,0 +1,170 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::model::{
+    dtmc::{read_distribution, Dtmc, MarkovError},
+    kripke::Kripke,
+};
+
+/// An action of a state, with the probabilities of its outcomes.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Action {
+    pub name: String,
+    /// key: to, value: probability
+    pub distribution: HashMap<usize, f64>,
+    /// The reward earned going to a state, 0 when missing.
+    ///
+    /// key: to, value: reward
+    pub rewards: HashMap<usize, f64>,
+}
+
+impl Action {
+    /// The expected reward of the transitions of the action.
+    pub fn reward(&self) -> f64 {
+        self.rewards
+            .iter()
+            .map(|(to, reward)| self.distribution[to] * reward)
+            .sum()
+    }
+}
+
+/// Markov decision process.
+///
+/// In each state, an action is chosen nondeterministically, then its
+/// outcome probabilistically. The Kripke structure has the transitions of
+/// every action.
+#[derive(Debug)]
+pub struct Mdp {
+    pub kripke: Kripke,
+
+    /// The actions of each state.
+    pub actions: HashMap<usize, Vec<Action>>,
+
+    /// The reward earned at each step in a state, 0 when missing.
+    pub state_rewards: HashMap<usize, f64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ActionInfo {
+    name: String,
+    transit_to: Vec<usize>,
+    probabilities: Vec<f64>,
+    #[serde(default)]
+    rewards: Vec<f64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct StateInfo {
+    id: usize,
+    name: String,
+    labels: Vec<String>,
+    #[serde(default)]
+    actions: Vec<ActionInfo>,
+    #[serde(default)]
+    reward: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct MdpBuilder {
+    states: Vec<StateInfo>,
+    initial_state: usize,
+}
+
+impl Mdp {
+    /// Read a decision process in the JSON format of Kripke structures, each
+    /// state having `actions` with a `name`, and `transit_to` and
+    /// `probabilities` as in Markov chains. A state without actions is
+    /// absorbing, with a single `stay` action. Rewards are given as in Markov
+    /// chains, those of the transitions in the actions.
+    pub fn from_json(data: &str) -> Result<Mdp, MarkovError> {
+        let builder = serde_json::from_str::<MdpBuilder>(data).map_err(MarkovError::Json)?;
+        let mut kripke = Kripke::new(builder.initial_state);
+        let mut actions = HashMap::new();
+        let mut state_rewards = HashMap::new();
+
+        for state in &builder.states {
+            kripke.add_state(state.id, &state.name, &state.labels);
+        }
+        for state in builder.states {
+            if state.reward < 0.0 {
+                return Err(MarkovError::NegativeReward(state.id));
+            }
+            if state.reward != 0.0 {
+                state_rewards.insert(state.id, state.reward);
+            }
+            let mut choices = Vec::new();
+            if state.actions.is_empty() {
+                kripke.add_transition(state.id, state.id);
+                choices.push(Action {
+                    name: "stay".to_string(),
+                    distribution: HashMap::from([(state.id, 1.0)]),
+                    rewards: HashMap::new(),
+                });
+            }
+            for action in state.actions {
+                let (distribution, rewards) = read_distribution(
+                    &mut kripke,
+                    state.id,
+                    action.transit_to,
+                    action.probabilities,
+                    action.rewards,
+                )?;
+                choices.push(Action {
+                    name: action.name,
+                    distribution,
+                    rewards,
+                });
+            }
+            actions.insert(state.id, choices);
+        }
+        if !kripke.states.contains_key(&kripke.initial_state) {
+            return Err(MarkovError::UnknownState(kripke.initial_state));
+        }
+
+        Ok(Mdp {
+            kripke,
+            actions,
+            state_rewards,
+        })
+    }
+
+    /// The Markov chain of the process when the action of each state is
+    /// chosen by `scheduler`, by name.
+    ///
+    /// Fails if a state has no action chosen, or no action with the name
+    /// chosen.
+    pub fn induced(&self, scheduler: &HashMap<usize, String>) -> Result<Dtmc, MarkovError> {
+        let mut kripke = Kripke::new(self.kripke.initial_state);
+        let mut probabilities = HashMap::new();
+        let mut transition_rewards = HashMap::new();
+        for (id, name) in &self.kripke.states {
+            let labels = self
+                .kripke
+                .label_names_of(*id)
+                .into_iter()
+                .collect::<Vec<&str>>();
+            kripke.add_state(*id, name, &labels);
+        }
+        for (id, choices) in &self.actions {
+            let chosen = scheduler.get(id).ok_or(MarkovError::Unscheduled(*id))?;
+            let action = choices
+                .iter()
+                .find(|action| action.name == *chosen)
+                .ok_or_else(|| MarkovError::UnknownAction(*id, chosen.clone()))?;
+            for to in action.distribution.keys() {
+                kripke.add_transition(*id, *to);
+            }
+            probabilities.insert(*id, action.distribution.clone());
+            if !action.rewards.is_empty() {
+                transition_rewards.insert(*id, action.rewards.clone());
+            }
+        }
+        Ok(Dtmc {
+            kripke,
+            probabilities,
+            state_rewards: self.state_rewards.clone(),
+            transition_rewards,
+        })
+    }
+}
@@ -0,0 +1,68 @@
+/// A player of a parity game.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Player {
+    Even,
+    Odd,
+}
+
+impl Player {
+    /// The player favoured by `priority`.
+    pub fn of(priority: usize) -> Player {
+        if priority.is_multiple_of(2) {
+            Player::Even
+        } else {
+            Player::Odd
+        }
+    }
+
+    pub fn opponent(self) -> Player {
+        match self {
+            Player::Even => Player::Odd,
+            Player::Odd => Player::Even,
+        }
+    }
+}
+
+/// Parity game.
+///
+/// A play moves a token along the edges, the owner of the current vertex
+/// choosing the next one. Even wins an infinite play when the largest
+/// priority seen infinitely often is even, and a player who cannot move
+/// loses.
+#[derive(Debug, Default)]
+pub struct ParityGame {
+    pub owners: Vec<Player>,
+    pub priorities: Vec<usize>,
+    pub successors: Vec<Vec<usize>>,
+    /// A description of each vertex.
+    pub names: Vec<String>,
+}
+
+impl ParityGame {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.owners.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.owners.is_empty()
+    }
+
+    /// Add a vertex without successors, returning its id.
+    pub fn add_vertex(&mut self, owner: Player, priority: usize, name: String) -> usize {
+        self.owners.push(owner);
+        self.priorities.push(priority);
+        self.successors.push(Vec::new());
+        self.names.push(name);
+        self.len() - 1
+    }
+
+    pub fn add_edge(&mut self, from: usize, to: usize) {
+        if !self.successors[from].contains(&to) {
+            self.successors[from].push(to);
+        }
+    }
+}
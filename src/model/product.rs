@@ -0,0 +1,373 @@
+use std::collections::HashSet;
+
+use crate::model::{kripke::Kripke, transition_system::TransitionSystem};
+
+/// How two components are composed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Composition {
+    /// Both components move at every step.
+    Synchronous,
+    /// Exactly one component moves at every step, unless both have to move
+    /// together to change a shared label or to perform a shared action.
+    Asynchronous,
+}
+
+impl Kripke {
+    /// Synchronous product of `self` and `other`.
+    ///
+    /// Labels are renamed to `prefix.label` with the prefix of their component,
+    /// except the `shared` ones which keep their name and on which both
+    /// components must agree in every product state, and the `actions`.
+    ///
+    /// An action is a label of the states a step performing it enters, so
+    /// that both components step into states agreeing on the actions. Fails
+    /// when the initial states disagree on the shared labels, the product
+    /// having no state.
+    pub fn synchronous_product(
+        &self,
+        other: &Kripke,
+        prefixes: (&str, &str),
+        shared: &[&str],
+        actions: &[&str],
+    ) -> Result<Kripke, String> {
+        product(
+            self,
+            other,
+            prefixes,
+            shared,
+            actions,
+            Composition::Synchronous,
+        )
+    }
+
+    /// Asynchronous (interleaving) product of `self` and `other`.
+    ///
+    /// Labels are renamed as in [`Kripke::synchronous_product`]. A component
+    /// moves alone as long as it keeps the `shared` labels unchanged and
+    /// performs none of the `actions`, while a change of the shared labels or
+    /// an action is a handshake in which both components move to states
+    /// agreeing on them. Fails as [`Kripke::synchronous_product`].
+    pub fn asynchronous_product(
+        &self,
+        other: &Kripke,
+        prefixes: (&str, &str),
+        shared: &[&str],
+        actions: &[&str],
+    ) -> Result<Kripke, String> {
+        product(
+            self,
+            other,
+            prefixes,
+            shared,
+            actions,
+            Composition::Asynchronous,
+        )
+    }
+}
+
+/// The product of two Kripke structures, explored lazily.
+///
+/// States are pairs of component states agreeing on the shared labels, and
+/// only the pairs reachable from the pair of initial states are ever
+/// generated. There is none when the initial states disagree.
+#[derive(Debug, Clone)]
+pub struct Product<'a> {
+    pub left: &'a Kripke,
+    pub right: &'a Kripke,
+    pub prefixes: (&'a str, &'a str),
+    pub shared: &'a [&'a str],
+    pub actions: &'a [&'a str],
+    pub composition: Composition,
+}
+
+impl<'a> Product<'a> {
+    pub fn new(
+        left: &'a Kripke,
+        right: &'a Kripke,
+        prefixes: (&'a str, &'a str),
+        shared: &'a [&'a str],
+        actions: &'a [&'a str],
+        composition: Composition,
+    ) -> Product<'a> {
+        Product {
+            left,
+            right,
+            prefixes,
+            shared,
+            actions,
+            composition,
+        }
+    }
+}
+
+impl TransitionSystem for Product<'_> {
+    type State = (usize, usize);
+
+    fn initial_states(&self) -> Vec<(usize, usize)> {
+        let (s, t) = (self.left.initial_state, self.right.initial_state);
+        if agree(self.left, self.right, s, t, self.shared) {
+            vec![(s, t)]
+        } else {
+            Vec::new()
+        }
+    }
+
+    fn successors(&self, state: &(usize, usize)) -> Vec<(usize, usize)> {
+        let mut next = product_successors(self, *state);
+        next.sort();
+        next.dedup();
+        next
+    }
+
+    fn labels(&self, state: &(usize, usize)) -> HashSet<String> {
+        product_labels(self, *state)
+    }
+
+    fn state_name(&self, (s, t): &(usize, usize)) -> String {
+        format!("({}, {})", self.left.states[s], self.right.states[t])
+    }
+}
+
+/// Build the reachable part of the product of `left` and `right`, failing
+/// when it has no initial state.
+pub fn product(
+    left: &Kripke,
+    right: &Kripke,
+    prefixes: (&str, &str),
+    shared: &[&str],
+    actions: &[&str],
+    composition: Composition,
+) -> Result<Kripke, String> {
+    let product = Product::new(left, right, prefixes, shared, actions, composition);
+    if product.initial_states().is_empty() {
+        return Err(format!(
+            "the initial states disagree on the shared labels {}",
+            shared.join(", ")
+        ));
+    }
+    Ok(product.to_kripke())
+}
+
+/// The successors of the pair `(s, t)` in `product`.
+fn product_successors(product: &Product, (s, t): (usize, usize)) -> Vec<(usize, usize)> {
+    let Product {
+        left,
+        right,
+        shared,
+        actions,
+        ..
+    } = *product;
+    let empty = HashSet::new();
+    let left_next = left.transitions.get(&s).unwrap_or(&empty);
+    let right_next = right.transitions.get(&t).unwrap_or(&empty);
+    let joint = || {
+        left_next
+            .iter()
+            .flat_map(|s2| right_next.iter().map(move |t2| (*s2, *t2)))
+            .filter(|(s2, t2)| agree(left, right, *s2, *t2, actions))
+    };
+
+    let next = match product.composition {
+        Composition::Synchronous => joint().collect::<Vec<_>>(),
+        Composition::Asynchronous => {
+            let current = valuation(left, s, shared);
+            // whether a component stepping into `state` may move alone
+            let alone = |model: &Kripke, state: usize| {
+                valuation(model, state, shared) == current
+                    && valuation(model, state, actions).is_empty()
+            };
+            let mut next = Vec::new();
+            for s2 in left_next {
+                if alone(left, *s2) {
+                    next.push((*s2, t));
+                }
+            }
+            for t2 in right_next {
+                if alone(right, *t2) {
+                    next.push((s, *t2));
+                }
+            }
+            next.extend(joint().filter(|(s2, _)| !alone(left, *s2)));
+            next
+        }
+    };
+    next.into_iter()
+        .filter(|(s2, t2)| agree(left, right, *s2, *t2, shared))
+        .collect()
+}
+
+/// The labels among `labels` holding in `state`.
+fn valuation<'a>(model: &Kripke, state: usize, labels: &[&'a str]) -> Vec<&'a str> {
+    let names = model.label_names_of(state);
+    labels
+        .iter()
+        .filter(|label| names.contains(**label))
+        .copied()
+        .collect()
+}
+
+fn agree(left: &Kripke, right: &Kripke, s: usize, t: usize, labels: &[&str]) -> bool {
+    valuation(left, s, labels) == valuation(right, t, labels)
+}
+
+/// The labels of the pair `(s, t)`, with the component prefixes applied to
+/// those which are neither shared nor actions.
+fn product_labels(product: &Product, (s, t): (usize, usize)) -> HashSet<String> {
+    let (left_prefix, right_prefix) = product.prefixes;
+    let mut labels = HashSet::new();
+    for (model, state, prefix) in [
+        (product.left, s, left_prefix),
+        (product.right, t, right_prefix),
+    ] {
+        for label in model.label_names_of(state) {
+            if product.shared.contains(&label) || product.actions.contains(&label) {
+                labels.insert(label.to_string());
+            } else {
+                labels.insert(format!("{}.{}", prefix, label));
+            }
+        }
+    }
+    labels
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A component toggling between an idle and a critical state, where the
+    /// critical state holds the shared label `lock`.
+    fn component() -> Kripke {
+        let data = r#"
+        {
+            "states": [
+                { "id": 0, "name": "idle", "labels": ["idle"], "transit_to": [0, 1] },
+                { "id": 1, "name": "crit", "labels": ["crit", "lock"], "transit_to": [0] }
+            ],
+            "initial_state": 0
+        }"#;
+        Kripke::from_json(data).unwrap()
+    }
+
+    fn names(k: &Kripke) -> HashSet<String> {
+        k.states.values().cloned().collect()
+    }
+
+    #[test]
+    fn test_synchronous_product() {
+        let k = component();
+        let p = k.synchronous_product(&k, ("p", "q"), &[], &[]).unwrap();
+        assert_eq!(p.states.len(), 4);
+        assert_eq!(p.transitions[&0].len(), 4);
+        assert!(p.contains_label("p.crit").is_some());
+        assert!(p.contains_label("q.lock").is_some());
+
+        let p = k
+            .synchronous_product(&k, ("p", "q"), &["lock"], &[])
+            .unwrap();
+        assert_eq!(
+            names(&p),
+            HashSet::from(["(idle, idle)".to_string(), "(crit, crit)".to_string()])
+        );
+        assert!(p.contains_label("lock").is_some());
+        assert!(p.contains_label("p.lock").is_none());
+    }
+
+    #[test]
+    fn test_asynchronous_product() {
+        let k = component();
+        let p = k.asynchronous_product(&k, ("p", "q"), &[], &[]).unwrap();
+        assert_eq!(p.states.len(), 4);
+        // (idle, idle) -> (idle, idle) twice, (crit, idle), (idle, crit)
+        assert_eq!(p.transitions[&0].len(), 3);
+        for (from, to) in &p.transitions {
+            for t in to {
+                let before = p.label_names_of(*from);
+                let after = p.label_names_of(*t);
+                let moved = ["p.crit", "q.crit"]
+                    .iter()
+                    .filter(|l| before.contains(**l) != after.contains(**l))
+                    .count();
+                assert!(moved <= 1);
+            }
+        }
+
+        // both components enter and leave the critical section together
+        let p = k
+            .asynchronous_product(&k, ("p", "q"), &["lock"], &[])
+            .unwrap();
+        assert_eq!(
+            names(&p),
+            HashSet::from(["(idle, idle)".to_string(), "(crit, crit)".to_string()])
+        );
+        let crit = p.get_state_with_label(p.contains_label("lock").unwrap());
+        assert_eq!(crit.len(), 1);
+        assert!(p.transitions[&0].contains(&crit[0]));
+    }
+
+    #[test]
+    fn test_disagreeing_components() {
+        let k = component();
+        let mut critical = component();
+        critical.initial_state = 1;
+        // the components start on different sides of the lock
+        for composition in [Composition::Synchronous, Composition::Asynchronous] {
+            let p = Product::new(&k, &critical, ("p", "q"), &["lock"], &[], composition);
+            assert!(p.initial_states().is_empty());
+            assert_eq!(
+                product(&k, &critical, ("p", "q"), &["lock"], &[], composition).unwrap_err(),
+                "the initial states disagree on the shared labels lock"
+            );
+        }
+        let p = Product::new(
+            &k,
+            &k,
+            ("p", "q"),
+            &["lock"],
+            &[],
+            Composition::Asynchronous,
+        );
+        for (s, t) in p.successors(&(0, 0)) {
+            assert!(agree(&k, &k, s, t, &["lock"]));
+        }
+    }
+
+    #[test]
+    fn test_shared_actions() {
+        let k = component();
+        // entering the critical section is a handshake, leaving it is not
+        let p = k
+            .asynchronous_product(&k, ("p", "q"), &[], &["crit"])
+            .unwrap();
+        let idle = p
+            .states
+            .iter()
+            .find(|(_, n)| *n == "(idle, idle)")
+            .unwrap()
+            .0;
+        let next = p.transitions[idle]
+            .iter()
+            .map(|s| p.states[s].as_str())
+            .collect::<HashSet<_>>();
+        assert_eq!(next, HashSet::from(["(idle, idle)", "(crit, crit)"]));
+        assert!(names(&p).contains("(idle, crit)"));
+        assert!(p.contains_label("crit").is_some());
+        assert!(p.contains_label("p.crit").is_none());
+    }
+
+    #[test]
+    fn test_only_reachable_states() {
+        let data = r#"
+        {
+            "states": [
+                { "id": 0, "name": "a", "labels": [], "transit_to": [0] },
+                { "id": 1, "name": "b", "labels": [], "transit_to": [1] }
+            ],
+            "initial_state": 0
+        }"#;
+        let k = Kripke::from_json(data).unwrap();
+        let p = k.synchronous_product(&k, ("l", "r"), &[], &[]).unwrap();
+        assert_eq!(names(&p), HashSet::from(["(a, a)".to_string()]));
+        let p = k.asynchronous_product(&k, ("l", "r"), &[], &[]).unwrap();
+        assert_eq!(names(&p), HashSet::from(["(a, a)".to_string()]));
+    }
+}
@@ -0,0 +1,291 @@
+use std::collections::BTreeSet;
+
+use crate::{
+    expr::Expr,
+    model::guarded::GuardedProgram,
+    sat::{Lit, Solver},
+};
+
+/// A copy of the state of a program in a solver.
+///
+/// `bits[v][i]` holds when variable `v` has value `min + i`, exactly one of
+/// the bits of each variable holding.
+#[derive(Debug, Clone)]
+pub struct Frame {
+    pub bits: Vec<Vec<Lit>>,
+}
+
+/// A partial assignment of the variables of a program, sorted by variable.
+pub type Cube = Vec<(usize, i64)>;
+
+/// The encoding of a guarded program into propositional clauses.
+///
+/// Variables are encoded one-hot, and expressions by enumerating the values
+/// of the variables they read, which is fine for the small domains of these
+/// programs.
+pub struct Encoding<'a> {
+    program: &'a GuardedProgram,
+}
+
+impl<'a> Encoding<'a> {
+    pub fn new(program: &'a GuardedProgram) -> Encoding<'a> {
+        Encoding { program }
+    }
+
+    /// Allocate a new copy of the state.
+    pub fn frame(&self, solver: &mut Solver) -> Frame {
+        let bits = self
+            .program
+            .variables
+            .iter()
+            .map(|v| {
+                let bits = (v.min..=v.max)
+                    .map(|_| solver.new_var())
+                    .collect::<Vec<Lit>>();
+                solver.add_clause(&bits);
+                for i in 0..bits.len() {
+                    for j in i + 1..bits.len() {
+                        solver.add_clause(&[!bits[i], !bits[j]]);
+                    }
+                }
+                bits
+            })
+            .collect();
+        Frame { bits }
+    }
+
+    /// The literal of variable `var` having `value` in `frame`.
+    pub fn literal(&self, frame: &Frame, var: usize, value: i64) -> Lit {
+        frame.bits[var][(value - self.program.variables[var].min) as usize]
+    }
+
+    pub fn cube(&self, frame: &Frame, cube: &[(usize, i64)]) -> Vec<Lit> {
+        cube.iter()
+            .map(|(var, value)| self.literal(frame, *var, *value))
+            .collect()
+    }
+
+    /// The negation of the cube as a clause, with `guard` as an extra
+    /// literal if given.
+    pub fn block(
+        &self,
+        solver: &mut Solver,
+        frame: &Frame,
+        cube: &[(usize, i64)],
+        guard: Option<Lit>,
+    ) {
+        let mut clause = self
+            .cube(frame, cube)
+            .into_iter()
+            .map(|lit| !lit)
+            .collect::<Vec<Lit>>();
+        clause.extend(guard.map(|g| !g));
+        solver.add_clause(&clause);
+    }
+
+    /// Constrain the states of `a` and `b` to differ.
+    pub fn distinct(&self, solver: &mut Solver, a: &Frame, b: &Frame) {
+        // some value of a variable is taken in `a` and not in `b`
+        let mut witnesses = Vec::new();
+        for (a_bits, b_bits) in a.bits.iter().zip(&b.bits) {
+            for (a_bit, b_bit) in a_bits.iter().zip(b_bits) {
+                let witness = solver.new_var();
+                solver.add_clause(&[!witness, *a_bit]);
+                solver.add_clause(&[!witness, !*b_bit]);
+                witnesses.push(witness);
+            }
+        }
+        solver.add_clause(&witnesses);
+    }
+
+    /// Constrain `frame` to the initial state, when `guard` holds if given.
+    pub fn init(&self, solver: &mut Solver, frame: &Frame, guard: Option<Lit>) {
+        let initial = self.program.initial_state();
+        for (var, value) in initial.into_iter().enumerate() {
+            let mut clause = vec![self.literal(frame, var, value)];
+            clause.extend(guard.map(|g| !g));
+            solver.add_clause(&clause);
+        }
+    }
+
+    /// Every assignment of `vars`.
+    fn valuations(&self, vars: &BTreeSet<usize>) -> Vec<Cube> {
+        let mut result = vec![Vec::new()];
+        for var in vars {
+            let v = &self.program.variables[*var];
+            result = result
+                .into_iter()
+                .flat_map(|cube: Cube| {
+                    (v.min..=v.max).map(move |value| {
+                        let mut cube = cube.clone();
+                        cube.push((*var, value));
+                        cube
+                    })
+                })
+                .collect();
+        }
+        result
+    }
+
+    /// A state with the values of `cube`, the other variables being at their
+    /// minimum.
+    fn state_of(&self, cube: &[(usize, i64)]) -> Vec<i64> {
+        let mut state = self
+            .program
+            .variables
+            .iter()
+            .map(|v| v.min)
+            .collect::<Vec<i64>>();
+        for (var, value) in cube {
+            state[*var] = *value;
+        }
+        state
+    }
+
+    /// A new literal equivalent to `expr` holding in `frame`, which it does
+    /// not where it cannot be evaluated.
+    pub fn predicate(&self, solver: &mut Solver, frame: &Frame, expr: &Expr) -> Lit {
+        let lit = solver.new_var();
+        for cube in self.valuations(&expr.variables()) {
+            let mut clause = self
+                .cube(frame, &cube)
+                .into_iter()
+                .map(|l| !l)
+                .collect::<Vec<Lit>>();
+            clause.push(if expr.holds(&self.state_of(&cube)) == Ok(true) {
+                lit
+            } else {
+                !lit
+            });
+            solver.add_clause(&clause);
+        }
+        lit
+    }
+
+    /// Constrain `to` to be a successor of `from`.
+    ///
+    /// A selector literal per command chooses the one executed, and another
+    /// one the self-loop of deadlocked states. A command whose guard or
+    /// updates cannot be evaluated, or assigning a value outside of the domain
+    /// of a variable, counts as disabled, so that every state has a successor.
+    pub fn transition(&self, solver: &mut Solver, from: &Frame, to: &Frame) {
+        let program = self.program;
+        let negated = |cube: &Cube, frame: &Frame| {
+            self.cube(frame, cube)
+                .into_iter()
+                .map(|l| !l)
+                .collect::<Vec<Lit>>()
+        };
+        let keep = |solver: &mut Solver, selector: Lit, vars: &mut dyn Iterator<Item = usize>| {
+            for var in vars {
+                for (bit, next) in from.bits[var].iter().zip(&to.bits[var]) {
+                    solver.add_clause(&[!selector, !*bit, *next]);
+                }
+            }
+        };
+
+        let idle = solver.new_var();
+        let mut selectors = vec![idle];
+        for (c, command) in program.commands.iter().enumerate() {
+            let selector = solver.new_var();
+            selectors.push(selector);
+            for cube in self.valuations(&program.reads(c)) {
+                let state = self.state_of(&cube);
+                let mut clause = negated(&cube, from);
+                let updates = command
+                    .updates
+                    .iter()
+                    .map(|(var, value)| Some((*var, value.eval(&state).ok()?)))
+                    .collect::<Option<Vec<(usize, i64)>>>()
+                    .filter(|updates| {
+                        command.guard.holds(&state) == Ok(true)
+                            && updates.iter().all(|(var, value)| {
+                                let v = &program.variables[*var];
+                                v.min <= *value && *value <= v.max
+                            })
+                    });
+                let Some(updates) = updates else {
+                    clause.push(!selector);
+                    solver.add_clause(&clause);
+                    continue;
+                };
+                for (var, value) in updates {
+                    let mut clause = clause.clone();
+                    clause.extend([!selector, self.literal(to, var, value)]);
+                    solver.add_clause(&clause);
+                }
+                clause.push(!idle);
+                solver.add_clause(&clause);
+            }
+            let written = program.writes(c);
+            keep(
+                solver,
+                selector,
+                &mut (0..program.variables.len()).filter(|v| !written.contains(v)),
+            );
+        }
+        keep(solver, idle, &mut (0..program.variables.len()));
+        solver.add_clause(&selectors);
+    }
+
+    /// The state of `frame` in the last assignment found by `solver`.
+    pub fn state(&self, solver: &Solver, frame: &Frame) -> Vec<i64> {
+        frame
+            .bits
+            .iter()
+            .zip(&self.program.variables)
+            .map(|(bits, v)| {
+                let i = bits.iter().position(|b| solver.model_value(*b)).unwrap();
+                v.min + i as i64
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::transition_system::TransitionSystem;
+
+    const PROGRAM: &str = r#"
+    {
+        "variables": [
+            { "name": "x", "min": 0, "max": 3, "init": 0 },
+            { "name": "y", "min": -1, "max": 1, "init": 1 }
+        ],
+        "processes": [
+            {
+                "name": "p",
+                "commands": [
+                    { "guard": "x < 3 && y > 0", "updates": { "x": "x + 1" } },
+                    { "guard": "x > 0 && y > -1", "updates": { "y": "y - 1", "x": "x - 1" } }
+                ]
+            }
+        ],
+        "labels": {}
+    }"#;
+
+    #[test]
+    fn test_transition_relation() {
+        let program = GuardedProgram::from_json(PROGRAM).unwrap();
+        let encoding = Encoding::new(&program);
+        let states = encoding.valuations(&BTreeSet::from([0, 1]));
+
+        // the successors allowed by the encoding are those of the program
+        for cube in states {
+            let state = encoding.state_of(&cube);
+            let mut solver = Solver::new();
+            let (from, to) = (encoding.frame(&mut solver), encoding.frame(&mut solver));
+            encoding.transition(&mut solver, &from, &to);
+            let mut found = Vec::new();
+            while solver.solve(&encoding.cube(&from, &cube)) {
+                let next = encoding.state(&solver, &to);
+                let next_cube = next.iter().cloned().enumerate().collect::<Cube>();
+                encoding.block(&mut solver, &to, &next_cube, None);
+                found.push(next);
+            }
+            found.sort();
+            assert_eq!(found, program.successors(&state), "{:?}", state);
+        }
+    }
+}
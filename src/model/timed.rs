@@ -0,0 +1,372 @@
+pub mod dbm;
+
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Display,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::model::{
+    kripke::Kripke,
+    timed::dbm::{Bound, Dbm},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Comparison {
+    Less,
+    LessEq,
+    Equal,
+    GreaterEq,
+    Greater,
+}
+
+/// A comparison of a clock with a constant, such as `x <= 5`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Constraint {
+    /// The index of the clock.
+    pub clock: usize,
+    pub comparison: Comparison,
+    pub constant: i64,
+}
+
+impl Constraint {
+    /// Intersect `zone` with the constraint. Returns whether the zone is not
+    /// empty.
+    pub fn apply(&self, zone: &mut Dbm) -> bool {
+        let x = self.clock + 1;
+        let c = self.constant;
+        match self.comparison {
+            Comparison::Less => zone.constrain(x, 0, Bound::less(c)),
+            Comparison::LessEq => zone.constrain(x, 0, Bound::less_eq(c)),
+            Comparison::Equal => {
+                zone.constrain(x, 0, Bound::less_eq(c)) && zone.constrain(0, x, Bound::less_eq(-c))
+            }
+            Comparison::GreaterEq => zone.constrain(0, x, Bound::less_eq(-c)),
+            Comparison::Greater => zone.constrain(0, x, Bound::less(-c)),
+        }
+    }
+}
+
+/// An error reading a timed automaton.
+#[derive(Debug)]
+pub enum TimedError {
+    Json(serde_json::Error),
+    UnknownLocation(usize),
+    UnknownClock(String),
+    /// A guard or an invariant which is not a conjunction of comparisons of
+    /// clocks with constants.
+    Constraint(String),
+}
+
+impl Display for TimedError {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TimedError::Json(e) => write!(formatter, "{}", e),
+            TimedError::UnknownLocation(l) => write!(formatter, "unknown location {}", l),
+            TimedError::UnknownClock(c) => write!(formatter, "unknown clock {}", c),
+            TimedError::Constraint(c) => write!(formatter, "invalid clock constraint {}", c),
+        }
+    }
+}
+
+impl std::error::Error for TimedError {}
+
+#[derive(Debug, Clone)]
+pub struct Location {
+    pub name: String,
+    pub labels: HashSet<String>,
+    /// The constraints which must hold while staying in the location.
+    pub invariant: Vec<Constraint>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Edge {
+    pub from: usize,
+    pub to: usize,
+    pub guard: Vec<Constraint>,
+    /// The clocks set to 0 by the edge.
+    pub resets: Vec<usize>,
+}
+
+/// Timed automaton.
+///
+/// The clocks all grow at the same rate while in a location, as long as its
+/// invariant holds. An edge can be taken when its guard holds, resetting
+/// some clocks.
+#[derive(Debug)]
+pub struct TimedAutomaton {
+    pub clocks: Vec<String>,
+
+    /// key: id, value: location
+    pub locations: HashMap<usize, Location>,
+
+    pub edges: Vec<Edge>,
+
+    pub initial_location: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct EdgeInfo {
+    to: usize,
+    #[serde(default)]
+    guard: String,
+    #[serde(default)]
+    resets: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct LocationInfo {
+    id: usize,
+    name: String,
+    labels: Vec<String>,
+    #[serde(default)]
+    invariant: String,
+    #[serde(default)]
+    edges: Vec<EdgeInfo>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct TimedAutomatonBuilder {
+    clocks: Vec<String>,
+    locations: Vec<LocationInfo>,
+    initial_location: usize,
+}
+
+/// Parse a conjunction of clock constraints, such as `x >= 2 && y < 3`.
+/// An empty string or `true` is no constraint.
+fn parse_constraints(clocks: &[String], input: &str) -> Result<Vec<Constraint>, TimedError> {
+    let input = input.trim();
+    if input.is_empty() || input == "true" {
+        return Ok(Vec::new());
+    }
+    let mut constraints = Vec::new();
+    for atom in input.split("&&") {
+        let error = || TimedError::Constraint(atom.trim().to_string());
+        // the two-character operators first, so that `<=` is not read as `<`
+        let (position, operator) = ["<=", ">=", "==", "<", ">"]
+            .iter()
+            .find_map(|op| atom.find(op).map(|p| (p, *op)))
+            .ok_or_else(error)?;
+        let clock = atom[..position].trim();
+        let constant = atom[position + operator.len()..]
+            .trim()
+            .parse::<i64>()
+            .map_err(|_| error())?;
+        if !(0..=Bound::MAX_CONSTANT).contains(&constant) {
+            return Err(error());
+        }
+        let clock = clocks
+            .iter()
+            .position(|c| c == clock)
+            .ok_or_else(|| TimedError::UnknownClock(clock.to_string()))?;
+        let comparison = match operator {
+            "<" => Comparison::Less,
+            "<=" => Comparison::LessEq,
+            "==" => Comparison::Equal,
+            ">=" => Comparison::GreaterEq,
+            _ => Comparison::Greater,
+        };
+        constraints.push(Constraint {
+            clock,
+            comparison,
+            constant,
+        });
+    }
+    Ok(constraints)
+}
+
+impl TimedAutomaton {
+    /// Read an automaton in JSON, with its `clocks`, and its `locations`
+    /// like the states of Kripke structures. A location may have an
+    /// `invariant`, and `edges` going `to` another location, with a `guard`
+    /// and the clocks they `resets`. Invariants and guards are conjunctions
+    /// such as `x >= 2 && y < 3`.
+    pub fn from_json(data: &str) -> Result<TimedAutomaton, TimedError> {
+        let builder =
+            serde_json::from_str::<TimedAutomatonBuilder>(data).map_err(TimedError::Json)?;
+        let clocks = builder.clocks;
+        let mut locations = HashMap::new();
+        let mut edges = Vec::new();
+        for location in &builder.locations {
+            locations.insert(
+                location.id,
+                Location {
+                    name: location.name.clone(),
+                    labels: location.labels.iter().cloned().collect(),
+                    invariant: parse_constraints(&clocks, &location.invariant)?,
+                },
+            );
+        }
+        for location in builder.locations {
+            for edge in location.edges {
+                if !locations.contains_key(&edge.to) {
+                    return Err(TimedError::UnknownLocation(edge.to));
+                }
+                let resets = edge
+                    .resets
+                    .iter()
+                    .map(|r| {
+                        clocks
+                            .iter()
+                            .position(|c| c == r)
+                            .ok_or_else(|| TimedError::UnknownClock(r.clone()))
+                    })
+                    .collect::<Result<Vec<usize>, TimedError>>()?;
+                edges.push(Edge {
+                    from: location.id,
+                    to: edge.to,
+                    guard: parse_constraints(&clocks, &edge.guard)?,
+                    resets,
+                });
+            }
+        }
+        if !locations.contains_key(&builder.initial_location) {
+            return Err(TimedError::UnknownLocation(builder.initial_location));
+        }
+
+        Ok(TimedAutomaton {
+            clocks,
+            locations,
+            edges,
+            initial_location: builder.initial_location,
+        })
+    }
+
+    /// The largest constant each clock is compared with, after 0 for the
+    /// constant clock of zones.
+    pub fn ceilings(&self) -> Vec<i64> {
+        let mut ceilings = vec![0; self.clocks.len() + 1];
+        let constraints = self
+            .locations
+            .values()
+            .flat_map(|l| &l.invariant)
+            .chain(self.edges.iter().flat_map(|e| &e.guard));
+        for constraint in constraints {
+            let ceiling = &mut ceilings[constraint.clock + 1];
+            *ceiling = (*ceiling).max(constraint.constant);
+        }
+        ceilings
+    }
+
+    /// Let time pass in `location`, as long as its invariant holds. Returns
+    /// whether the zone is not empty.
+    pub fn delay(&self, location: usize, zone: &mut Dbm) -> bool {
+        let invariant = &self.locations[&location].invariant;
+        if !invariant.iter().all(|c| c.apply(zone)) {
+            return false;
+        }
+        zone.up();
+        invariant.iter().all(|c| c.apply(zone))
+    }
+
+    /// The zone of the initial location, with `extra` clocks beyond those
+    /// of the automaton, all starting at 0.
+    pub fn initial_zone(&self, extra: usize) -> Option<Dbm> {
+        let mut zone = Dbm::zero(self.clocks.len() + extra);
+        self.delay(self.initial_location, &mut zone).then_some(zone)
+    }
+
+    /// The zone reached from `zone` by taking `edge`, which also resets the
+    /// extra clocks `resets`, then letting time pass. The values above the
+    /// `ceilings` are abstracted.
+    pub fn successor(
+        &self,
+        zone: &Dbm,
+        edge: &Edge,
+        resets: &[usize],
+        ceilings: &[i64],
+    ) -> Option<Dbm> {
+        let mut zone = zone.clone();
+        if !edge.guard.iter().all(|c| c.apply(&mut zone)) {
+            return None;
+        }
+        for clock in edge.resets.iter().chain(resets) {
+            zone.reset(clock + 1);
+        }
+        if !self.delay(edge.to, &mut zone) {
+            return None;
+        }
+        zone.extrapolate(ceilings);
+        Some(zone)
+    }
+
+    /// The edges leaving `location`.
+    pub fn edges_from(&self, location: usize) -> impl Iterator<Item = &Edge> {
+        self.edges.iter().filter(move |e| e.from == location)
+    }
+
+    /// The zone graph, whose states are the pairs of a location and a zone,
+    /// labelled as their location. The zones only keep the differences
+    /// which the automaton can tell apart, so that the graph is finite.
+    pub fn zone_graph(&self) -> Kripke {
+        let mut kripke = Kripke::new(0);
+        let ceilings = self.ceilings();
+        let Some(zone) = self.initial_zone(0) else {
+            return kripke;
+        };
+        let mut states = vec![(self.initial_location, zone)];
+        let mut ids = HashMap::<(usize, Dbm), usize>::new();
+        ids.insert(states[0].clone(), 0);
+        let mut id = 0;
+        while id < states.len() {
+            let (location, zone) = states[id].clone();
+            let l = &self.locations[&location];
+            let labels = l.labels.iter().collect::<Vec<&String>>();
+            kripke.add_state(id, &format!("{} [{}]", l.name, zone), &labels);
+            for edge in self.edges_from(location) {
+                if let Some(next) = self.successor(&zone, edge, &[], &ceilings) {
+                    let to = *ids.entry((edge.to, next)).or_insert_with_key(|state| {
+                        states.push(state.clone());
+                        states.len() - 1
+                    });
+                    kripke.add_transition(id, to);
+                }
+            }
+            id += 1;
+        }
+        kripke
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_json() {
+        let data = r#"
+        {
+            "clocks": ["x", "y"],
+            "locations": [
+                { "id": 0, "name": "idle", "labels": ["idle"], "invariant": "y <= 4", "edges": [
+                    { "to": 1, "guard": "x >= 2 && y < 3", "resets": ["x"] }
+                ] },
+                { "id": 1, "name": "busy", "labels": ["busy"], "edges": [
+                    { "to": 0, "guard": "x == 1", "resets": ["x", "y"] }
+                ] }
+            ],
+            "initial_location": 0
+        }"#;
+        let automaton = TimedAutomaton::from_json(data).unwrap();
+        assert_eq!(automaton.edges.len(), 2);
+        assert_eq!(automaton.edges[0].guard.len(), 2);
+        assert_eq!(automaton.ceilings(), vec![0, 2, 4]);
+        // busy is only entered with y in [2, 3), then idle is entered again
+        let graph = automaton.zone_graph();
+        assert_eq!(graph.states.len(), 2);
+
+        let data = data.replace("x >= 2", "z >= 2");
+        assert!(matches!(
+            TimedAutomaton::from_json(&data),
+            Err(TimedError::UnknownClock(_))
+        ));
+        for constraint in ["x >= -2", "x >= 9223372036854775807"] {
+            let data = data.replace("z >= 2", constraint);
+            assert!(matches!(
+                TimedAutomaton::from_json(&data),
+                Err(TimedError::Constraint(_))
+            ));
+        }
+    }
+}
@@ -0,0 +1,245 @@
+use std::{fmt::Display, ops::Add};
+
+/// A bound `≺ c` on a difference of clocks, where `≺` is `<` or `<=`.
+///
+/// It is encoded as `2c` when strict and `2c + 1` otherwise, so that bounds
+/// are ordered from the tightest.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Bound(i64);
+
+impl Bound {
+    pub const INFINITY: Bound = Bound(i64::MAX);
+    pub const ZERO: Bound = Bound(1);
+    /// The largest constant of a clock constraint, so that neither the
+    /// encoding nor the sums of the bounds of a zone overflow.
+    pub const MAX_CONSTANT: i64 = i64::MAX >> 8;
+
+    /// `< c`
+    pub fn less(c: i64) -> Bound {
+        Bound(c << 1)
+    }
+
+    /// `<= c`
+    pub fn less_eq(c: i64) -> Bound {
+        Bound((c << 1) | 1)
+    }
+
+    pub fn constant(&self) -> i64 {
+        self.0 >> 1
+    }
+
+    pub fn is_strict(&self) -> bool {
+        self.0 & 1 == 0
+    }
+}
+
+impl Add for Bound {
+    type Output = Bound;
+
+    /// The bound of the sum of two differences.
+    fn add(self, other: Bound) -> Bound {
+        if self == Bound::INFINITY || other == Bound::INFINITY {
+            return Bound::INFINITY;
+        }
+        Bound(((self.constant() + other.constant()) << 1) | (self.0 & other.0 & 1))
+    }
+}
+
+impl Display for Bound {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match (*self == Bound::INFINITY, self.is_strict()) {
+            (true, _) => write!(formatter, "<∞"),
+            (false, true) => write!(formatter, "<{}", self.constant()),
+            (false, false) => write!(formatter, "<={}", self.constant()),
+        }
+    }
+}
+
+/// Difference bound matrix, a convex set of clock valuations, or zone.
+///
+/// The entry `(i, j)` bounds `x_i - x_j`, where `x_0` is the constant 0 and
+/// the clocks are numbered from 1. The operations keep the matrix
+/// canonical, with the tightest bounds.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Dbm {
+    dimension: usize,
+    bounds: Vec<Bound>,
+}
+
+impl Dbm {
+    /// The zone where all the `clocks` are 0.
+    pub fn zero(clocks: usize) -> Dbm {
+        let dimension = clocks + 1;
+        Dbm {
+            dimension,
+            bounds: vec![Bound::ZERO; dimension * dimension],
+        }
+    }
+
+    pub fn clocks(&self) -> usize {
+        self.dimension - 1
+    }
+
+    pub fn get(&self, i: usize, j: usize) -> Bound {
+        self.bounds[i * self.dimension + j]
+    }
+
+    fn set(&mut self, i: usize, j: usize, bound: Bound) {
+        self.bounds[i * self.dimension + j] = bound;
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.get(0, 0) < Bound::ZERO
+    }
+
+    /// Tighten all the bounds, by the Floyd-Warshall algorithm.
+    fn close(&mut self) {
+        let n = self.dimension;
+        for k in 0..n {
+            for i in 0..n {
+                for j in 0..n {
+                    let through = self.get(i, k) + self.get(k, j);
+                    if through < self.get(i, j) {
+                        self.set(i, j, through);
+                    }
+                }
+            }
+            if (0..n).any(|i| self.get(i, i) < Bound::ZERO) {
+                self.set(0, 0, Bound::less(0));
+                return;
+            }
+        }
+    }
+
+    /// Let any amount of time pass.
+    pub fn up(&mut self) {
+        for i in 1..self.dimension {
+            self.set(i, 0, Bound::INFINITY);
+        }
+    }
+
+    /// Intersect with `x_i - x_j ≺ c`. Returns whether the zone is not
+    /// empty.
+    pub fn constrain(&mut self, i: usize, j: usize, bound: Bound) -> bool {
+        if self.is_empty() {
+            return false;
+        }
+        if bound < self.get(i, j) {
+            self.set(i, j, bound);
+            self.close();
+        }
+        !self.is_empty()
+    }
+
+    /// Set clock `x` to 0.
+    pub fn reset(&mut self, x: usize) {
+        for j in 0..self.dimension {
+            self.set(x, j, self.get(0, j));
+            self.set(j, x, self.get(j, 0));
+        }
+    }
+
+    /// Whether every valuation of `other` is in the zone.
+    pub fn includes(&self, other: &Dbm) -> bool {
+        other.is_empty() || self.bounds.iter().zip(&other.bounds).all(|(a, b)| b <= a)
+    }
+
+    /// Abstract the values of each clock `x_i` above `ceilings[i]`, which the
+    /// guards and invariants cannot tell apart, so that there are finitely
+    /// many zones. `ceilings[0]` is 0.
+    ///
+    /// This is exact for reachability when the constraints compare clocks
+    /// with constants only.
+    pub fn extrapolate(&mut self, ceilings: &[i64]) {
+        if self.is_empty() {
+            return;
+        }
+        let n = self.dimension;
+        for i in 0..n {
+            for j in 0..n {
+                if i == j {
+                    continue;
+                }
+                let bound = self.get(i, j);
+                if bound != Bound::INFINITY && bound > Bound::less_eq(ceilings[i]) {
+                    self.set(i, j, Bound::INFINITY);
+                } else if bound < Bound::less(-ceilings[j]) {
+                    self.set(i, j, Bound::less(-ceilings[j]));
+                }
+            }
+        }
+        self.close();
+    }
+}
+
+impl Display for Dbm {
+    /// The non-trivial bounds, such as `x1<=3 ∧ x1-x2<2`.
+    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.is_empty() {
+            return write!(formatter, "false");
+        }
+        let mut constraints = Vec::new();
+        for i in 0..self.dimension {
+            for j in 0..self.dimension {
+                let bound = self.get(i, j);
+                if i == j || bound == Bound::INFINITY || (i == 0 && bound == Bound::ZERO) {
+                    continue;
+                }
+                constraints.push(match (i, j) {
+                    (0, j) => format!("-x{}{}", j, bound),
+                    (i, 0) => format!("x{}{}", i, bound),
+                    (i, j) => format!("x{}-x{}{}", i, j, bound),
+                });
+            }
+        }
+        if constraints.is_empty() {
+            write!(formatter, "true")
+        } else {
+            write!(formatter, "{}", constraints.join(" ∧ "))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_zone_operations() {
+        // x1 - x2 stays 0 while time passes
+        let mut zone = Dbm::zero(2);
+        zone.up();
+        assert!(zone.constrain(1, 0, Bound::less_eq(5)));
+        assert_eq!(zone.get(2, 0), Bound::less_eq(5));
+        assert_eq!(zone.get(1, 2), Bound::ZERO);
+
+        // after x1 >= 2, x2 := 0 and a delay, x1 - x2 >= 2
+        assert!(zone.constrain(0, 1, Bound::less_eq(-2)));
+        zone.reset(2);
+        assert_eq!(zone.get(2, 1), Bound::less_eq(-2));
+        zone.up();
+        let before = zone.clone();
+        assert!(!zone.clone().constrain(1, 2, Bound::less(2)));
+        assert!(zone.constrain(2, 0, Bound::less_eq(1)));
+        assert!(before.includes(&zone) && !zone.includes(&before));
+
+        // x1 in [2, 5] then unbounded above the ceiling 3
+        let mut zone = Dbm::zero(1);
+        zone.up();
+        zone.constrain(1, 0, Bound::less_eq(5));
+        zone.constrain(0, 1, Bound::less_eq(-2));
+        zone.extrapolate(&[0, 3]);
+        assert_eq!(zone.get(1, 0), Bound::INFINITY);
+        assert_eq!(zone.get(0, 1), Bound::less_eq(-2));
+        assert_eq!(zone.to_string(), "-x1<=-2");
+    }
+
+    #[test]
+    fn test_bounds() {
+        assert!(Bound::less(3) < Bound::less_eq(3));
+        assert!(Bound::less_eq(3) < Bound::less(4));
+        assert_eq!(Bound::less(-2) + Bound::less_eq(5), Bound::less(3));
+        assert_eq!(Bound::less_eq(-2) + Bound::less_eq(2), Bound::ZERO);
+        assert_eq!(Bound::less(1) + Bound::INFINITY, Bound::INFINITY);
+    }
+}
@@ -0,0 +1,77 @@
+use std::collections::HashSet;
+
+use serde::{Deserialize, Serialize};
+
+use crate::model::kripke::Kripke;
+
+/// A recorded execution, as the labels of its states: the lasso
+/// `prefix · cycle^ω`, or a finite trace when the cycle is empty.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Trace {
+    pub prefix: Vec<HashSet<String>>,
+    pub cycle: Vec<HashSet<String>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct TraceBuilder {
+    prefix: Vec<Vec<String>>,
+    #[serde(default)]
+    cycle: Vec<Vec<String>>,
+}
+
+impl Trace {
+    pub fn finite(events: Vec<HashSet<String>>) -> Trace {
+        Trace {
+            prefix: events,
+            cycle: Vec::new(),
+        }
+    }
+
+    pub fn lasso(prefix: Vec<HashSet<String>>, cycle: Vec<HashSet<String>>) -> Trace {
+        Trace { prefix, cycle }
+    }
+
+    /// Read a trace given by the arrays of labels of its `prefix` and of its
+    /// `cycle`, if any, such as `{ "prefix": [["p"], []], "cycle": [["q"]] }`.
+    pub fn from_json(data: &str) -> Result<Trace, serde_json::Error> {
+        let builder = serde_json::from_str::<TraceBuilder>(data)?;
+        let read = |events: Vec<Vec<String>>| {
+            events
+                .into_iter()
+                .map(|labels| labels.into_iter().collect())
+                .collect()
+        };
+        Ok(Trace {
+            prefix: read(builder.prefix),
+            cycle: read(builder.cycle),
+        })
+    }
+
+    pub fn is_finite(&self) -> bool {
+        self.cycle.is_empty()
+    }
+
+    /// The trace as a linear Kripke structure, state `i` being its `i`th
+    /// position. The last state goes back to the start of the cycle, or
+    /// loops on itself for a finite trace.
+    pub fn to_kripke(&self) -> Kripke {
+        let mut kripke = Kripke::new(0);
+        let events = self.prefix.iter().chain(&self.cycle).collect::<Vec<_>>();
+        for (i, labels) in events.iter().enumerate() {
+            let labels = labels.iter().collect::<Vec<&String>>();
+            kripke.add_state(i, &format!("s{}", i), &labels);
+            if i > 0 {
+                kripke.add_transition(i - 1, i);
+            }
+        }
+        if let Some(last) = events.len().checked_sub(1) {
+            let back = if self.is_finite() {
+                last
+            } else {
+                self.prefix.len()
+            };
+            kripke.add_transition(last, back);
+        }
+        kripke
+    }
+}
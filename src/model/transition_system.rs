@@ -0,0 +1,88 @@
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    fmt::Debug,
+    hash::Hash,
+};
+
+use crate::model::kripke::Kripke;
+
+/// A transition system whose states are generated on demand.
+///
+/// Algorithms working on a `TransitionSystem` only build the states they
+/// visit, so they can stop as soon as they find what they are looking for.
+pub trait TransitionSystem {
+    type State: Clone + Eq + Hash + Debug;
+
+    fn initial_states(&self) -> Vec<Self::State>;
+
+    fn successors(&self, state: &Self::State) -> Vec<Self::State>;
+
+    /// The names of the atomic propositions holding in `state`.
+    fn labels(&self, state: &Self::State) -> HashSet<String>;
+
+    fn has_label(&self, state: &Self::State, label: &str) -> bool {
+        self.labels(state).contains(label)
+    }
+
+    fn state_name(&self, state: &Self::State) -> String {
+        format!("{:?}", state)
+    }
+
+    /// Materialize the reachable part of the system as a Kripke structure.
+    ///
+    /// States are numbered in breadth-first order, so the first initial state
+    /// gets id 0 and becomes the initial state of the result. A system without
+    /// initial state gives a structure without state, whose initial state 0
+    /// does not exist.
+    fn to_kripke(&self) -> Kripke {
+        self.to_kripke_with_states().0
+    }
+
+    /// Same as [`TransitionSystem::to_kripke`], also returning the state of
+    /// each id.
+    fn to_kripke_with_states(&self) -> (Kripke, Vec<Self::State>) {
+        let mut result = Kripke::new(0);
+        let mut ids: HashMap<Self::State, usize> = HashMap::new();
+        let mut queue = VecDeque::new();
+
+        for init in self.initial_states() {
+            if !ids.contains_key(&init) {
+                add_state(self, &mut result, &mut ids, &init);
+                queue.push_back(init);
+            }
+        }
+
+        while let Some(state) = queue.pop_front() {
+            let from = ids[&state];
+            for next in self.successors(&state) {
+                let to = match ids.get(&next) {
+                    Some(id) => *id,
+                    None => {
+                        let id = add_state(self, &mut result, &mut ids, &next);
+                        queue.push_back(next);
+                        id
+                    }
+                };
+                result.add_transition(from, to);
+            }
+        }
+
+        let mut states = ids.into_iter().collect::<Vec<(Self::State, usize)>>();
+        states.sort_by_key(|(_, id)| *id);
+        (result, states.into_iter().map(|(state, _)| state).collect())
+    }
+}
+
+fn add_state<T: TransitionSystem + ?Sized>(
+    system: &T,
+    result: &mut Kripke,
+    ids: &mut HashMap<T::State, usize>,
+    state: &T::State,
+) -> usize {
+    let id = ids.len();
+    ids.insert(state.clone(), id);
+    let mut labels = system.labels(state).into_iter().collect::<Vec<String>>();
+    labels.sort();
+    result.add_state(id, &system.state_name(state), &labels);
+    id
+}
@@ -0,0 +1,219 @@
+use std::fmt::Display;
+
+use crate::{ctl::CTLFormulae, ltl::unroll_until};
+
+/// Modal mu-calculus formulae, with the least and greatest fixpoints of
+/// formulae over sets of states.
+#[derive(Debug, Clone)]
+pub enum MuFormulae {
+    True,
+    False,
+    Atomic(String),
+    /// A variable bound by an enclosing fixpoint.
+    Variable(String),
+
+    Not(Box<MuFormulae>),
+    And(Box<MuFormulae>, Box<MuFormulae>),
+    Or(Box<MuFormulae>, Box<MuFormulae>),
+
+    /// `<>f`, some successor satisfies `f`.
+    Diamond(Box<MuFormulae>),
+    /// `[]f`, every successor satisfies `f`.
+    Square(Box<MuFormulae>),
+
+    /// `mu X. f`, the least fixpoint.
+    Mu(String, Box<MuFormulae>),
+    /// `nu X. f`, the greatest fixpoint.
+    Nu(String, Box<MuFormulae>),
+}
+
+/// An error making a formula meaningless.
+#[derive(Debug, PartialEq, Eq)]
+pub enum MuError {
+    /// A variable outside of the fixpoints binding it.
+    Unbound(String),
+    /// A variable under an odd number of negations, so that its fixpoint may
+    /// not exist.
+    NotMonotone(String),
+}
+
+impl Display for MuError {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MuError::Unbound(x) => write!(formatter, "variable {} is not bound", x),
+            MuError::NotMonotone(x) => write!(formatter, "variable {} occurs negated", x),
+        }
+    }
+}
+
+impl std::error::Error for MuError {}
+
+impl Display for MuFormulae {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MuFormulae::True => write!(formatter, "true"),
+            MuFormulae::False => write!(formatter, "false"),
+            MuFormulae::Atomic(s) | MuFormulae::Variable(s) => write!(formatter, "{}", s),
+            MuFormulae::Not(f) => write!(formatter, "¬{}", f),
+            MuFormulae::And(f, g) => write!(formatter, "({} ∧ {})", f, g),
+            MuFormulae::Or(f, g) => write!(formatter, "({} ∨ {})", f, g),
+            MuFormulae::Diamond(f) => write!(formatter, "<>{}", f),
+            MuFormulae::Square(f) => write!(formatter, "[]{}", f),
+            MuFormulae::Mu(x, f) => write!(formatter, "μ{}.{}", x, f),
+            MuFormulae::Nu(x, f) => write!(formatter, "ν{}.{}", x, f),
+        }
+    }
+}
+
+impl MuFormulae {
+    /// Check that every variable is bound, and only occurs under an even
+    /// number of negations from its fixpoint.
+    pub fn validate(&self) -> Result<(), MuError> {
+        self.validate_with(&mut Vec::new(), false)
+    }
+
+    /// `bound` holds the variables in scope, with whether they were bound
+    /// under a negation.
+    fn validate_with(&self, bound: &mut Vec<(String, bool)>, negated: bool) -> Result<(), MuError> {
+        match self {
+            MuFormulae::True | MuFormulae::False | MuFormulae::Atomic(_) => Ok(()),
+            MuFormulae::Variable(x) => match bound.iter().rev().find(|(y, _)| y == x) {
+                None => Err(MuError::Unbound(x.clone())),
+                Some((_, polarity)) if *polarity != negated => Err(MuError::NotMonotone(x.clone())),
+                Some(_) => Ok(()),
+            },
+            MuFormulae::Not(f) => f.validate_with(bound, !negated),
+            MuFormulae::Diamond(f) | MuFormulae::Square(f) => f.validate_with(bound, negated),
+            MuFormulae::And(f, g) | MuFormulae::Or(f, g) => {
+                f.validate_with(bound, negated)?;
+                g.validate_with(bound, negated)
+            }
+            MuFormulae::Mu(x, f) | MuFormulae::Nu(x, f) => {
+                bound.push((x.clone(), negated));
+                let result = f.validate_with(bound, negated);
+                bound.pop();
+                result
+            }
+        }
+    }
+
+    /// Translate a CTL state formula, whose temporal operators are all
+    /// quantified, to the mu-calculus.
+    ///
+    /// `A(f U g)` and `AF f` require every state before `g` to have a
+    /// successor. The bounded operators are unrolled, without fixpoints.
+    /// Fails when `formula` is not a state formula.
+    pub fn from_ctl(formula: &CTLFormulae) -> Result<MuFormulae, String> {
+        from_ctl_at(formula, 0)
+    }
+}
+
+/// Translate `formula`, naming the variables from the nesting `depth`.
+fn from_ctl_at(formula: &CTLFormulae, depth: usize) -> Result<MuFormulae, String> {
+    let x = format!("X{}", depth);
+    let var = || Box::new(MuFormulae::Variable(x.clone()));
+    let sub = |f: &CTLFormulae| Ok::<_, String>(Box::new(from_ctl_at(f, depth + 1)?));
+    let and = |f, g| Box::new(MuFormulae::And(f, g));
+    let or = |f, g| Box::new(MuFormulae::Or(f, g));
+    // the states with a successor in X
+    let progress = || {
+        and(
+            Box::new(MuFormulae::Square(var())),
+            Box::new(MuFormulae::Diamond(Box::new(MuFormulae::True))),
+        )
+    };
+    let error = || Err(format!("{} is not a CTL state formula", formula));
+    Ok(match formula {
+        CTLFormulae::True => MuFormulae::True,
+        CTLFormulae::Atomic(a) => MuFormulae::Atomic(a.clone()),
+        CTLFormulae::Not(f) => MuFormulae::Not(sub(f)?),
+        CTLFormulae::And(f, g) => MuFormulae::And(sub(f)?, sub(g)?),
+        CTLFormulae::Or(f, g) => MuFormulae::Or(sub(f)?, sub(g)?),
+        CTLFormulae::Exist(path) => match &**path {
+            CTLFormulae::Next(f) => MuFormulae::Diamond(sub(f)?),
+            // μX. f ∨ <>X
+            CTLFormulae::Finally(f) => {
+                MuFormulae::Mu(x.clone(), or(sub(f)?, Box::new(MuFormulae::Diamond(var()))))
+            }
+            // νX. f ∧ <>X
+            CTLFormulae::Globally(f) => MuFormulae::Nu(
+                x.clone(),
+                and(sub(f)?, Box::new(MuFormulae::Diamond(var()))),
+            ),
+            // μX. g ∨ (f ∧ <>X)
+            CTLFormulae::Until(f, g) => MuFormulae::Mu(
+                x.clone(),
+                or(sub(g)?, and(sub(f)?, Box::new(MuFormulae::Diamond(var())))),
+            ),
+            // νX. g ∧ (f ∨ <>X)
+            CTLFormulae::Release(f, g) => MuFormulae::Nu(
+                x.clone(),
+                and(sub(g)?, or(sub(f)?, Box::new(MuFormulae::Diamond(var())))),
+            ),
+            CTLFormulae::BoundedFinally(a, b, f) => {
+                bounded_until(*a, *b, MuFormulae::True, *sub(f)?, MuFormulae::Diamond)
+            }
+            // EG[a,b] f = !AF[a,b] !f
+            CTLFormulae::BoundedGlobally(a, b, f) => {
+                let f = MuFormulae::Not(sub(f)?);
+                let af = bounded_until(*a, *b, MuFormulae::True, f, MuFormulae::Square);
+                MuFormulae::Not(Box::new(af))
+            }
+            CTLFormulae::BoundedUntil(a, b, f, g) => {
+                bounded_until(*a, *b, *sub(f)?, *sub(g)?, MuFormulae::Diamond)
+            }
+            _ => return error(),
+        },
+        CTLFormulae::All(path) => match &**path {
+            CTLFormulae::Next(f) => MuFormulae::Square(sub(f)?),
+            // μX. f ∨ ([]X ∧ <>true)
+            CTLFormulae::Finally(f) => MuFormulae::Mu(x.clone(), or(sub(f)?, progress())),
+            // νX. f ∧ []X
+            CTLFormulae::Globally(f) => {
+                MuFormulae::Nu(x.clone(), and(sub(f)?, Box::new(MuFormulae::Square(var()))))
+            }
+            // μX. g ∨ (f ∧ []X ∧ <>true)
+            CTLFormulae::Until(f, g) => {
+                MuFormulae::Mu(x.clone(), or(sub(g)?, and(sub(f)?, progress())))
+            }
+            // νX. g ∧ (f ∨ []X)
+            CTLFormulae::Release(f, g) => MuFormulae::Nu(
+                x.clone(),
+                and(sub(g)?, or(sub(f)?, Box::new(MuFormulae::Square(var())))),
+            ),
+            CTLFormulae::BoundedFinally(a, b, f) => {
+                bounded_until(*a, *b, MuFormulae::True, *sub(f)?, MuFormulae::Square)
+            }
+            // AG[a,b] f = !EF[a,b] !f
+            CTLFormulae::BoundedGlobally(a, b, f) => {
+                let f = MuFormulae::Not(sub(f)?);
+                let ef = bounded_until(*a, *b, MuFormulae::True, f, MuFormulae::Diamond);
+                MuFormulae::Not(Box::new(ef))
+            }
+            CTLFormulae::BoundedUntil(a, b, f, g) => {
+                bounded_until(*a, *b, *sub(f)?, *sub(g)?, MuFormulae::Square)
+            }
+            _ => return error(),
+        },
+        _ => return error(),
+    })
+}
+
+/// `f U[a,b] g` without fixpoint, the next steps being taken by `modality`.
+fn bounded_until(
+    a: usize,
+    b: usize,
+    f: MuFormulae,
+    g: MuFormulae,
+    modality: fn(Box<MuFormulae>) -> MuFormulae,
+) -> MuFormulae {
+    unroll_until(
+        a,
+        b,
+        f,
+        g,
+        |f| modality(Box::new(f)),
+        |f, g| MuFormulae::And(Box::new(f), Box::new(g)),
+        |f, g| MuFormulae::Or(Box::new(f), Box::new(g)),
+    )
+}
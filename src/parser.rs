@@ -1,2 +1,29 @@
+pub mod atl_parser;
 pub mod ctl_parser;
+pub mod ctl_star_parser;
+pub mod expr_parser;
 pub mod ltl_parser;
+pub mod mu_parser;
+pub mod pctl_parser;
+pub mod tctl_parser;
+
+/// The number of steps of a bound of a temporal operator, failing when it
+/// does not fit.
+pub(crate) fn steps(bound: &str) -> Result<usize, String> {
+    bound
+        .parse()
+        .map_err(|_| format!("Bound {} is too large", bound))
+}
+
+/// The first and last steps of an interval `[a,b]`, failing when it is empty
+/// or a bound does not fit.
+pub(crate) fn interval<'a>(
+    mut bounds: impl Iterator<Item = &'a str>,
+) -> Result<(usize, usize), String> {
+    let (a, b) = (bounds.next().unwrap(), bounds.next().unwrap());
+    let (first, last) = (steps(a)?, steps(b)?);
+    if first > last {
+        return Err(format!("Empty interval [{},{}]", a, b));
+    }
+    Ok((first, last))
+}
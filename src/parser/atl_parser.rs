@@ -0,0 +1,113 @@
+use once_cell::sync::OnceCell;
+use pest::{
+    iterators::{Pair, Pairs},
+    pratt_parser::PrattParser,
+    Parser,
+};
+
+use crate::atl::ATLFormulae;
+
+#[derive(pest_derive::Parser)]
+#[grammar = "parser/atl.pest"]
+pub struct ATLParser;
+
+fn atl_parser() -> &'static PrattParser<Rule> {
+    static INSTANCE: OnceCell<PrattParser<Rule>> = OnceCell::new();
+    INSTANCE.get_or_init(|| {
+        use pest::pratt_parser::{Assoc::*, Op};
+        use Rule::*;
+
+        // Precedence is defined lowest to highest
+        PrattParser::new()
+            .op(Op::infix(And, Left) | Op::infix(Or, Left) | Op::infix(Implies, Left))
+            .op(Op::prefix(Not))
+    })
+}
+
+/// Parse an ATL formula, such as `<<a, b>> G !crash && <<>> X p`. The
+/// temporal operators apply to the formula right after them, and `<<A>> F f`
+/// stands for `<<A>> (true U f)`.
+pub fn parse_atl(input: &str) -> Result<ATLFormulae, Box<pest::error::Error<Rule>>> {
+    match ATLParser::parse(Rule::formulae, input) {
+        Ok(mut pairs) => {
+            let formula = pairs.next().unwrap().into_inner().next().unwrap();
+            Ok(parse_expr(formula.into_inner()))
+        }
+        Err(e) => {
+            eprintln!("Parse failed: {:?}", e);
+            Err(Box::new(e))
+        }
+    }
+}
+
+fn parse_strategic(pair: Pair<Rule>) -> ATLFormulae {
+    let mut inner = pair.into_inner();
+    let coalition = inner
+        .next()
+        .unwrap()
+        .into_inner()
+        .map(|agent| agent.as_str().to_owned())
+        .collect::<Vec<String>>();
+    let first = inner.next().unwrap();
+    match first.as_rule() {
+        Rule::formula => {
+            let f = parse_expr(first.into_inner());
+            inner.next();
+            let g = parse_expr(inner.next().unwrap().into_inner());
+            ATLFormulae::Until(coalition, Box::new(f), Box::new(g))
+        }
+        op => {
+            let f = Box::new(parse_expr(inner.next().unwrap().into_inner()));
+            match op {
+                Rule::Next => ATLFormulae::Next(coalition, f),
+                Rule::Globally => ATLFormulae::Globally(coalition, f),
+                _ => ATLFormulae::Until(coalition, Box::new(ATLFormulae::True), f),
+            }
+        }
+    }
+}
+
+fn parse_expr(pairs: Pairs<Rule>) -> ATLFormulae {
+    atl_parser()
+        .map_primary(|primary| match primary.as_rule() {
+            Rule::AP => ATLFormulae::Atomic(primary.as_str().to_owned()),
+            Rule::TRUE => ATLFormulae::True,
+            Rule::FALSE => ATLFormulae::Not(Box::new(ATLFormulae::True)),
+            Rule::formula => parse_expr(primary.into_inner()),
+            Rule::strategic => parse_strategic(primary),
+            rule => unreachable!("Expr::parse expected atom, found {:?}", rule),
+        })
+        .map_infix(|lhs, op, rhs| match op.as_rule() {
+            Rule::And => ATLFormulae::And(Box::new(lhs), Box::new(rhs)),
+            Rule::Or => ATLFormulae::Or(Box::new(lhs), Box::new(rhs)),
+            Rule::Implies => {
+                ATLFormulae::Or(Box::new(ATLFormulae::Not(Box::new(lhs))), Box::new(rhs))
+            }
+            _ => unreachable!(),
+        })
+        .map_prefix(|op, rhs| match op.as_rule() {
+            Rule::Not => ATLFormulae::Not(Box::new(rhs)),
+            _ => unreachable!(),
+        })
+        .parse(pairs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_atl() {
+        let f = parse_atl("<<a, b>> G !crash && <<>> X p").unwrap();
+        assert_eq!(f.to_string(), "(⟨⟨a,b⟩⟩G¬crash ∧ ⟨⟨⟩⟩Xp)");
+        let f = parse_atl("<<train>> (!cross U (open && <<gate>> F closed))").unwrap();
+        assert_eq!(
+            f.to_string(),
+            "⟨⟨train⟩⟩(¬cross U (open ∧ ⟨⟨gate⟩⟩(true U closed)))"
+        );
+        let f = parse_atl("!<<a>> X <<b>> G p -> q").unwrap();
+        assert_eq!(f.to_string(), "(¬¬⟨⟨a⟩⟩X⟨⟨b⟩⟩Gp ∨ q)");
+        assert!(parse_atl("<<a>> p").is_err());
+        assert!(parse_atl("<<a>> (p && q)").is_err());
+    }
+}
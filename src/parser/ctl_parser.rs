@@ -1,13 +1,20 @@
 use once_cell::sync::OnceCell;
-use pest::{iterators::Pairs, pratt_parser::PrattParser, Parser};
+use pest::{
+    error::{Error, ErrorVariant},
+    iterators::{Pair, Pairs},
+    pratt_parser::PrattParser,
+    Parser, Position,
+};
 
-use crate::ctl::CTLFormulae;
+use crate::{ctl::CTLFormulae, parser::interval};
 
 #[derive(pest_derive::Parser)]
 #[grammar = "parser/ctl.pest"]
+#[grammar = "parser/pctl.pest"]
+#[grammar = "parser/tctl.pest"]
 pub struct CTLParser;
 
-fn ctl_parser() -> &'static PrattParser<Rule> {
+pub(crate) fn ctl_parser() -> &'static PrattParser<Rule> {
     static INSTANCE: OnceCell<PrattParser<Rule>> = OnceCell::new();
     INSTANCE.get_or_init(|| {
         use pest::pratt_parser::{Assoc::*, Op};
@@ -17,73 +24,155 @@ fn ctl_parser() -> &'static PrattParser<Rule> {
         PrattParser::new()
             // Addition and subtract have equal precedence
             .op(Op::infix(And, Left) | Op::infix(Or, Left) | Op::infix(Implies, Left))
-            .op(Op::infix(Until, Right) | Op::infix(WeakUntil, Right) | Op::infix(Release, Right))
+            .op(Op::infix(Until, Right)
+                | Op::infix(WeakUntil, Right)
+                | Op::infix(Release, Right)
+                | Op::infix(BoundedUntil, Right)
+                | Op::infix(IntervalUntil, Right))
             .op(Op::prefix(All) | Op::prefix(Exists))
-            .op(Op::prefix(Not) | Op::prefix(Next) | Op::prefix(Finally) | Op::prefix(Globally))
+            .op(Op::prefix(Not)
+                | Op::prefix(Next)
+                | Op::prefix(Finally)
+                | Op::prefix(Globally)
+                | Op::prefix(BoundedFinally)
+                | Op::prefix(BoundedGlobally)
+                | Op::prefix(IntervalFinally)
+                | Op::prefix(IntervalGlobally)
+                | Op::prefix(TimedFinally)
+                | Op::prefix(TimedGlobally))
     })
 }
 
-pub fn parse_ctl(input: &str) -> Result<CTLFormulae, Box<pest::error::Error<Rule>>> {
-    match CTLParser::parse(Rule::formula, input) {
-        Ok(mut pairs) => Ok(parse_expr(pairs.next().unwrap().into_inner())),
+pub fn parse_ctl(input: &str) -> Result<CTLFormulae, Box<Error<Rule>>> {
+    let pairs = match CTLParser::parse(Rule::formula, input) {
+        Ok(mut pairs) => pairs.next().unwrap().into_inner(),
         Err(e) => {
             eprintln!("Parse failed: {:?}", e);
-            Err(Box::new(e))
+            return Err(Box::new(e));
         }
-    }
+    };
+    parse_expr(pairs).map_err(|message| {
+        Box::new(Error::new_from_pos(
+            ErrorVariant::CustomError { message },
+            Position::from_start(input),
+        ))
+    })
 }
 
-fn parse_expr(pairs: Pairs<Rule>) -> CTLFormulae {
+fn parse_expr(pairs: Pairs<Rule>) -> Result<CTLFormulae, String> {
     ctl_parser()
         .map_primary(|primary| match primary.as_rule() {
-            Rule::AP => CTLFormulae::Atomic(primary.as_str().to_owned()),
-            Rule::TRUE => CTLFormulae::True,
+            Rule::AP => Ok(CTLFormulae::Atomic(primary.as_str().to_owned())),
+            Rule::TRUE => Ok(CTLFormulae::True),
             Rule::formula => parse_expr(primary.into_inner()),
             rule => unreachable!("Expr::parse expected atom, found {:?}", rule),
         })
-        .map_infix(|lhs, op, rhs| match op.as_rule() {
-            Rule::And => CTLFormulae::And(Box::new(lhs), Box::new(rhs)),
-            Rule::Or => CTLFormulae::Or(Box::new(lhs), Box::new(rhs)),
-            Rule::Implies => {
-                CTLFormulae::Or(Box::new(CTLFormulae::Not(Box::new(lhs))), Box::new(rhs))
-            }
-            Rule::Until => CTLFormulae::Until(Box::new(lhs), Box::new(rhs)),
-            Rule::Release => CTLFormulae::Release(Box::new(lhs), Box::new(rhs)),
-            Rule::WeakUntil => todo!(),
-            _ => unreachable!(),
-        })
-        .map_prefix(|op, rhs| match op.as_rule() {
-            Rule::All => match rhs {
-                CTLFormulae::All(_) => {
-                    eprintln!("Warning: repeated quantifier");
-                    rhs
-                }
-                CTLFormulae::Exist(_) => {
-                    panic!("Cannot mix quantifiers");
-                }
-                _ => CTLFormulae::All(Box::new(rhs)),
-            },
-            Rule::Exists => match rhs {
-                CTLFormulae::Exist(_) => {
-                    eprintln!("Warning: repeated quantifier");
-                    rhs
-                }
-                CTLFormulae::All(_) => {
-                    panic!("Cannot mix quantifiers");
-                }
-                CTLFormulae::Next(_)
-                | CTLFormulae::Until(_, _)
-                | CTLFormulae::Globally(_)
-                | CTLFormulae::Release(_, _) => CTLFormulae::Exist(Box::new(rhs)),
-                _ => {
-                    panic!("Path quantifier must be followed by a temporal operator")
-                }
-            },
-            Rule::Next => CTLFormulae::Next(Box::new(rhs)),
-            Rule::Not => CTLFormulae::Not(Box::new(rhs)),
-            Rule::Finally => CTLFormulae::Finally(Box::new(rhs)),
-            Rule::Globally => CTLFormulae::Globally(Box::new(rhs)),
-            _ => unreachable!(),
-        })
+        .map_infix(|lhs, op, rhs| infix(lhs?, op, rhs?))
+        .map_prefix(|op, rhs| prefix(op, rhs?))
         .parse(pairs)
 }
+
+pub(crate) fn infix(
+    lhs: CTLFormulae,
+    op: Pair<Rule>,
+    rhs: CTLFormulae,
+) -> Result<CTLFormulae, String> {
+    Ok(match op.as_rule() {
+        Rule::And => CTLFormulae::And(Box::new(lhs), Box::new(rhs)),
+        Rule::Or => CTLFormulae::Or(Box::new(lhs), Box::new(rhs)),
+        Rule::Implies => CTLFormulae::Or(Box::new(CTLFormulae::Not(Box::new(lhs))), Box::new(rhs)),
+        Rule::Until => CTLFormulae::Until(Box::new(lhs), Box::new(rhs)),
+        Rule::Release => CTLFormulae::Release(Box::new(lhs), Box::new(rhs)),
+        Rule::IntervalUntil => {
+            let (a, b) = interval(op.into_inner().map(|k| k.as_str()))?;
+            CTLFormulae::BoundedUntil(a, b, Box::new(lhs), Box::new(rhs))
+        }
+        // (f U g) || G f has no single path quantifier
+        Rule::WeakUntil => {
+            return Err("W is not a CTL operator, parse it as CTL* instead".to_string())
+        }
+        _ => unreachable!(),
+    })
+}
+
+pub(crate) fn prefix(op: Pair<Rule>, rhs: CTLFormulae) -> Result<CTLFormulae, String> {
+    Ok(match op.as_rule() {
+        Rule::All => match rhs {
+            CTLFormulae::All(_) => {
+                eprintln!("Warning: repeated quantifier");
+                rhs
+            }
+            CTLFormulae::Exist(_) => {
+                return Err("Cannot mix quantifiers, parse it as CTL* instead".to_string());
+            }
+            _ => CTLFormulae::All(Box::new(rhs)),
+        },
+        Rule::Exists => match rhs {
+            CTLFormulae::Exist(_) => {
+                eprintln!("Warning: repeated quantifier");
+                rhs
+            }
+            CTLFormulae::All(_) => {
+                return Err("Cannot mix quantifiers, parse it as CTL* instead".to_string());
+            }
+            CTLFormulae::Next(_)
+            | CTLFormulae::Finally(_)
+            | CTLFormulae::Until(_, _)
+            | CTLFormulae::Globally(_)
+            | CTLFormulae::Release(_, _)
+            | CTLFormulae::BoundedFinally(_, _, _)
+            | CTLFormulae::BoundedGlobally(_, _, _)
+            | CTLFormulae::BoundedUntil(_, _, _, _) => CTLFormulae::Exist(Box::new(rhs)),
+            _ => {
+                return Err("Path quantifier must be followed by a temporal operator".to_string());
+            }
+        },
+        Rule::Next => CTLFormulae::Next(Box::new(rhs)),
+        Rule::Not => CTLFormulae::Not(Box::new(rhs)),
+        Rule::Finally => CTLFormulae::Finally(Box::new(rhs)),
+        Rule::Globally => CTLFormulae::Globally(Box::new(rhs)),
+        Rule::IntervalFinally => {
+            let (a, b) = interval(op.into_inner().map(|k| k.as_str()))?;
+            CTLFormulae::BoundedFinally(a, b, Box::new(rhs))
+        }
+        Rule::IntervalGlobally => {
+            let (a, b) = interval(op.into_inner().map(|k| k.as_str()))?;
+            CTLFormulae::BoundedGlobally(a, b, Box::new(rhs))
+        }
+        _ => unreachable!(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(formula: &str) -> String {
+        match parse_ctl(formula).unwrap_err().variant {
+            ErrorVariant::CustomError { message } => message,
+            variant => panic!("{:?}", variant),
+        }
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(parse_ctl("EF[2,5] p && A (p U[0,3] q)").is_ok());
+        assert_eq!(message("EF[5,2] p"), "Empty interval [5,2]");
+        assert_eq!(
+            message("EF[0,99999999999999999999999] p"),
+            "Bound 99999999999999999999999 is too large"
+        );
+        assert_eq!(
+            message("A (p W q)"),
+            "W is not a CTL operator, parse it as CTL* instead"
+        );
+        assert_eq!(
+            message("E p"),
+            "Path quantifier must be followed by a temporal operator"
+        );
+        assert_eq!(
+            message("A E F p"),
+            "Cannot mix quantifiers, parse it as CTL* instead"
+        );
+    }
+}
@@ -0,0 +1,139 @@
+use pest::{
+    error::{Error, ErrorVariant},
+    iterators::Pairs,
+    Parser, Position,
+};
+
+use crate::{
+    ctl_star::{bounded_until, CTLStarFormulae},
+    parser::{
+        ctl_parser::{ctl_parser, CTLParser, Rule},
+        interval,
+    },
+};
+
+/// Parse a CTL* formula, with the syntax of CTL formulae where the path
+/// quantifiers and the temporal operators may be mixed, such as
+/// `E (G F p && F G q)`. The operators with an interval of steps are
+/// unrolled into next operators.
+pub fn parse_ctl_star(input: &str) -> Result<CTLStarFormulae, Box<Error<Rule>>> {
+    let formula = match CTLParser::parse(Rule::formulae, input) {
+        Ok(mut pairs) => pairs.next().unwrap().into_inner().next().unwrap(),
+        Err(e) => {
+            eprintln!("Parse failed: {:?}", e);
+            return Err(Box::new(e));
+        }
+    };
+    parse_expr(formula.into_inner()).map_err(|message| {
+        Box::new(Error::new_from_pos(
+            ErrorVariant::CustomError { message },
+            Position::from_start(input),
+        ))
+    })
+}
+
+fn parse_expr(pairs: Pairs<Rule>) -> Result<CTLStarFormulae, String> {
+    ctl_parser()
+        .map_primary(|primary| match primary.as_rule() {
+            Rule::AP => Ok(CTLStarFormulae::Atomic(primary.as_str().to_owned())),
+            Rule::TRUE => Ok(CTLStarFormulae::True),
+            Rule::formula => parse_expr(primary.into_inner()),
+            rule => unreachable!("Expr::parse expected atom, found {:?}", rule),
+        })
+        .map_infix(|lhs, op, rhs| {
+            let (lhs, rhs) = (Box::new(lhs?), Box::new(rhs?));
+            Ok(match op.as_rule() {
+                Rule::And => CTLStarFormulae::And(lhs, rhs),
+                Rule::Or => CTLStarFormulae::Or(lhs, rhs),
+                Rule::Implies => CTLStarFormulae::Or(Box::new(CTLStarFormulae::Not(lhs)), rhs),
+                Rule::Until => CTLStarFormulae::Until(lhs, rhs),
+                Rule::Release => CTLStarFormulae::Release(lhs, rhs),
+                // f W g = (f U g) || G f
+                Rule::WeakUntil => CTLStarFormulae::Or(
+                    Box::new(CTLStarFormulae::Until(lhs.clone(), rhs)),
+                    Box::new(CTLStarFormulae::Globally(lhs)),
+                ),
+                Rule::IntervalUntil => {
+                    let (a, b) = interval(op.into_inner().map(|k| k.as_str()))?;
+                    bounded_until(a, b, *lhs, *rhs)
+                }
+                _ => unreachable!(),
+            })
+        })
+        .map_prefix(|op, rhs| {
+            let rhs = Box::new(rhs?);
+            Ok(match op.as_rule() {
+                Rule::All => CTLStarFormulae::All(rhs),
+                Rule::Exists => CTLStarFormulae::Exist(rhs),
+                Rule::Not => CTLStarFormulae::Not(rhs),
+                Rule::Next => CTLStarFormulae::Next(rhs),
+                Rule::Finally => CTLStarFormulae::Finally(rhs),
+                Rule::Globally => CTLStarFormulae::Globally(rhs),
+                Rule::IntervalFinally => {
+                    let (a, b) = interval(op.into_inner().map(|k| k.as_str()))?;
+                    bounded_until(a, b, CTLStarFormulae::True, *rhs)
+                }
+                // G[a,b] f = !(true U[a,b] !f)
+                Rule::IntervalGlobally => {
+                    let (a, b) = interval(op.into_inner().map(|k| k.as_str()))?;
+                    let f = CTLStarFormulae::Not(rhs);
+                    CTLStarFormulae::Not(Box::new(bounded_until(a, b, CTLStarFormulae::True, f)))
+                }
+                _ => unreachable!(),
+            })
+        })
+        .parse(pairs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::ctl_parser::parse_ctl;
+
+    #[test]
+    fn test_parse_ctl_star() {
+        let f = parse_ctl_star("E (G F p && F G q)").unwrap();
+        assert_eq!(f.to_string(), "E(GFp ∧ FGq)");
+        assert!(f.is_state_formula());
+
+        // quantifiers nested in path formulae
+        let f = parse_ctl_star("A (F p -> E G q) || !q").unwrap();
+        assert_eq!(f.to_string(), "(A(¬Fp ∨ EGq) ∨ ¬q)");
+        let f = parse_ctl_star("A E X p").unwrap();
+        assert_eq!(f.to_string(), "AEXp");
+
+        let f = parse_ctl_star("p W q").unwrap();
+        assert_eq!(f.to_string(), "((p U q) ∨ Gp)");
+        assert!(!f.is_state_formula());
+
+        assert!(parse_ctl_star("E (p U)").is_err());
+    }
+
+    #[test]
+    fn test_intervals() {
+        // the same formulae as their CTL counterparts
+        for formula in ["E F[2,5] p", "A (p U[0,3] q)", "A G[0,2] p"] {
+            let ctl = CTLStarFormulae::from(parse_ctl(formula).unwrap());
+            assert_eq!(
+                parse_ctl_star(formula).unwrap().to_string(),
+                ctl.to_string()
+            );
+        }
+        let f = parse_ctl_star("G[0,2] p").unwrap();
+        assert_eq!(f.to_string(), "¬(¬p ∨ (true ∧ X(¬p ∨ (true ∧ X¬p))))");
+        assert!(!f.is_state_formula());
+
+        for (formula, error) in [
+            ("E F[5,2] p", "Empty interval [5,2]"),
+            (
+                "p U[0,99999999999999999999999] q",
+                "Bound 99999999999999999999999 is too large",
+            ),
+        ] {
+            match parse_ctl_star(formula).unwrap_err().variant {
+                ErrorVariant::CustomError { message } => assert_eq!(message, error),
+                variant => panic!("{:?}", variant),
+            }
+        }
+    }
+}
@@ -0,0 +1,136 @@
+use once_cell::sync::OnceCell;
+use pest::{
+    error::{Error, ErrorVariant},
+    iterators::Pairs,
+    pratt_parser::PrattParser,
+    Parser,
+};
+
+use crate::expr::{BinaryOp, Expr};
+
+#[derive(pest_derive::Parser)]
+#[grammar = "parser/expr.pest"]
+pub struct ExprParser;
+
+fn expr_parser() -> &'static PrattParser<Rule> {
+    static INSTANCE: OnceCell<PrattParser<Rule>> = OnceCell::new();
+    INSTANCE.get_or_init(|| {
+        use pest::pratt_parser::{Assoc::*, Op};
+        use Rule::*;
+
+        // Precedence is defined lowest to highest
+        PrattParser::new()
+            .op(Op::infix(Or, Left))
+            .op(Op::infix(And, Left))
+            .op(Op::infix(Eq, Left) | Op::infix(Neq, Left))
+            .op(Op::infix(Lt, Left)
+                | Op::infix(Le, Left)
+                | Op::infix(Gt, Left)
+                | Op::infix(Ge, Left))
+            .op(Op::infix(Add, Left) | Op::infix(Sub, Left))
+            .op(Op::infix(Mul, Left) | Op::infix(Div, Left) | Op::infix(Mod, Left))
+            .op(Op::prefix(Not) | Op::prefix(Neg))
+    })
+}
+
+/// Parse an expression, where `resolve` gives the index of a variable.
+pub fn parse_expr<F>(input: &str, resolve: F) -> Result<Expr, Box<Error<Rule>>>
+where
+    F: Fn(&str) -> Option<usize>,
+{
+    let mut pairs = ExprParser::parse(Rule::expression, input).map_err(Box::new)?;
+    let expr = pairs.next().unwrap().into_inner().next().unwrap();
+
+    // report the first unknown variable or out of range integer
+    for pair in expr.clone().into_inner().flatten() {
+        let message = match pair.as_rule() {
+            Rule::VAR if resolve(pair.as_str()).is_none() => {
+                format!("unknown variable {}", pair.as_str())
+            }
+            Rule::INT if pair.as_str().parse::<i64>().is_err() => {
+                format!("integer {} is out of range", pair.as_str())
+            }
+            _ => continue,
+        };
+        return Err(Box::new(Error::new_from_span(
+            ErrorVariant::CustomError { message },
+            pair.as_span(),
+        )));
+    }
+
+    Ok(parse_pairs(expr.into_inner(), &resolve))
+}
+
+fn parse_pairs<F>(pairs: Pairs<Rule>, resolve: &F) -> Expr
+where
+    F: Fn(&str) -> Option<usize>,
+{
+    expr_parser()
+        .map_primary(|primary| match primary.as_rule() {
+            Rule::TRUE => Expr::Int(1),
+            Rule::FALSE => Expr::Int(0),
+            Rule::INT => Expr::Int(primary.as_str().parse().unwrap()),
+            Rule::VAR => Expr::Var(resolve(primary.as_str()).unwrap()),
+            Rule::expr => parse_pairs(primary.into_inner(), resolve),
+            rule => unreachable!("Expr::parse expected atom, found {:?}", rule),
+        })
+        .map_infix(|lhs, op, rhs| {
+            let op = match op.as_rule() {
+                Rule::Or => BinaryOp::Or,
+                Rule::And => BinaryOp::And,
+                Rule::Eq => BinaryOp::Eq,
+                Rule::Neq => BinaryOp::Neq,
+                Rule::Lt => BinaryOp::Lt,
+                Rule::Le => BinaryOp::Le,
+                Rule::Gt => BinaryOp::Gt,
+                Rule::Ge => BinaryOp::Ge,
+                Rule::Add => BinaryOp::Add,
+                Rule::Sub => BinaryOp::Sub,
+                Rule::Mul => BinaryOp::Mul,
+                Rule::Div => BinaryOp::Div,
+                Rule::Mod => BinaryOp::Mod,
+                _ => unreachable!(),
+            };
+            Expr::Binary(op, Box::new(lhs), Box::new(rhs))
+        })
+        .map_prefix(|op, rhs| match op.as_rule() {
+            Rule::Not => Expr::Not(Box::new(rhs)),
+            Rule::Neg => match rhs {
+                Expr::Int(i) => Expr::Int(-i),
+                _ => Expr::Neg(Box::new(rhs)),
+            },
+            _ => unreachable!(),
+        })
+        .parse(pairs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn resolve(name: &str) -> Option<usize> {
+        ["x", "y", "pc_0"].iter().position(|v| *v == name)
+    }
+
+    #[test]
+    fn test_parse_expr() {
+        let e = parse_expr("x + 2 * y == 7 && !(pc_0 > 1)", resolve).unwrap();
+        assert!(e.holds(&[1, 3, 0]).unwrap());
+        assert!(!e.holds(&[1, 3, 2]).unwrap());
+        assert!(!e.holds(&[2, 3, 0]).unwrap());
+        assert_eq!(e.conjuncts().len(), 2);
+        assert_eq!(e.variables().into_iter().collect::<Vec<_>>(), vec![0, 1, 2]);
+
+        let e = parse_expr("-x % 3 - 1 < 0 || false", resolve).unwrap();
+        assert!(e.holds(&[4, 0, 0]).unwrap());
+        assert!(parse_expr("true", resolve).unwrap().holds(&[]).unwrap());
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(parse_expr("x + z", resolve).is_err());
+        assert!(parse_expr("x +", resolve).is_err());
+        assert!(parse_expr("x y", resolve).is_err());
+        assert!(parse_expr("x + 99999999999999999999", resolve).is_err());
+    }
+}
@@ -1,7 +1,12 @@
 use once_cell::sync::OnceCell;
-use pest::{iterators::Pairs, pratt_parser::PrattParser, Parser};
+use pest::{
+    error::{Error, ErrorVariant},
+    iterators::Pairs,
+    pratt_parser::PrattParser,
+    Parser, Position,
+};
 
-use crate::ltl::LTLFormulae;
+use crate::{ltl::LTLFormulae, parser::interval};
 
 #[derive(pest_derive::Parser)]
 #[grammar = "parser/ltl.pest"]
@@ -17,53 +22,140 @@ fn ltl_parser() -> &'static PrattParser<Rule> {
         PrattParser::new()
             // Addition and subtract have equal precedence
             .op(Op::infix(And, Left) | Op::infix(Or, Left) | Op::infix(Implies, Left))
-            .op(Op::infix(Until, Right) | Op::infix(WeakUntil, Right) | Op::infix(Release, Right))
-            .op(Op::prefix(Globally) | Op::prefix(Eventually))
-            .op(Op::prefix(Not) | Op::prefix(Next))
+            .op(Op::infix(Until, Right)
+                | Op::infix(WeakUntil, Right)
+                | Op::infix(Release, Right)
+                | Op::infix(Since, Right)
+                | Op::infix(IntervalUntil, Right))
+            .op(Op::prefix(Globally)
+                | Op::prefix(Eventually)
+                | Op::prefix(IntervalGlobally)
+                | Op::prefix(IntervalFinally)
+                | Op::prefix(Historically)
+                | Op::prefix(Once))
+            .op(Op::prefix(Not) | Op::prefix(Next) | Op::prefix(Yesterday))
     })
 }
 
-pub fn parse_ltl(input: &str) -> Result<LTLFormulae, Box<pest::error::Error<Rule>>> {
-    match LTLParser::parse(Rule::formula, input) {
-        Ok(mut pairs) => Ok(parse_expr(pairs.next().unwrap().into_inner())),
+pub fn parse_ltl(input: &str) -> Result<LTLFormulae, Box<Error<Rule>>> {
+    let pairs = match LTLParser::parse(Rule::formula, input) {
+        Ok(mut pairs) => pairs.next().unwrap().into_inner(),
         Err(e) => {
             eprintln!("Parse failed: {:?}", e);
-            Err(Box::new(e))
+            return Err(Box::new(e));
         }
-    }
+    };
+    parse_expr(pairs).map_err(|message| {
+        Box::new(Error::new_from_pos(
+            ErrorVariant::CustomError { message },
+            Position::from_start(input),
+        ))
+    })
 }
 
-fn parse_expr(pairs: Pairs<Rule>) -> LTLFormulae {
+fn parse_expr(pairs: Pairs<Rule>) -> Result<LTLFormulae, String> {
     ltl_parser()
         .map_primary(|primary| match primary.as_rule() {
-            Rule::AP => LTLFormulae::Atomic(primary.as_str().to_owned()),
-            Rule::TRUE => LTLFormulae::True,
+            Rule::AP => Ok(LTLFormulae::Atomic(primary.as_str().to_owned())),
+            Rule::TRUE => Ok(LTLFormulae::True),
             Rule::formula => parse_expr(primary.into_inner()),
             rule => unreachable!("Expr::parse expected atom, found {:?}", rule),
         })
-        .map_infix(|lhs, op, rhs| match op.as_rule() {
-            Rule::And => LTLFormulae::And(Box::new(lhs), Box::new(rhs)),
-            Rule::Or => LTLFormulae::And(
-                Box::new(LTLFormulae::Not(Box::new(lhs))),
-                Box::new(LTLFormulae::Not(Box::new(rhs))),
-            ),
-            Rule::Implies => {
-                LTLFormulae::And(Box::new(lhs), Box::new(LTLFormulae::Not(Box::new(rhs))))
-            }
-            Rule::Until => LTLFormulae::Until(Box::new(lhs), Box::new(rhs)),
-            Rule::Release => todo!(),
-            Rule::WeakUntil => todo!(),
-            _ => unreachable!(),
+        .map_infix(|lhs, op, rhs| {
+            let (lhs, rhs) = (lhs?, rhs?);
+            Ok(match op.as_rule() {
+                Rule::And => LTLFormulae::And(Box::new(lhs), Box::new(rhs)),
+                Rule::Or => LTLFormulae::Not(Box::new(LTLFormulae::And(
+                    Box::new(LTLFormulae::Not(Box::new(lhs))),
+                    Box::new(LTLFormulae::Not(Box::new(rhs))),
+                ))),
+                Rule::Implies => LTLFormulae::Not(Box::new(LTLFormulae::And(
+                    Box::new(lhs),
+                    Box::new(LTLFormulae::Not(Box::new(rhs))),
+                ))),
+                Rule::Until => LTLFormulae::Until(Box::new(lhs), Box::new(rhs)),
+                Rule::Since => LTLFormulae::Since(Box::new(lhs), Box::new(rhs)),
+                Rule::IntervalUntil => {
+                    let (a, b) = interval(op.into_inner().map(|k| k.as_str()))?;
+                    LTLFormulae::BoundedUntil(a, b, Box::new(lhs), Box::new(rhs))
+                }
+                // f R g = !(!f U !g)
+                Rule::Release => LTLFormulae::Not(Box::new(LTLFormulae::Until(
+                    Box::new(LTLFormulae::Not(Box::new(lhs))),
+                    Box::new(LTLFormulae::Not(Box::new(rhs))),
+                ))),
+                // f W g = !(!g U (!f && !g))
+                Rule::WeakUntil => LTLFormulae::Not(Box::new(LTLFormulae::Until(
+                    Box::new(LTLFormulae::Not(Box::new(rhs.clone()))),
+                    Box::new(LTLFormulae::And(
+                        Box::new(LTLFormulae::Not(Box::new(lhs))),
+                        Box::new(LTLFormulae::Not(Box::new(rhs))),
+                    )),
+                ))),
+                _ => unreachable!(),
+            })
         })
-        .map_prefix(|op, rhs| match op.as_rule() {
-            Rule::Globally => LTLFormulae::Not(Box::new(LTLFormulae::Until(
-                Box::new(LTLFormulae::True),
-                Box::new(LTLFormulae::Not(Box::new(rhs))),
-            ))),
-            Rule::Eventually => LTLFormulae::Until(Box::new(LTLFormulae::True), Box::new(rhs)),
-            Rule::Next => LTLFormulae::Next(Box::new(rhs)),
-            Rule::Not => LTLFormulae::Not(Box::new(rhs)),
-            _ => unreachable!(),
+        .map_prefix(|op, rhs| {
+            let rhs = rhs?;
+            Ok(match op.as_rule() {
+                Rule::Globally => LTLFormulae::Not(Box::new(LTLFormulae::Until(
+                    Box::new(LTLFormulae::True),
+                    Box::new(LTLFormulae::Not(Box::new(rhs))),
+                ))),
+                Rule::Eventually => LTLFormulae::Until(Box::new(LTLFormulae::True), Box::new(rhs)),
+                Rule::IntervalFinally => {
+                    let (a, b) = interval(op.into_inner().map(|k| k.as_str()))?;
+                    LTLFormulae::BoundedUntil(a, b, Box::new(LTLFormulae::True), Box::new(rhs))
+                }
+                // G[a,b] f = !(true U[a,b] !f)
+                Rule::IntervalGlobally => {
+                    let (a, b) = interval(op.into_inner().map(|k| k.as_str()))?;
+                    LTLFormulae::Not(Box::new(LTLFormulae::BoundedUntil(
+                        a,
+                        b,
+                        Box::new(LTLFormulae::True),
+                        Box::new(LTLFormulae::Not(Box::new(rhs))),
+                    )))
+                }
+                Rule::Next => LTLFormulae::Next(Box::new(rhs)),
+                Rule::Yesterday => LTLFormulae::Yesterday(Box::new(rhs)),
+                Rule::Once => LTLFormulae::Since(Box::new(LTLFormulae::True), Box::new(rhs)),
+                // H f = !O !f
+                Rule::Historically => LTLFormulae::Not(Box::new(LTLFormulae::Since(
+                    Box::new(LTLFormulae::True),
+                    Box::new(LTLFormulae::Not(Box::new(rhs))),
+                ))),
+                Rule::Not => LTLFormulae::Not(Box::new(rhs)),
+                _ => unreachable!(),
+            })
         })
         .parse(pairs)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_derived_operators() {
+        let parse = |formula: &str| parse_ltl(formula).unwrap().to_string();
+        assert_eq!(parse("p || q"), "¬(¬p ∧ ¬q)");
+        assert_eq!(parse("p -> q"), "¬(p ∧ ¬q)");
+        assert_eq!(parse("p R q"), "¬(¬p U ¬q)");
+        assert_eq!(parse("p W q"), "¬(¬q U (¬p ∧ ¬q))");
+    }
+
+    #[test]
+    fn test_intervals() {
+        assert!(parse_ltl("F[2,2] p && p U[0,3] q").is_ok());
+        let message = |formula: &str| match parse_ltl(formula).unwrap_err().variant {
+            ErrorVariant::CustomError { message } => message,
+            variant => panic!("{:?}", variant),
+        };
+        assert_eq!(message("G (r -> F[5,2] g)"), "Empty interval [5,2]");
+        assert_eq!(
+            message("p U[0,99999999999999999999999] q"),
+            "Bound 99999999999999999999999 is too large"
+        );
+    }
+}
@@ -0,0 +1,102 @@
+use once_cell::sync::OnceCell;
+use pest::{iterators::Pairs, pratt_parser::PrattParser, Parser};
+
+use crate::mu_calculus::MuFormulae;
+
+#[derive(pest_derive::Parser)]
+#[grammar = "parser/mu.pest"]
+pub struct MuParser;
+
+fn mu_parser() -> &'static PrattParser<Rule> {
+    static INSTANCE: OnceCell<PrattParser<Rule>> = OnceCell::new();
+    INSTANCE.get_or_init(|| {
+        use pest::pratt_parser::{Assoc::*, Op};
+        use Rule::*;
+
+        // Precedence is defined lowest to highest
+        PrattParser::new()
+            .op(Op::infix(And, Left) | Op::infix(Or, Left) | Op::infix(Implies, Left))
+            .op(Op::prefix(Not) | Op::prefix(Diamond) | Op::prefix(Square))
+    })
+}
+
+/// Parse a mu-calculus formula, such as `nu X. p && [] [] X`. Variables
+/// start with an uppercase letter, and the body of a fixpoint extends as
+/// far right as possible.
+pub fn parse_mu(input: &str) -> Result<MuFormulae, Box<pest::error::Error<Rule>>> {
+    match MuParser::parse(Rule::formulae, input) {
+        Ok(mut pairs) => {
+            let formula = pairs.next().unwrap().into_inner().next().unwrap();
+            Ok(parse_expr(formula.into_inner()))
+        }
+        Err(e) => {
+            eprintln!("Parse failed: {:?}", e);
+            Err(Box::new(e))
+        }
+    }
+}
+
+fn parse_expr(pairs: Pairs<Rule>) -> MuFormulae {
+    mu_parser()
+        .map_primary(|primary| match primary.as_rule() {
+            Rule::AP => MuFormulae::Atomic(primary.as_str().to_owned()),
+            Rule::VAR => MuFormulae::Variable(primary.as_str().to_owned()),
+            Rule::TRUE => MuFormulae::True,
+            Rule::FALSE => MuFormulae::False,
+            Rule::formula => parse_expr(primary.into_inner()),
+            Rule::fixpoint => {
+                let mut inner = primary.into_inner();
+                let kind = inner.next().unwrap().as_rule();
+                let x = inner.next().unwrap().as_str().to_owned();
+                let body = Box::new(parse_expr(inner.next().unwrap().into_inner()));
+                match kind {
+                    Rule::Mu => MuFormulae::Mu(x, body),
+                    _ => MuFormulae::Nu(x, body),
+                }
+            }
+            rule => unreachable!("Expr::parse expected atom, found {:?}", rule),
+        })
+        .map_infix(|lhs, op, rhs| match op.as_rule() {
+            Rule::And => MuFormulae::And(Box::new(lhs), Box::new(rhs)),
+            Rule::Or => MuFormulae::Or(Box::new(lhs), Box::new(rhs)),
+            Rule::Implies => {
+                MuFormulae::Or(Box::new(MuFormulae::Not(Box::new(lhs))), Box::new(rhs))
+            }
+            _ => unreachable!(),
+        })
+        .map_prefix(|op, rhs| match op.as_rule() {
+            Rule::Not => MuFormulae::Not(Box::new(rhs)),
+            Rule::Diamond => MuFormulae::Diamond(Box::new(rhs)),
+            Rule::Square => MuFormulae::Square(Box::new(rhs)),
+            _ => unreachable!(),
+        })
+        .parse(pairs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mu_calculus::MuError;
+
+    #[test]
+    fn test_parse_mu() {
+        let f = parse_mu("mu X. p || <> X").unwrap();
+        assert_eq!(f.to_string(), "μX.(p ∨ <>X)");
+        let f = parse_mu("nu X. p && [] [] X").unwrap();
+        assert_eq!(f.to_string(), "νX.(p ∧ [][]X)");
+        let f = parse_mu("!q -> (nu Y. mu Z. (mutex && <>Y) || <>Z)").unwrap();
+        assert_eq!(f.to_string(), "(¬¬q ∨ νY.μZ.((mutex ∧ <>Y) ∨ <>Z))");
+        assert!(f.validate().is_ok());
+
+        assert_eq!(
+            parse_mu("<>X").unwrap().validate(),
+            Err(MuError::Unbound("X".to_string()))
+        );
+        assert_eq!(
+            parse_mu("mu X. p || !<>X").unwrap().validate(),
+            Err(MuError::NotMonotone("X".to_string()))
+        );
+        assert!(parse_mu("mu X. !(p && !<>X)").unwrap().validate().is_ok());
+        assert!(parse_mu("mu x. p").is_err());
+    }
+}
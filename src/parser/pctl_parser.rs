@@ -0,0 +1,232 @@
+use pest::{
+    error::{Error, ErrorVariant},
+    iterators::{Pair, Pairs},
+    Parser, Position,
+};
+
+use crate::{
+    ctl::CTLFormulae,
+    parser::{
+        ctl_parser::{ctl_parser, infix, prefix, CTLParser, Rule},
+        steps,
+    },
+    pctl::{Bound, PCTLFormulae, PathFormulae, RewardFormulae},
+};
+
+/// A formula while parsing, which can still be a path formula.
+enum Node {
+    State(PCTLFormulae),
+    Path(PathFormulae),
+}
+
+/// Parse a PCTL formula, such as `P>=0.9 [F<=10 done] && !fail`.
+///
+/// Path quantifiers and temporal operators are read as CTL when they only
+/// apply to formulae without probabilistic operators.
+pub fn parse_pctl(input: &str) -> Result<PCTLFormulae, Box<Error<Rule>>> {
+    let error = |message: &str| {
+        Box::new(Error::new_from_pos(
+            ErrorVariant::CustomError {
+                message: message.to_string(),
+            },
+            Position::from_start(input),
+        ))
+    };
+    let mut pairs = match CTLParser::parse(Rule::pctl_formulae, input) {
+        Ok(pairs) => pairs,
+        Err(e) => {
+            eprintln!("Parse failed: {:?}", e);
+            return Err(Box::new(e));
+        }
+    };
+    let formula = pairs.next().unwrap().into_inner().next().unwrap();
+    match parse_expr(formula.into_inner()).map_err(|m| error(&m))? {
+        Node::State(f) if f.has_nested_query() => {
+            Err(error("Queries are only allowed at the top level"))
+        }
+        Node::State(f) => Ok(f),
+        Node::Path(_) => Err(error("Path formulae must be inside a P operator")),
+    }
+}
+
+fn state(node: Node) -> Result<PCTLFormulae, String> {
+    match node {
+        Node::State(f) => Ok(f),
+        Node::Path(p) => Err(format!("{} is not a state formula", p)),
+    }
+}
+
+/// The path formula read as CTL, without its path quantifier.
+fn path(node: Node) -> Result<PathFormulae, String> {
+    let ctl = |f: Box<CTLFormulae>| PCTLFormulae::Ctl(*f);
+    match node {
+        Node::Path(p) => Ok(p),
+        Node::State(PCTLFormulae::Ctl(f)) => match f {
+            CTLFormulae::Next(f) => Ok(PathFormulae::Next(ctl(f))),
+            CTLFormulae::Finally(f) => Ok(PathFormulae::Until(
+                PCTLFormulae::Ctl(CTLFormulae::True),
+                ctl(f),
+                None,
+            )),
+            CTLFormulae::Globally(f) => Ok(PathFormulae::Globally(ctl(f), None)),
+            CTLFormulae::Until(f, g) => Ok(PathFormulae::Until(ctl(f), ctl(g), None)),
+            f => Err(format!("{} is not a path formula", f)),
+        },
+        Node::State(f) => Err(format!("{} is not a path formula", f)),
+    }
+}
+
+/// The bound on the steps of `op`, if it has one.
+fn bounded(op: Pair<Rule>) -> Result<Option<usize>, String> {
+    op.into_inner()
+        .next()
+        .map(|k| steps(k.as_str()))
+        .transpose()
+}
+
+fn bound(pairs: &mut Pairs<Rule>) -> Bound {
+    let first = pairs.next().unwrap();
+    match (first.as_rule(), first.as_str()) {
+        (Rule::Query, _) => return Bound::Query,
+        (Rule::Optimum, optimum) => {
+            pairs.next();
+            return if optimum == "max" {
+                Bound::Max
+            } else {
+                Bound::Min
+            };
+        }
+        _ => {}
+    }
+    let p = pairs.next().unwrap().as_str().parse().unwrap();
+    match first.as_str() {
+        "<" => Bound::Less(p),
+        "<=" => Bound::LessEq(p),
+        ">" => Bound::Greater(p),
+        _ => Bound::GreaterEq(p),
+    }
+}
+
+fn parse_expr(pairs: Pairs<Rule>) -> Result<Node, String> {
+    ctl_parser()
+        .map_primary(|primary| match primary.as_rule() {
+            Rule::AP => Ok(Node::State(PCTLFormulae::Ctl(CTLFormulae::Atomic(
+                primary.as_str().to_owned(),
+            )))),
+            Rule::TRUE => Ok(Node::State(PCTLFormulae::Ctl(CTLFormulae::True))),
+            Rule::pctl_formula => parse_expr(primary.into_inner()),
+            Rule::Probability | Rule::SteadyState => {
+                let rule = primary.as_rule();
+                let mut inner = primary.into_inner();
+                let bound = bound(&mut inner);
+                let operand = parse_expr(inner.next().unwrap().into_inner())?;
+                Ok(Node::State(if rule == Rule::Probability {
+                    PCTLFormulae::Probability(bound, Box::new(path(operand)?))
+                } else {
+                    PCTLFormulae::SteadyState(bound, Box::new(state(operand)?))
+                }))
+            }
+            Rule::Reward => {
+                let mut inner = primary.into_inner();
+                let bound = bound(&mut inner);
+                let operand = inner.next().unwrap();
+                let rewards = if operand.as_rule() == Rule::Cumulative {
+                    RewardFormulae::Cumulative(bounded(operand)?.unwrap())
+                } else {
+                    match path(parse_expr(operand.into_inner())?)? {
+                        PathFormulae::Until(PCTLFormulae::Ctl(CTLFormulae::True), f, None) => {
+                            RewardFormulae::Reachability(f)
+                        }
+                        p => return Err(format!("R cannot measure {}", p)),
+                    }
+                };
+                Ok(Node::State(PCTLFormulae::Reward(bound, Box::new(rewards))))
+            }
+            rule => unreachable!("Expr::parse expected atom, found {:?}", rule),
+        })
+        .map_infix(|lhs, op, rhs| {
+            let (lhs, rhs) = (state(lhs?)?, state(rhs?)?);
+            let (lhs, rhs) = match (lhs, rhs, op.as_rule()) {
+                (lhs, rhs, Rule::BoundedUntil) => {
+                    return Ok(Node::Path(PathFormulae::Until(lhs, rhs, bounded(op)?)))
+                }
+                (PCTLFormulae::Ctl(lhs), PCTLFormulae::Ctl(rhs), _) => {
+                    return Ok(Node::State(PCTLFormulae::Ctl(infix(lhs, op, rhs)?)))
+                }
+                (lhs, rhs, _) => (Box::new(lhs), Box::new(rhs)),
+            };
+            match op.as_rule() {
+                Rule::And => Ok(Node::State(PCTLFormulae::And(lhs, rhs))),
+                Rule::Or => Ok(Node::State(PCTLFormulae::Or(lhs, rhs))),
+                Rule::Implies => Ok(Node::State(PCTLFormulae::Or(
+                    Box::new(PCTLFormulae::Not(lhs)),
+                    rhs,
+                ))),
+                Rule::Until => Ok(Node::Path(PathFormulae::Until(*lhs, *rhs, None))),
+                _ => Err(format!(
+                    "{} cannot apply to probabilistic formulae",
+                    op.as_str()
+                )),
+            }
+        })
+        .map_prefix(|op, rhs| {
+            let rhs = state(rhs?)?;
+            match (op.as_rule(), rhs) {
+                (Rule::BoundedFinally, f) => Ok(Node::Path(PathFormulae::Until(
+                    PCTLFormulae::Ctl(CTLFormulae::True),
+                    f,
+                    bounded(op)?,
+                ))),
+                (Rule::BoundedGlobally, f) => {
+                    Ok(Node::Path(PathFormulae::Globally(f, bounded(op)?)))
+                }
+                (_, PCTLFormulae::Ctl(f)) => Ok(Node::State(PCTLFormulae::Ctl(prefix(op, f)?))),
+                (Rule::Not, f) => Ok(Node::State(PCTLFormulae::Not(Box::new(f)))),
+                (Rule::Next, f) => Ok(Node::Path(PathFormulae::Next(f))),
+                (Rule::Finally, f) => Ok(Node::Path(PathFormulae::Until(
+                    PCTLFormulae::Ctl(CTLFormulae::True),
+                    f,
+                    None,
+                ))),
+                (Rule::Globally, f) => Ok(Node::Path(PathFormulae::Globally(f, None))),
+                _ => Err(format!(
+                    "{} cannot apply to probabilistic formulae",
+                    op.as_str()
+                )),
+            }
+        })
+        .parse(pairs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_pctl() {
+        let f = parse_pctl("P>=0.9 [F<=10 done] && !fail").unwrap();
+        assert_eq!(f.to_string(), "(P>=0.9 [true U<=10 done] ∧ ¬fail)");
+        let f = parse_pctl("P=? [try U (P>0.5 [X ok])]").unwrap();
+        assert!(f.is_query());
+        assert_eq!(f.to_string(), "P=? [try U P>0.5 [Xok]]");
+        let f = parse_pctl("Pmin=? [G<=5 safe]").unwrap();
+        assert!(f.is_query());
+        assert_eq!(f.to_string(), "Pmin=? [G<=5 safe]");
+        let f = parse_pctl("R<=2.5 [F delivered] && Rmax=? [C<=10]").unwrap_err();
+        assert!(f.to_string().contains("top level"));
+        let f = parse_pctl("R<=2.5 [F P>0.5 [X done]]").unwrap();
+        assert_eq!(f.to_string(), "R<=2.5 [F P>0.5 [Xdone]]");
+        let f = parse_pctl("Rmax=? [C<=10]").unwrap();
+        assert!(f.is_query());
+        let f = parse_pctl("S<0.1 [down] -> AG up").unwrap();
+        assert_eq!(f.to_string(), "(¬S<0.1 [down] ∨ AGup)");
+
+        assert!(parse_pctl("P>=0.5 [a]").is_err());
+        assert!(parse_pctl("Pmax>=0.5 [F a]").is_err());
+        assert!(parse_pctl("F<=3 a").is_err());
+        assert!(parse_pctl("R=? [G a]").is_err());
+        assert!(parse_pctl("EF P>0.5 [X a]").is_err());
+        assert!(parse_pctl("P>=0.5 [X P=? [F a]]").is_err());
+        assert!(parse_pctl("P>=0.5 [F<=99999999999999999999999 a]").is_err());
+    }
+}
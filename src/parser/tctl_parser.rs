@@ -0,0 +1,101 @@
+use pest::{
+    error::{Error, ErrorVariant},
+    iterators::{Pair, Pairs},
+    Parser, Position,
+};
+
+use crate::{
+    model::timed::dbm::Bound,
+    parser::ctl_parser::{ctl_parser, CTLParser, Rule},
+    tctl::{TCTLFormulae, TimeBound},
+};
+
+/// Parse a TCTL formula, such as `AG (req -> AF<=5 ack)`.
+pub fn parse_tctl(input: &str) -> Result<TCTLFormulae, Box<Error<Rule>>> {
+    let mut pairs = match CTLParser::parse(Rule::tctl_formulae, input) {
+        Ok(pairs) => pairs,
+        Err(e) => {
+            eprintln!("Parse failed: {:?}", e);
+            return Err(Box::new(e));
+        }
+    };
+    let formula = pairs.next().unwrap().into_inner().next().unwrap();
+    parse_expr(formula.into_inner()).map_err(|message| {
+        Box::new(Error::new_from_pos(
+            ErrorVariant::CustomError { message },
+            Position::from_start(input),
+        ))
+    })
+}
+
+/// The bound of a timed operator, failing when its constant is too large
+/// for the zones.
+fn time_bound(op: Pair<Rule>) -> Result<TimeBound, String> {
+    let mut inner = op.into_inner();
+    let comparison = inner.next().unwrap().as_str();
+    let constant = inner.next().unwrap().as_str();
+    let c = constant
+        .parse()
+        .ok()
+        .filter(|c| *c <= Bound::MAX_CONSTANT)
+        .ok_or_else(|| format!("Bound {} is too large", constant))?;
+    Ok(match comparison {
+        "<" => TimeBound::Less(c),
+        "<=" => TimeBound::LessEq(c),
+        ">" => TimeBound::Greater(c),
+        _ => TimeBound::GreaterEq(c),
+    })
+}
+
+fn parse_expr(pairs: Pairs<Rule>) -> Result<TCTLFormulae, String> {
+    ctl_parser()
+        .map_primary(|primary| match primary.as_rule() {
+            Rule::AP => Ok(TCTLFormulae::Atomic(primary.as_str().to_owned())),
+            Rule::TRUE => Ok(TCTLFormulae::True),
+            Rule::tctl_formula => parse_expr(primary.into_inner()),
+            rule => unreachable!("Expr::parse expected atom, found {:?}", rule),
+        })
+        .map_infix(|lhs, op, rhs| {
+            let (lhs, rhs) = (Box::new(lhs?), Box::new(rhs?));
+            match op.as_rule() {
+                Rule::And => Ok(TCTLFormulae::And(lhs, rhs)),
+                Rule::Or => Ok(TCTLFormulae::Or(lhs, rhs)),
+                Rule::Implies => Ok(TCTLFormulae::Or(Box::new(TCTLFormulae::Not(lhs)), rhs)),
+                _ => Err(format!("{} is not supported in TCTL", op.as_str())),
+            }
+        })
+        .map_prefix(|op, rhs| {
+            let rhs = Box::new(rhs?);
+            match op.as_rule() {
+                Rule::Not => Ok(TCTLFormulae::Not(rhs)),
+                Rule::All => Ok(TCTLFormulae::All(rhs)),
+                Rule::Exists => Ok(TCTLFormulae::Exist(rhs)),
+                Rule::Finally => Ok(TCTLFormulae::Finally(rhs, None)),
+                Rule::Globally => Ok(TCTLFormulae::Globally(rhs, None)),
+                Rule::TimedFinally => Ok(TCTLFormulae::Finally(rhs, Some(time_bound(op)?))),
+                Rule::TimedGlobally => Ok(TCTLFormulae::Globally(rhs, Some(time_bound(op)?))),
+                _ => Err(format!("{} is not defined in dense time", op.as_str())),
+            }
+        })
+        .parse(pairs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_tctl() {
+        let f = parse_tctl("AG (req -> AF<=5 ack)").unwrap();
+        assert_eq!(f.to_string(), "AG(¬req ∨ AF<=5 ack)");
+        let f = parse_tctl("EF>3 (idle && !busy)").unwrap();
+        assert_eq!(f.to_string(), "EF>3 (idle ∧ ¬busy)");
+        assert!(!f.is_propositional());
+
+        assert!(parse_tctl("AX ack").is_err());
+        assert!(parse_tctl("A (req U ack)").is_err());
+        assert!(parse_tctl("AF<=x ack").is_err());
+        assert!(parse_tctl("AF<=99999999999999999999 ack").is_err());
+        assert!(parse_tctl("AF<=9223372036854775807 ack").is_err());
+    }
+}
@@ -0,0 +1,154 @@
+use std::fmt::Display;
+
+use crate::ctl::CTLFormulae;
+
+/// The bound of a probabilistic operator, or a query for the probability.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Bound {
+    Query,
+    /// A query for the maximal probability over the schedulers of a
+    /// decision process.
+    Max,
+    /// A query for the minimal probability.
+    Min,
+    Less(f64),
+    LessEq(f64),
+    Greater(f64),
+    GreaterEq(f64),
+}
+
+impl Bound {
+    pub fn is_query(&self) -> bool {
+        matches!(self, Bound::Query | Bound::Max | Bound::Min)
+    }
+
+    /// Whether `probability` is within the bound.
+    ///
+    /// Panics for a query, which has no truth value.
+    pub fn holds(&self, probability: f64) -> bool {
+        match self {
+            Bound::Query | Bound::Max | Bound::Min => panic!("A query has no truth value"),
+            Bound::Less(p) => probability < *p,
+            Bound::LessEq(p) => probability <= *p,
+            Bound::Greater(p) => probability > *p,
+            Bound::GreaterEq(p) => probability >= *p,
+        }
+    }
+}
+
+impl Display for Bound {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Bound::Query => write!(formatter, "=?"),
+            Bound::Max => write!(formatter, "max=?"),
+            Bound::Min => write!(formatter, "min=?"),
+            Bound::Less(p) => write!(formatter, "<{}", p),
+            Bound::LessEq(p) => write!(formatter, "<={}", p),
+            Bound::Greater(p) => write!(formatter, ">{}", p),
+            Bound::GreaterEq(p) => write!(formatter, ">={}", p),
+        }
+    }
+}
+
+/// The path formulae measured by the probabilistic operator.
+#[derive(Debug, Clone)]
+pub enum PathFormulae {
+    Next(PCTLFormulae),
+    /// `f U g`, or `f U<=k g` when `g` must hold within `k` steps.
+    Until(PCTLFormulae, PCTLFormulae, Option<usize>),
+    /// `G f`, or `G<=k f` when `f` must only hold for `k` steps.
+    Globally(PCTLFormulae, Option<usize>),
+}
+
+/// The rewards measured by the reward operator.
+#[derive(Debug, Clone)]
+pub enum RewardFormulae {
+    /// `F f`, the reward accumulated until reaching a state satisfying `f`.
+    Reachability(PCTLFormulae),
+    /// `C<=k`, the reward accumulated in the first `k` steps.
+    Cumulative(usize),
+}
+
+/// PCTL state formulae.
+///
+/// The parts without probabilistic operators are CTL formulae, checked as
+/// such on the underlying Kripke structure.
+#[derive(Debug, Clone)]
+pub enum PCTLFormulae {
+    Ctl(CTLFormulae),
+    Not(Box<PCTLFormulae>),
+    And(Box<PCTLFormulae>, Box<PCTLFormulae>),
+    Or(Box<PCTLFormulae>, Box<PCTLFormulae>),
+    /// `P⋈p [path]`, the probability of the paths satisfying `path`.
+    Probability(Bound, Box<PathFormulae>),
+    /// `S⋈p [f]`, the long-run probability of being in a state satisfying `f`.
+    SteadyState(Bound, Box<PCTLFormulae>),
+    /// `R⋈r [rewards]`, the expected reward.
+    Reward(Bound, Box<RewardFormulae>),
+}
+
+impl PCTLFormulae {
+    /// Whether the formula is a query for a probability, such as
+    /// `P=? [...]` or `R=? [...]`.
+    pub fn is_query(&self) -> bool {
+        match self {
+            PCTLFormulae::Probability(bound, _)
+            | PCTLFormulae::SteadyState(bound, _)
+            | PCTLFormulae::Reward(bound, _) => bound.is_query(),
+            _ => false,
+        }
+    }
+
+    /// Whether there is a query strictly inside the formula.
+    pub fn has_nested_query(&self) -> bool {
+        let inner = |f: &PCTLFormulae| f.is_query() || f.has_nested_query();
+        match self {
+            PCTLFormulae::Ctl(_) => false,
+            PCTLFormulae::Not(f) | PCTLFormulae::SteadyState(_, f) => inner(f),
+            PCTLFormulae::And(f, g) | PCTLFormulae::Or(f, g) => inner(f) || inner(g),
+            PCTLFormulae::Probability(_, path) => match &**path {
+                PathFormulae::Next(f) | PathFormulae::Globally(f, _) => inner(f),
+                PathFormulae::Until(f, g, _) => inner(f) || inner(g),
+            },
+            PCTLFormulae::Reward(_, rewards) => match &**rewards {
+                RewardFormulae::Reachability(f) => inner(f),
+                RewardFormulae::Cumulative(_) => false,
+            },
+        }
+    }
+}
+
+impl Display for PathFormulae {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PathFormulae::Next(f) => write!(formatter, "X{}", f),
+            PathFormulae::Until(f, g, None) => write!(formatter, "{} U {}", f, g),
+            PathFormulae::Until(f, g, Some(k)) => write!(formatter, "{} U<={} {}", f, k, g),
+            PathFormulae::Globally(f, None) => write!(formatter, "G{}", f),
+            PathFormulae::Globally(f, Some(k)) => write!(formatter, "G<={} {}", k, f),
+        }
+    }
+}
+
+impl Display for RewardFormulae {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RewardFormulae::Reachability(f) => write!(formatter, "F {}", f),
+            RewardFormulae::Cumulative(k) => write!(formatter, "C<={}", k),
+        }
+    }
+}
+
+impl Display for PCTLFormulae {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PCTLFormulae::Ctl(f) => write!(formatter, "{}", f),
+            PCTLFormulae::Not(f) => write!(formatter, "¬{}", f),
+            PCTLFormulae::And(f, g) => write!(formatter, "({} ∧ {})", f, g),
+            PCTLFormulae::Or(f, g) => write!(formatter, "({} ∨ {})", f, g),
+            PCTLFormulae::Probability(bound, path) => write!(formatter, "P{} [{}]", bound, path),
+            PCTLFormulae::SteadyState(bound, f) => write!(formatter, "S{} [{}]", bound, f),
+            PCTLFormulae::Reward(bound, rewards) => write!(formatter, "R{} [{}]", bound, rewards),
+        }
+    }
+}
@@ -0,0 +1,433 @@
+use std::ops::Not;
+
+/// A literal, a boolean variable or its negation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Lit(u32);
+
+impl Lit {
+    pub fn new(var: usize, positive: bool) -> Lit {
+        Lit((var as u32) << 1 | !positive as u32)
+    }
+
+    pub fn var(self) -> usize {
+        (self.0 >> 1) as usize
+    }
+
+    pub fn is_positive(self) -> bool {
+        self.0 & 1 == 0
+    }
+
+    fn index(self) -> usize {
+        self.0 as usize
+    }
+}
+
+impl Not for Lit {
+    type Output = Lit;
+
+    fn not(self) -> Lit {
+        Lit(self.0 ^ 1)
+    }
+}
+
+/// A CDCL SAT solver.
+///
+/// Clauses are watched by two literals, conflicts are analyzed to the first
+/// unique implication point, and decisions follow the activity of the
+/// variables in recent conflicts. Clauses can be added between calls to
+/// [`Solver::solve`], which takes assumptions, so the solver can be used
+/// incrementally.
+#[derive(Debug, Default)]
+pub struct Solver {
+    clauses: Vec<Vec<Lit>>,
+    /// The clauses watching each literal, visited when it becomes false.
+    watches: Vec<Vec<usize>>,
+    assigns: Vec<Option<bool>>,
+    level: Vec<usize>,
+    reason: Vec<Option<usize>>,
+    trail: Vec<Lit>,
+    trail_lim: Vec<usize>,
+    propagated: usize,
+    activity: Vec<f64>,
+    increment: f64,
+    phase: Vec<bool>,
+    /// Whether the clauses are unsatisfiable without assumptions.
+    unsat: bool,
+    model: Vec<bool>,
+}
+
+impl Solver {
+    pub fn new() -> Solver {
+        Solver {
+            increment: 1.0,
+            ..Solver::default()
+        }
+    }
+
+    pub fn new_var(&mut self) -> Lit {
+        let var = self.assigns.len();
+        self.watches.push(Vec::new());
+        self.watches.push(Vec::new());
+        self.assigns.push(None);
+        self.level.push(0);
+        self.reason.push(None);
+        self.activity.push(0.0);
+        self.phase.push(false);
+        self.model.push(false);
+        Lit::new(var, true)
+    }
+
+    pub fn num_vars(&self) -> usize {
+        self.assigns.len()
+    }
+
+    fn value(&self, lit: Lit) -> Option<bool> {
+        self.assigns[lit.var()].map(|value| value == lit.is_positive())
+    }
+
+    fn decision_level(&self) -> usize {
+        self.trail_lim.len()
+    }
+
+    /// Add a clause, returning false if the clauses became unsatisfiable.
+    pub fn add_clause(&mut self, lits: &[Lit]) -> bool {
+        if self.unsat {
+            return false;
+        }
+
+        let mut clause = lits.to_vec();
+        clause.sort();
+        clause.dedup();
+        if clause.windows(2).any(|w| w[0] == !w[1])
+            || clause.iter().any(|l| self.value(*l) == Some(true))
+        {
+            return true;
+        }
+        clause.retain(|l| self.value(*l).is_none());
+
+        match clause.len() {
+            0 => self.unsat = true,
+            1 => {
+                self.enqueue(clause[0], None);
+                if self.propagate().is_some() {
+                    self.unsat = true;
+                }
+            }
+            _ => {
+                self.attach(clause);
+            }
+        }
+        !self.unsat
+    }
+
+    fn attach(&mut self, clause: Vec<Lit>) -> usize {
+        let index = self.clauses.len();
+        self.watches[clause[0].index()].push(index);
+        self.watches[clause[1].index()].push(index);
+        self.clauses.push(clause);
+        index
+    }
+
+    fn enqueue(&mut self, lit: Lit, reason: Option<usize>) {
+        let var = lit.var();
+        self.assigns[var] = Some(lit.is_positive());
+        self.level[var] = self.decision_level();
+        self.reason[var] = reason;
+        self.trail.push(lit);
+    }
+
+    /// Propagate the assignments of the trail, returning a conflicting
+    /// clause if there is one.
+    fn propagate(&mut self) -> Option<usize> {
+        while self.propagated < self.trail.len() {
+            let falsified = !self.trail[self.propagated];
+            self.propagated += 1;
+
+            let watching = std::mem::take(&mut self.watches[falsified.index()]);
+            let mut kept = Vec::with_capacity(watching.len());
+            let mut conflict = None;
+            for (i, index) in watching.iter().enumerate() {
+                if conflict.is_some() {
+                    kept.extend(&watching[i..]);
+                    break;
+                }
+
+                let clause = &mut self.clauses[*index];
+                if clause[0] == falsified {
+                    clause.swap(0, 1);
+                }
+                let first = clause[0];
+                if self.assigns[first.var()].map(|v| v == first.is_positive()) == Some(true) {
+                    kept.push(*index);
+                    continue;
+                }
+
+                let replacement = (2..clause.len()).find(|k| {
+                    let lit = clause[*k];
+                    self.assigns[lit.var()].map(|v| v == lit.is_positive()) != Some(false)
+                });
+                match replacement {
+                    Some(k) => {
+                        clause.swap(1, k);
+                        let watch = clause[1];
+                        self.watches[watch.index()].push(*index);
+                    }
+                    None => {
+                        kept.push(*index);
+                        if self.value(first) == Some(false) {
+                            conflict = Some(*index);
+                        } else {
+                            self.enqueue(first, Some(*index));
+                        }
+                    }
+                }
+            }
+            self.watches[falsified.index()] = kept;
+            if conflict.is_some() {
+                return conflict;
+            }
+        }
+        None
+    }
+
+    /// Learn a clause from a conflict, which is asserting at the returned
+    /// level.
+    fn analyze(&mut self, mut conflict: usize) -> (Vec<Lit>, usize) {
+        let mut seen = vec![false; self.num_vars()];
+        let mut learnt = vec![Lit(0)];
+        let mut pending = 0;
+        let mut index = self.trail.len();
+        let mut implied = None;
+
+        loop {
+            let skip = if implied.is_some() { 1 } else { 0 };
+            for k in skip..self.clauses[conflict].len() {
+                let lit = self.clauses[conflict][k];
+                let var = lit.var();
+                if !seen[var] && self.level[var] > 0 {
+                    seen[var] = true;
+                    self.bump(var);
+                    if self.level[var] == self.decision_level() {
+                        pending += 1;
+                    } else {
+                        learnt.push(lit);
+                    }
+                }
+            }
+
+            loop {
+                index -= 1;
+                if seen[self.trail[index].var()] {
+                    break;
+                }
+            }
+            let lit = self.trail[index];
+            seen[lit.var()] = false;
+            pending -= 1;
+            implied = Some(lit);
+            if pending == 0 {
+                break;
+            }
+            conflict = self.reason[lit.var()].unwrap();
+        }
+        learnt[0] = !implied.unwrap();
+
+        let mut level = 0;
+        if learnt.len() > 1 {
+            let max = (1..learnt.len())
+                .max_by_key(|k| self.level[learnt[*k].var()])
+                .unwrap();
+            learnt.swap(1, max);
+            level = self.level[learnt[1].var()];
+        }
+        self.increment /= 0.95;
+        (learnt, level)
+    }
+
+    fn bump(&mut self, var: usize) {
+        self.activity[var] += self.increment;
+        if self.activity[var] > 1e100 {
+            for activity in &mut self.activity {
+                *activity *= 1e-100;
+            }
+            self.increment *= 1e-100;
+        }
+    }
+
+    fn backtrack(&mut self, level: usize) {
+        if self.decision_level() > level {
+            let start = self.trail_lim[level];
+            for lit in self.trail.drain(start..) {
+                self.assigns[lit.var()] = None;
+                self.phase[lit.var()] = lit.is_positive();
+            }
+            self.trail_lim.truncate(level);
+            self.propagated = self.trail.len();
+        }
+    }
+
+    fn pick(&self) -> Option<Lit> {
+        (0..self.num_vars())
+            .filter(|v| self.assigns[*v].is_none())
+            .max_by(|a, b| {
+                self.activity[*a]
+                    .total_cmp(&self.activity[*b])
+                    .then(b.cmp(a))
+            })
+            .map(|v| Lit::new(v, self.phase[v]))
+    }
+
+    /// Whether the clauses are satisfiable with the `assumptions` true.
+    ///
+    /// On success, the assignment found is available with
+    /// [`Solver::model_value`].
+    pub fn solve(&mut self, assumptions: &[Lit]) -> bool {
+        if self.unsat {
+            return false;
+        }
+
+        let mut conflicts = 0;
+        let mut restart = 100;
+        let result = loop {
+            if let Some(conflict) = self.propagate() {
+                if self.decision_level() == 0 {
+                    self.unsat = true;
+                    break false;
+                }
+                conflicts += 1;
+                let (learnt, level) = self.analyze(conflict);
+                self.backtrack(level);
+                if learnt.len() == 1 {
+                    self.enqueue(learnt[0], None);
+                } else {
+                    let lit = learnt[0];
+                    let index = self.attach(learnt);
+                    self.enqueue(lit, Some(index));
+                }
+                continue;
+            }
+
+            if conflicts >= restart {
+                conflicts = 0;
+                restart += restart / 2;
+                self.backtrack(0);
+                continue;
+            }
+
+            // assumptions are decided first, one per level
+            let level = self.decision_level();
+            let decision = if level < assumptions.len() {
+                let lit = assumptions[level];
+                match self.value(lit) {
+                    Some(true) => {
+                        self.trail_lim.push(self.trail.len());
+                        continue;
+                    }
+                    Some(false) => break false,
+                    None => lit,
+                }
+            } else {
+                match self.pick() {
+                    Some(lit) => lit,
+                    None => break true,
+                }
+            };
+            self.trail_lim.push(self.trail.len());
+            self.enqueue(decision, None);
+        };
+
+        if result {
+            for (var, value) in self.assigns.iter().enumerate() {
+                self.model[var] = value.unwrap();
+            }
+        }
+        self.backtrack(0);
+        result
+    }
+
+    /// The value of `lit` in the assignment found by the last successful call
+    /// to [`Solver::solve`].
+    pub fn model_value(&self, lit: Lit) -> bool {
+        self.model[lit.var()] == lit.is_positive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pigeonhole() {
+        // 4 pigeons do not fit in 3 holes
+        let mut solver = Solver::new();
+        let holes = (0..4)
+            .map(|_| (0..3).map(|_| solver.new_var()).collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+        for pigeon in &holes {
+            solver.add_clause(pigeon);
+        }
+        for (p, first) in holes.iter().enumerate() {
+            for second in &holes[p + 1..] {
+                for (a, b) in first.iter().zip(second) {
+                    solver.add_clause(&[!*a, !*b]);
+                }
+            }
+        }
+        assert!(!solver.solve(&[]));
+    }
+
+    #[test]
+    fn test_assumptions() {
+        let mut solver = Solver::new();
+        let (a, b, c) = (solver.new_var(), solver.new_var(), solver.new_var());
+        solver.add_clause(&[!a, b]);
+        solver.add_clause(&[!b, c]);
+        assert!(solver.solve(&[a]));
+        assert!(solver.model_value(c));
+        assert!(!solver.solve(&[a, !c]));
+        // the clauses stay satisfiable
+        assert!(solver.solve(&[!c]));
+        assert!(!solver.model_value(a));
+    }
+
+    #[test]
+    fn test_random_against_brute_force() {
+        let mut seed: u64 = 1;
+        let mut random = |n: u64| {
+            seed = seed
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            (seed >> 33) % n
+        };
+        for _ in 0..200 {
+            let vars = 8;
+            let clauses = (0..34)
+                .map(|_| {
+                    (0..3)
+                        .map(|_| Lit::new(random(vars) as usize, random(2) == 0))
+                        .collect::<Vec<_>>()
+                })
+                .collect::<Vec<_>>();
+
+            let mut solver = Solver::new();
+            for _ in 0..vars {
+                solver.new_var();
+            }
+            for clause in &clauses {
+                solver.add_clause(clause);
+            }
+            let sat = solver.solve(&[]);
+            let brute = (0..1u32 << vars).any(|m| {
+                clauses
+                    .iter()
+                    .all(|c| c.iter().any(|l| (m >> l.var() & 1 == 1) == l.is_positive()))
+            });
+            assert_eq!(sat, brute);
+            if sat {
+                assert!(clauses
+                    .iter()
+                    .all(|c| c.iter().any(|l| solver.model_value(*l))));
+            }
+        }
+    }
+}
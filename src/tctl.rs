@@ -0,0 +1,93 @@
+use std::fmt::Display;
+
+/// A bound on the time for a temporal operator to hold, such as `<=5`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimeBound {
+    Less(i64),
+    LessEq(i64),
+    Greater(i64),
+    GreaterEq(i64),
+}
+
+impl TimeBound {
+    /// Whether only the times up to the bound count.
+    pub fn is_upper(&self) -> bool {
+        matches!(self, TimeBound::Less(_) | TimeBound::LessEq(_))
+    }
+
+    pub fn constant(&self) -> i64 {
+        match self {
+            TimeBound::Less(c)
+            | TimeBound::LessEq(c)
+            | TimeBound::Greater(c)
+            | TimeBound::GreaterEq(c) => *c,
+        }
+    }
+}
+
+impl Display for TimeBound {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TimeBound::Less(c) => write!(formatter, "<{}", c),
+            TimeBound::LessEq(c) => write!(formatter, "<={}", c),
+            TimeBound::Greater(c) => write!(formatter, ">{}", c),
+            TimeBound::GreaterEq(c) => write!(formatter, ">={}", c),
+        }
+    }
+}
+
+/// TCTL formulae, CTL whose temporal operators may be bounded in time.
+///
+/// The atomic propositions are the labels of the locations of timed
+/// automata.
+#[derive(Debug, Clone)]
+pub enum TCTLFormulae {
+    True,
+    Atomic(String),
+
+    Not(Box<TCTLFormulae>),
+    And(Box<TCTLFormulae>, Box<TCTLFormulae>),
+    Or(Box<TCTLFormulae>, Box<TCTLFormulae>),
+
+    All(Box<TCTLFormulae>),
+    Exist(Box<TCTLFormulae>),
+
+    /// `F f`, or `F⋈c f` when `f` must hold at a time within the bound.
+    Finally(Box<TCTLFormulae>, Option<TimeBound>),
+    /// `G f`, or `G⋈c f` when `f` must only hold at the times within the
+    /// bound.
+    Globally(Box<TCTLFormulae>, Option<TimeBound>),
+}
+
+impl TCTLFormulae {
+    /// Whether the formula has no temporal operator, so that it only depends
+    /// on the location.
+    pub fn is_propositional(&self) -> bool {
+        match self {
+            TCTLFormulae::True | TCTLFormulae::Atomic(_) => true,
+            TCTLFormulae::Not(f) => f.is_propositional(),
+            TCTLFormulae::And(f, g) | TCTLFormulae::Or(f, g) => {
+                f.is_propositional() && g.is_propositional()
+            }
+            _ => false,
+        }
+    }
+}
+
+impl Display for TCTLFormulae {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TCTLFormulae::True => write!(formatter, "true"),
+            TCTLFormulae::Atomic(s) => write!(formatter, "{}", s),
+            TCTLFormulae::Not(f) => write!(formatter, "¬{}", f),
+            TCTLFormulae::And(f, g) => write!(formatter, "({} ∧ {})", f, g),
+            TCTLFormulae::Or(f, g) => write!(formatter, "({} ∨ {})", f, g),
+            TCTLFormulae::All(f) => write!(formatter, "A{}", f),
+            TCTLFormulae::Exist(f) => write!(formatter, "E{}", f),
+            TCTLFormulae::Finally(f, None) => write!(formatter, "F{}", f),
+            TCTLFormulae::Finally(f, Some(bound)) => write!(formatter, "F{} {}", bound, f),
+            TCTLFormulae::Globally(f, None) => write!(formatter, "G{}", f),
+            TCTLFormulae::Globally(f, Some(bound)) => write!(formatter, "G{} {}", bound, f),
+        }
+    }
+}